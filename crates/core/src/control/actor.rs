@@ -37,6 +37,10 @@ pub trait Actor: Listener + ActorClone + Send {
     // このメソッドが呼ばれた際はStageRefのロックをすぐに開放することが望ましい.
     fn expire(&mut self) {}
 
+    // 不正なアクションの通知
+    // selectで返却したアクションが無効だった場合に呼ばれる. 代わりにerror.fallbackが適用される.
+    fn reject(&mut self, _error: &ActionError) {}
+
     // Actorの詳細表示用
     fn get_config(&self) -> &Config;
 
@@ -15,6 +15,12 @@ pub fn calc_seat_wind(dealer: Seat, seat: Seat) -> Tnum {
     calc_seat_offset(dealer, seat) + 1 // WE | WS | WW | WN
 }
 
+// 対局人数 (4麻: 4, 3麻: 3) 3麻の場合は座席3を空席として扱う
+#[inline]
+pub fn calc_n_seat(is_sanma: bool) -> usize {
+    if is_sanma { 3 } else { SEAT }
+}
+
 // Stage用関数
 #[inline]
 pub fn is_dealer(stg: &Stage, seat: Seat) -> bool {
@@ -28,7 +34,13 @@ pub fn get_prevalent_wind(stg: &Stage) -> Tnum {
 
 #[inline]
 pub fn get_seat_wind(stg: &Stage, seat: Seat) -> Tnum {
-    calc_seat_wind(stg.dealer, seat)
+    let n_seat = get_n_seat(stg);
+    (seat + n_seat - stg.dealer) % n_seat + 1 // 3麻の場合, 北家は存在しない
+}
+
+#[inline]
+pub fn get_n_seat(stg: &Stage) -> usize {
+    calc_n_seat(stg.rule.is_sanma)
 }
 
 pub fn get_scores(stg: &Stage) -> [Score; SEAT] {
@@ -47,6 +59,44 @@ pub fn get_names(stg: &Stage) -> [String; SEAT] {
     names.map(|name| name.into())
 }
 
+// 流局時に公開する手牌 (荒牌平局: 聴牌者, 九種九牌: 宣言者, 三家和: 和了者以外, 四家立直: 全員)
+pub fn get_draw_hands(stg: &Stage, draw_type: DrawType) -> [Vec<Tile>; SEAT] {
+    let n_seat = get_n_seat(stg);
+    let open_seats: Vec<Seat> = match draw_type {
+        DrawType::Kouhaiheikyoku => (0..n_seat)
+            .filter(|&s| !stg.players[s].winning_tiles.is_empty())
+            .collect(),
+        DrawType::Kyushukyuhai => vec![stg.turn],
+        DrawType::Sanchaho => (0..n_seat).filter(|&s| s != stg.turn).collect(),
+        DrawType::Suuchariichi => (0..n_seat).collect(),
+        DrawType::Suufuurenda | DrawType::Suukansanra | DrawType::Unknown => vec![],
+    };
+    let mut hands = [vec![], vec![], vec![], vec![]];
+    for s in open_seats {
+        hands[s] = tiles_from_tile_table(&stg.players[s].hand);
+    }
+    hands
+}
+
+// 見えている牌(手牌,副露,河,ドラ表示牌など)の枚数 (赤5は通常の5として集計)
+// 3麻で使用しない萬子(2~8)はすべて見えているものとして扱う
+pub fn get_visible_tiles(stg: &Stage) -> TileTable {
+    let mut tt = TileTable::default();
+    for ti in 0..TYPE {
+        for ni in 1..TNUM {
+            tt[ti][ni] = if stg.rule.is_sanma && ti == TM && 1 < ni && ni < 9 {
+                TILE
+            } else {
+                stg.tile_states[ti][ni]
+                    .iter()
+                    .filter(|&&st| st != TileState::U)
+                    .count()
+            };
+        }
+    }
+    tt
+}
+
 // ダブル立直, 天和, 地和の判定用
 pub fn is_no_meld_turn1(stg: &Stage, seat: Seat) -> bool {
     if !stg.players[seat].discards.is_empty() {
@@ -58,6 +108,32 @@ pub fn is_no_meld_turn1(stg: &Stage, seat: Seat) -> bool {
     }
 }
 
+// 鳴き操作を適用する前のプレイヤーに対して責任払いが発生するかどうかを判定
+// 返り値: (大三元または大四喜の責任払い, 四槓子の責任払い)
+pub fn check_pao(pl: &Player, meld_type: MeldType, t: Tile) -> (bool, bool) {
+    let count = |f: fn(&Meld) -> bool| pl.melds.iter().filter(|m| f(m)).count();
+
+    // 大三元, 大四喜
+    let is_pao = if t.is_doragon() {
+        count(|m| m.tiles[0].is_doragon()) == 2
+    } else if t.is_wind() {
+        count(|m| m.tiles[0].is_wind()) == 3
+    } else {
+        false
+    };
+
+    // 四槓子
+    let is_pao_kan = meld_type == MeldType::Minkan
+        && count(|m| {
+            matches!(
+                m.meld_type,
+                MeldType::Ankan | MeldType::Kakan | MeldType::Minkan
+            )
+        }) == 3;
+
+    (is_pao, is_pao_kan)
+}
+
 // TileTable
 pub fn count_tile(tt: &TileTable, t: Tile) -> usize {
     if t.1 == 5 {
@@ -1,13 +1,21 @@
-use std::task::{Context, Poll, Waker};
+use std::{
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
 
 use rand::prelude::*;
 
 use super::{
-    actor::Actor, common::*, listener::Listener, possible_actions::*,
-    stage_controller::StageController, wall::create_wall,
+    actor::{Actor, ready},
+    common::*,
+    listener::Listener,
+    possible_actions::*,
+    stage_controller::StageController,
+    wall::create_wall,
 };
 use crate::{
-    hand::{evaluate_hand_ron, evaluate_hand_tsumo},
+    error,
+    hand::{calc_points, evaluate_hand_ron, evaluate_hand_tsumo},
     model::*,
     util::{
         misc::{sleep, vec_to_string},
@@ -32,6 +40,7 @@ struct NextRoundInfo {
     honba: usize,
     riichi_sticks: usize,
     scores: [Score; SEAT],
+    n_seat: usize,
 }
 
 impl NextRoundInfo {
@@ -42,12 +51,13 @@ impl NextRoundInfo {
             honba: stg.honba,
             riichi_sticks: stg.riichi_sticks,
             scores: [0; SEAT],
+            n_seat: get_n_seat(stg),
         }
     }
 
     fn change_dealer(&mut self) {
         self.dealer += 1;
-        if self.dealer == SEAT {
+        if self.dealer == self.n_seat {
             self.dealer = 0;
             self.round += 1;
         }
@@ -71,13 +81,16 @@ pub struct MahjongEngine {
     round_result: Option<RoundResult>,
     next_round_info: NextRoundInfo,
     // 牌山
-    wall: Vec<Tile>,             // 牌山全体 (=136)
-    dora_wall: Vec<Tile>,        // ドラ表示牌
-    ura_dora_wall: Vec<Tile>,    // 裏ドラ
-    replacement_wall: Vec<Tile>, // 嶺上牌
+    wall: Vec<Tile>,              // 牌山全体 (4麻: 136, 3麻: 108)
+    dora_wall: Vec<Tile>,         // ドラ表示牌
+    ura_dora_wall: Vec<Tile>,     // 裏ドラ
+    replacement_wall: Vec<Tile>,  // 嶺上牌
+    preset_walls: Vec<Vec<Tile>>, // テスト用に指定した牌山 (局ごとに先頭から使用)
     // 非同期制御
     waiter: Waiter,
     waker: Waker,
+    extra_times: [f64; SEAT],  // 各プレイヤーの残りの考慮時間(秒)
+    violations: [usize; SEAT], // 各プレイヤーの不正なアクションの回数
 }
 
 impl MahjongEngine {
@@ -87,15 +100,22 @@ impl MahjongEngine {
         pause: f64,
         actors: [Box<dyn Actor>; SEAT],
         listeners: Vec<Box<dyn Listener>>,
+        omniscient: [bool; SEAT], // 秘匿情報を取り除かないStageとEventを通知する座席 (デバッグ用)
     ) -> Self {
-        let ctrl = StageController::new(actors, listeners);
+        let ctrl = StageController::new(actors, listeners, omniscient);
         let rng = rand::SeedableRng::seed_from_u64(seed);
+        let n_seat = calc_n_seat(rule.is_sanma);
+        let mut scores = [0; SEAT];
+        for s in 0..n_seat {
+            scores[s] = rule.initial_score;
+        }
         let next_round_info = NextRoundInfo {
             round: 0,
             dealer: 0,
             honba: 0,
             riichi_sticks: 0,
-            scores: [rule.initial_score; SEAT],
+            scores,
+            n_seat,
         };
         let (waiter, waker) = waiter_waker();
 
@@ -117,14 +137,18 @@ impl MahjongEngine {
             dora_wall: vec![],
             ura_dora_wall: vec![],
             replacement_wall: vec![],
+            preset_walls: vec![],
             waiter,
             waker,
+            extra_times: [0.0; SEAT],
+            violations: [0; SEAT],
         }
     }
 
     pub fn run(&mut self) {
         self.do_event_begin();
-        while !self.is_game_end() {
+        // 終了判定は局の終了後のみ (局開始前のStageの点数は0のため飛びと判定される)
+        loop {
             self.do_event_new();
             loop {
                 self.do_event_deal();
@@ -145,6 +169,9 @@ impl MahjongEngine {
                 }
             }
             self.do_event_win_draw();
+            if self.is_game_end() {
+                break;
+            }
         }
         self.do_event_end();
     }
@@ -168,10 +195,12 @@ impl MahjongEngine {
         let rule = &self.rule;
         let next_round = self.next_round_info.round;
 
-        // 飛びによる対戦終了
-        for s in 0..SEAT {
-            if stg.players[s].score < 0 {
-                return true;
+        // 飛びによる対戦終了 (飛びなしの場合は負の点数でも続行)
+        if rule.bust {
+            for s in 0..get_n_seat(&stg) {
+                if stg.players[s].score < rule.bust_score {
+                    return true;
+                }
             }
         }
 
@@ -203,6 +232,8 @@ impl MahjongEngine {
     }
 
     fn do_event_begin(&mut self) {
+        self.extra_times = [self.rule.time_extra; SEAT];
+        self.violations = [0; SEAT];
         self.handle_event(Event::begin());
     }
 
@@ -225,7 +256,10 @@ impl MahjongEngine {
         self.replacement_wall = vec![];
 
         // 山の初期化
-        self.wall = create_wall(self.rng.next_u64(), self.rule.red5);
+        self.wall = create_wall(self.rng.next_u64(), self.rule.red5, self.rule.is_sanma);
+        if !self.preset_walls.is_empty() {
+            self.wall = self.preset_walls.remove(0);
+        }
         // self.wall = super::wall::create_wall_debug(self.rng.next_u64(), self.rule.red5);
 
         // 王牌
@@ -233,9 +267,9 @@ impl MahjongEngine {
         self.ura_dora_wall = self.draw_tiles(5); // 裏ドラ
         self.replacement_wall = self.draw_tiles(4); // 嶺上牌
 
-        // プレイヤーの手牌生成
+        // プレイヤーの手牌生成 (3麻の場合,座席3は空席)
         let mut ph = [vec![], vec![], vec![], vec![]];
-        for s in 0..SEAT {
+        for s in 0..calc_n_seat(self.rule.is_sanma) {
             ph[s] = self.draw_tiles(13);
         }
 
@@ -292,20 +326,23 @@ impl MahjongEngine {
                 }
                 Nukidora => {
                     let k = self.draw_nukidora_tile();
-                    self.handle_event(Event::deal(turn, k, false));
+                    self.handle_event(Event::deal(turn, k, true));
                 }
                 _ => panic!(),
             }
         } else {
             if wall_count > 0 {
-                let s = (turn + 1) % SEAT;
+                let s = (turn + 1) % calc_n_seat(self.rule.is_sanma);
                 let t = self.draw_tile();
                 self.handle_event(Event::deal(s, t, false));
             } else {
                 self.round_result = Some(RoundResult::Draw(DrawType::Kouhaiheikyoku));
             }
         }
-        assert!(self.get_stage().wall_count + self.n_deal + self.n_kan == self.wall.len());
+        assert!(
+            self.get_stage().wall_count + self.n_deal + self.n_kan + self.n_nukidora
+                == self.wall.len()
+        );
     }
 
     fn do_turn_operation(&mut self) {
@@ -314,31 +351,60 @@ impl MahjongEngine {
         let stg = self.get_stage();
         let turn = stg.turn;
         let pl = &stg.players[turn];
-        let tenpais =
-            calc_possible_tenpai_discards(pl, get_prevalent_wind(&stg), get_seat_wind(&stg, turn));
+        let tenpais = calc_possible_tenpai_discards(
+            pl,
+            get_prevalent_wind(&stg),
+            get_seat_wind(&stg, turn),
+            &stg.rule,
+        );
         let acts = calc_possible_turn_actions(&stg, &self.melding, &tenpais);
         drop(stg);
 
-        let mut cx = Context::from_waker(&self.waker);
-        let mut selected_acton = self.ctrl.query_action(turn, &acts, &tenpais);
-        let act = loop {
-            match selected_acton.as_mut().poll(&mut cx) {
-                Poll::Ready(act) => {
-                    break act;
+        let act = if self.is_forfeited(turn) {
+            // 失格したプレイヤーには問い合わせずに既定のアクションを適用
+            self.default_turn_action(turn, &acts)
+        } else {
+            let mut cx = Context::from_waker(&self.waker);
+            let start = Instant::now();
+            let deadline = self.calc_deadline(turn, start);
+            let mut selected_acton = self.ctrl.query_action(turn, &acts, &tenpais);
+            let act = loop {
+                match selected_acton.as_mut().poll(&mut cx) {
+                    Poll::Ready(act) => {
+                        self.consume_time(turn, start);
+                        break Some(act);
+                    }
+                    Poll::Pending => {}
+                }
+                if !self.wait_until(deadline) {
+                    // 時間切れ: Futureを破棄してから既定のアクション(ツモ切り)を適用
+                    self.ctrl.expire_action(turn);
+                    break None;
+                }
+            };
+            drop(selected_acton);
+
+            match act {
+                Some(act) => match self.validate_turn_action(turn, &acts, &act) {
+                    Ok(()) => act,
+                    Err(reason) => {
+                        let fallback = self.default_turn_action(turn, &acts);
+                        self.reject_action(turn, act, fallback.clone(), reason);
+                        fallback
+                    }
+                },
+                None => {
+                    self.extra_times[turn] = 0.0;
+                    self.handle_event(Event::timeout(turn));
+                    self.default_turn_action(turn, &acts)
                 }
-                Poll::Pending => {}
             }
-            self.waiter.wait();
         };
 
         let tp = act.ty;
         let cs = act.tiles.clone();
         self.melding = None;
 
-        match tp {
-            Discard | Riichi => {}             // 後で個別にチェック
-            _ => assert!(acts.contains(&act)), // 選択されたActionが有効であることを検証
-        }
         match tp {
             Nop => {
                 // 打牌: ツモ切り
@@ -358,14 +424,6 @@ impl MahjongEngine {
                     false
                 };
 
-                assert!(
-                    !acts
-                        .iter()
-                        .find(|a| a.ty == Discard)
-                        .unwrap()
-                        .tiles
-                        .contains(&t)
-                );
                 drop(stg);
                 self.handle_event(Event::discard(turn, t, m, false))
             }
@@ -380,13 +438,6 @@ impl MahjongEngine {
                     let m = pl.drawn == Some(t) && pl.hand[t.0][t.1] == 1;
                     (t, m)
                 };
-                assert!(
-                    acts.iter()
-                        .find(|a| a.ty == Riichi)
-                        .unwrap()
-                        .tiles
-                        .contains(&t)
-                );
                 drop(stg);
                 self.handle_event(Event::discard(turn, t, m, true));
             }
@@ -420,7 +471,12 @@ impl MahjongEngine {
     fn do_call_operation(&mut self) {
         // 順番以外のプレイヤーにActionを要求
         // act: Nop, Chi, Pon, Minkan, Ron
-        let acts_list = calc_possible_call_actions(&self.get_stage(), !self.is_suukansanra);
+        let mut acts_list = calc_possible_call_actions(&self.get_stage(), !self.is_suukansanra);
+        for s in 0..SEAT {
+            if self.is_forfeited(s) {
+                acts_list[s] = vec![Action::nop()]; // 失格したプレイヤーは常にスキップ
+            }
+        }
 
         // プレイヤー全体でのアクション数をカウント
         let mut n_rons = 0;
@@ -449,32 +505,42 @@ impl MahjongEngine {
 
         let mut selected_actions = vec![]; // actionそのものではなくFutureであることに注意
         let mut completed_actors = vec![];
+        let mut timeout_actors = vec![];
+        let mut invalid_actions = vec![];
+        let start = Instant::now();
+        let mut deadlines = [None; SEAT];
         for s in 0..SEAT {
             if acts_list[s].len() > 1 {
+                deadlines[s] = self.calc_deadline(s, start);
                 selected_actions.push((s, self.ctrl.query_action(s, &acts_list[s], &[])));
             }
         }
 
         // 各プレイヤーのcall oprationを非同期で決定するためのmini executor
         let mut event = None;
-        let mut cx = Context::from_waker(&self.waker);
+        let waker = self.waker.clone(); // selfの可変借用と両立させるために複製
+        let mut cx = Context::from_waker(&waker);
         loop {
             for (s, f) in &mut selected_actions {
                 if !completed_actors.contains(s) {
                     match f.as_mut().poll(&mut cx) {
                         Poll::Ready(act) => {
                             let s = *s;
-                            match act.ty {
-                                Nop => {}
-                                Chi => chi = Some((s, act)),
-                                Pon => pon = Some((s, act)),
-                                Minkan => minkan = Some((s, act)),
-                                Ron => rons.push(s),
-                                _ => panic!(
-                                    "action {} not found in {}",
-                                    act,
-                                    vec_to_string(&acts_list[s])
-                                ),
+                            self.consume_time(s, start);
+                            if !acts_list[s].contains(&act) {
+                                // 不正なアクションはスキップ(Nop)を選択したものとして扱う
+                                let reason =
+                                    format!("action not found in {}", vec_to_string(&acts_list[s]));
+                                invalid_actions.push((s, act, reason));
+                            } else {
+                                match act.ty {
+                                    Nop => {}
+                                    Chi => chi = Some((s, act)),
+                                    Pon => pon = Some((s, act)),
+                                    Minkan => minkan = Some((s, act)),
+                                    Ron => rons.push(s),
+                                    _ => panic!(),
+                                }
                             }
 
                             for act in &acts_list[s] {
@@ -496,7 +562,7 @@ impl MahjongEngine {
 
             let mut n_priority = n_rons;
             if n_priority == 0 && !rons.is_empty() {
-                self.round_result = Some(RoundResult::Ron(rons));
+                self.round_result = Some(self.resolve_rons(rons));
                 break;
             }
             n_priority += n_minkan;
@@ -530,7 +596,36 @@ impl MahjongEngine {
                 break; // すべてのActionがキャンセルされた場合はここに到達
             }
 
-            self.waiter.wait();
+            // 最も早く時間切れになるプレイヤーの期限まで待機
+            let deadline = selected_actions
+                .iter()
+                .filter(|(s, _)| !completed_actors.contains(s))
+                .filter_map(|(s, _)| deadlines[*s])
+                .min();
+            if !self.wait_until(deadline) {
+                // 時間切れのプレイヤーはスキップ(Nop)を選択したものとして扱う
+                let now = Instant::now();
+                for (s, f) in &mut selected_actions {
+                    if completed_actors.contains(s) || deadlines[*s].is_none_or(|d| now < d) {
+                        continue;
+                    }
+                    for act in &acts_list[*s] {
+                        match act.ty {
+                            Nop => {}
+                            Chi => n_chi -= 1,
+                            Pon => n_pon -= 1,
+                            Minkan => n_minkan -= 1,
+                            Ron => n_rons -= 1,
+                            _ => panic!(),
+                        }
+                    }
+                    self.ctrl.expire_action(*s);
+                    *f = ready(Action::nop()); // 応答のないFutureは破棄
+                    self.extra_times[*s] = 0.0;
+                    completed_actors.push(*s);
+                    timeout_actors.push(*s);
+                }
+            }
         }
 
         // アクションをまだ選択していないActorがあったら失効通知を送る
@@ -550,10 +645,27 @@ impl MahjongEngine {
                     }
                 }
             }
-            self.waiter.wait();
+            if selected_actions.len() == completed_actors.len() {
+                break;
+            }
+            let deadline = selected_actions
+                .iter()
+                .filter(|(s, _)| !completed_actors.contains(s))
+                .filter_map(|(s, _)| deadlines[*s])
+                .min();
+            if !self.wait_until(deadline) {
+                break; // 失効通知後も応答がないFutureは破棄
+            }
         }
 
         // Futureが獲得しているReadGuardがすべて開放されてからイベントを処理
+        drop(selected_actions);
+        for s in timeout_actors {
+            self.handle_event(Event::timeout(s));
+        }
+        for (s, act, reason) in invalid_actions {
+            self.reject_action(s, act, Action::nop(), reason);
+        }
         if let Some(ev) = event {
             self.handle_event(ev);
         }
@@ -584,36 +696,18 @@ impl MahjongEngine {
         let mut round_info = NextRoundInfo::from_stage(&stg);
 
         let score_ctx = evaluate_hand_tsumo(&stg, &self.ura_dora_wall).unwrap();
-        let (mut ron, mut non_dealer, mut dealer) = score_ctx.points;
-
         let pl = &stg.players[turn];
-        let mut d_scores = [0; SEAT]; // 得点変動
-
-        // TODO: 大四喜と四槓子の包の同時発生, 包を含む2倍以上の役満時の点数計算
-        if let Some(pao) = pl.pao {
-            // 責任払い
-            ron += stg.honba as i32 * 300;
-            d_scores[pao] -= ron;
-            d_scores[turn] += ron;
-        } else {
-            // 積み棒
-            non_dealer += stg.honba as i32 * 100;
-            dealer += stg.honba as i32 * 100;
-
-            for s in 0..SEAT {
-                if s != turn {
-                    if !is_dealer(&stg, s) {
-                        // 子の支払い
-                        d_scores[s] -= non_dealer;
-                        d_scores[turn] += non_dealer;
-                    } else {
-                        // 親の支払い
-                        d_scores[s] -= dealer;
-                        d_scores[turn] += dealer;
-                    }
-                };
-            }
-        }
+        let paos = calc_pao_yakuman(pl.pao, pl.pao_kan, &score_ctx);
+        let mut d_scores = calc_win_delta_scores(
+            get_n_seat(&stg),
+            stg.dealer,
+            turn,
+            turn,
+            &score_ctx,
+            &paos,
+            stg.honba,
+            &self.rule,
+        );
 
         // 供託
         d_scores[turn] += stg.riichi_sticks as i32 * 1000;
@@ -641,7 +735,7 @@ impl MahjongEngine {
             is_dealer: is_dealer(&stg, turn),
             is_drawn: true,
             is_riichi: pl.is_riichi,
-            pao: pl.pao,
+            pao: paos.first().map(|p| p.0),
             delta_scores: d_scores,
             score_context: score_ctx,
         };
@@ -651,49 +745,62 @@ impl MahjongEngine {
         (event, round_info)
     }
 
-    fn round_result_ron(&self, seats: &[Seat]) -> (Event, NextRoundInfo) {
+    // 複数人のロンをルールに従って処理 (三家和, 頭ハネ)
+    // 返却されるRonの座席は放銃者から一番近いプレイヤー順
+    fn resolve_rons(&self, rons: Vec<Seat>) -> RoundResult {
         let stg = self.get_stage();
         let turn = stg.turn;
-        let mut round_info = NextRoundInfo::from_stage(&stg);
+        let n_seat = get_n_seat(&stg);
+        if self.rule.sanchahou && rons.len() == 3 {
+            return RoundResult::Draw(DrawType::Sanchaho);
+        }
 
-        // 放銃者から一番近いプレイヤー順にソート
-        let mut seats_sorted = vec![];
-        for s in turn + 1..turn + SEAT {
-            let s = s % SEAT;
-            if seats.contains(&s) {
-                seats_sorted.push(s);
+        let mut seats = vec![];
+        for s in turn + 1..turn + n_seat {
+            let s = s % n_seat;
+            if rons.contains(&s) {
+                seats.push(s);
             }
         }
+        if self.rule.atamahane {
+            seats.truncate(1);
+        }
+        RoundResult::Ron(seats)
+    }
+
+    fn round_result_ron(&self, seats: &[Seat]) -> (Event, NextRoundInfo) {
+        let stg = self.get_stage();
+        let turn = stg.turn;
+        let mut round_info = NextRoundInfo::from_stage(&stg);
 
         let mut is_first = true; // 上家取り
         let mut ctxs = vec![];
         let mut total_d_scores = [0; SEAT];
-        for s in seats_sorted {
+        for &s in seats {
             let score_ctx = evaluate_hand_ron(&stg, &self.ura_dora_wall, s).unwrap();
-            let (ron, _, _) = score_ctx.points;
-
             let pl = &stg.players[s];
-            let mut d_scores = [0; SEAT]; // 得点変動
+            let paos = calc_pao_yakuman(pl.pao, pl.pao_kan, &score_ctx);
 
-            if let Some(pao) = pl.pao {
-                // 責任払いが発生している場合,ロンの半分ずつの支払い
-                d_scores[turn] -= ron / 2;
-                d_scores[pao] -= ron / 2;
+            // 積み棒(上家取り, またはルールにより和了者全員)
+            let honba = if is_first || self.rule.honba_per_winner {
+                stg.honba
             } else {
-                d_scores[turn] -= ron; // 直撃を受けたプレイヤー
+                0
             };
-            d_scores[s] += ron; // 和了ったプレイヤー
-
-            // 積み棒&供託(上家取り)
+            let mut d_scores = calc_win_delta_scores(
+                get_n_seat(&stg),
+                stg.dealer,
+                s,
+                turn,
+                &score_ctx,
+                &paos,
+                honba,
+                &self.rule,
+            );
+
+            // 供託(上家取り)
             if is_first {
                 is_first = false;
-                if let Some(pao) = pl.pao {
-                    // 積み棒は責任払い優先
-                    d_scores[pao] -= stg.honba as i32 * 300;
-                } else {
-                    d_scores[turn] -= stg.honba as i32 * 300;
-                }
-                d_scores[s] += stg.honba as i32 * 300;
                 d_scores[s] += stg.riichi_sticks as i32 * 1000;
             }
 
@@ -709,7 +816,7 @@ impl MahjongEngine {
                 is_dealer: is_dealer(&stg, s),
                 is_drawn: false,
                 is_riichi: pl.is_riichi,
-                pao: pl.pao,
+                pao: paos.first().map(|p| p.0),
                 delta_scores: d_scores,
                 score_context: score_ctx,
             };
@@ -735,6 +842,7 @@ impl MahjongEngine {
 
     fn round_result_draw(&self) -> (Event, NextRoundInfo) {
         let stg = self.get_stage();
+        let n_seat = get_n_seat(&stg);
         let mut round_info = NextRoundInfo::from_stage(&stg);
         round_info.honba += 1;
 
@@ -742,7 +850,7 @@ impl MahjongEngine {
         let mut tenpais = [false; SEAT];
         let mut n_tenpai = 0;
         let mut nagashimangan = false;
-        for s in 0..SEAT {
+        for s in 0..n_seat {
             let pl = &stg.players[s];
             tenpais[s] = !pl.winning_tiles.is_empty();
             nagashimangan |= pl.is_nagashimangan;
@@ -752,7 +860,7 @@ impl MahjongEngine {
         }
 
         let mut hands = [vec![], vec![], vec![], vec![]];
-        for s in 0..SEAT {
+        for s in 0..n_seat {
             if tenpais[s] {
                 hands[s] = tiles_from_tile_table(&stg.players[s].hand);
             }
@@ -762,46 +870,38 @@ impl MahjongEngine {
         let mut d_scores = [0; SEAT];
         let mut nm_scores = [0; SEAT]; // 流し満貫スコア
         if nagashimangan {
-            // 流し満貫スコア集計
-            for s_nm in 0..SEAT {
+            // 流し満貫スコア集計 (満貫のツモ和了と同じ支払い, 3麻の場合はツモ損あり)
+            for s_nm in 0..n_seat {
                 if stg.players[s_nm].is_nagashimangan {
-                    if is_dealer(&stg, s_nm) {
-                        nm_scores[s_nm] = 12000;
-                        for s in 0..SEAT {
-                            if s_nm == s {
-                                d_scores[s] += 12000;
-                            } else {
-                                d_scores[s] -= 4000;
-                            }
-                        }
-                    } else {
-                        nm_scores[s_nm] = 8000;
-                        for s in 0..SEAT {
-                            if s_nm == s {
-                                d_scores[s] += 8000;
-                            } else {
-                                if is_dealer(&stg, s) {
-                                    d_scores[s] -= 4000;
-                                } else {
-                                    d_scores[s] -= 2000;
-                                }
-                            }
+                    for s in 0..n_seat {
+                        if s_nm == s {
+                            continue;
                         }
+                        let pay = if is_dealer(&stg, s_nm) || is_dealer(&stg, s) {
+                            4000
+                        } else {
+                            2000
+                        };
+                        d_scores[s] -= pay;
+                        d_scores[s_nm] += pay;
+                        nm_scores[s_nm] += pay;
                     }
                 }
             }
         } else {
             // 流局時の聴牌人数による得点変動
-            let (pay, recv) = match n_tenpai {
-                0 => (0, 0), // 全員ノーテン
-                1 => (1000, 3000),
-                2 => (1500, 1500),
-                3 => (3000, 1000),
-                4 => (0, 0), // 全員聴牌
+            let (pay, recv) = match (n_seat, n_tenpai) {
+                (_, 0) => (0, 0), // 全員ノーテン
+                (4, 1) => (1000, 3000),
+                (4, 2) => (1500, 1500),
+                (4, 3) => (3000, 1000),
+                (3, 1) => (1000, 2000),
+                (3, 2) => (2000, 1000),
+                (_, n) if n == n_seat => (0, 0), // 全員聴牌
                 _ => panic!(),
             };
 
-            for s in 0..SEAT {
+            for s in 0..n_seat {
                 d_scores[s] = if tenpais[s] { recv } else { -pay };
             }
         }
@@ -820,17 +920,11 @@ impl MahjongEngine {
         let mut round_info = NextRoundInfo::from_stage(&stg);
         round_info.honba += 1;
 
-        let open_seats: Vec<Seat> = match type_ {
-            DrawType::Kouhaiheikyoku | DrawType::Unknown => panic!(),
-            DrawType::Suufuurenda | DrawType::Suukansanra => vec![],
-            DrawType::Kyushukyuhai => vec![stg.turn],
-            DrawType::Sanchaho => (0..SEAT).filter(|&s| s != stg.turn).collect(),
-            DrawType::Suuchariichi => (0..SEAT).collect(),
-        };
-        let mut hands = [vec![], vec![], vec![], vec![]];
-        for s in open_seats {
-            hands[s] = tiles_from_tile_table(&stg.players[s].hand);
-        }
+        assert!(!matches!(
+            type_,
+            DrawType::Kouhaiheikyoku | DrawType::Unknown
+        ));
+        let hands = get_draw_hands(&stg, type_);
 
         let event = Event::draw(type_, [0; SEAT], [0; SEAT], hands);
 
@@ -852,19 +946,157 @@ impl MahjongEngine {
     fn draw_kan_tile(&mut self) -> (Tile, Tile) {
         let (c, k) = (self.n_kan, self.n_nukidora);
         self.n_kan += 1;
-        (self.replacement_wall[c + k], self.dora_wall[c + 1]) // (replacement_tile, dora_tile)
+        (self.draw_replacement_tile(c + k), self.dora_wall[c + 1]) // (replacement_tile, dora_tile)
     }
 
     fn draw_nukidora_tile(&mut self) -> Tile {
         let (c, k) = (self.n_kan, self.n_nukidora);
         self.n_nukidora += 1;
-        self.replacement_wall[c + k]
+        self.draw_replacement_tile(c + k)
+    }
+
+    fn draw_replacement_tile(&self, i: usize) -> Tile {
+        // 3麻で槓と北抜きの合計が嶺上牌の数を超えた場合は牌山の末尾(海底側)から補充
+        // 嶺上牌をツモるたびにwall_countが減少するため通常のツモと重複することはない
+        let n = self.replacement_wall.len();
+        if i < n {
+            self.replacement_wall[i]
+        } else {
+            self.wall[self.wall.len() - 1 - (i - n)]
+        }
+    }
+
+    // 持ち時間と考慮時間からアクション選択の期限を計算 (持ち時間が0の場合は無制限)
+    fn calc_deadline(&self, seat: Seat, start: Instant) -> Option<Instant> {
+        if self.rule.time_base <= 0.0 {
+            return None;
+        }
+        let limit = self.rule.time_base + self.extra_times[seat];
+        Some(start + Duration::from_secs_f64(limit))
+    }
+
+    // 持ち時間を超過した分を考慮時間から消費
+    fn consume_time(&mut self, seat: Seat, start: Instant) {
+        if self.rule.time_base <= 0.0 {
+            return;
+        }
+        let over = start.elapsed().as_secs_f64() - self.rule.time_base;
+        if over > 0.0 {
+            self.extra_times[seat] = (self.extra_times[seat] - over).max(0.0);
+        }
+    }
+
+    // 期限まで待機 期限を過ぎた場合はfalseを返却
+    fn wait_until(&self, deadline: Option<Instant>) -> bool {
+        let Some(deadline) = deadline else {
+            self.waiter.wait();
+            return true;
+        };
+        let now = Instant::now();
+        if deadline <= now {
+            return false;
+        }
+        self.waiter.wait_timeout(deadline - now).ok();
+        Instant::now() < deadline
+    }
+
+    pub fn get_violations(&self) -> [usize; SEAT] {
+        self.violations
+    }
+
+    // 不正なアクションの回数が上限に達したプレイヤーは失格 (以降のアクションは自動で選択)
+    fn is_forfeited(&self, seat: Seat) -> bool {
+        self.rule.max_violations != 0 && self.violations[seat] >= self.rule.max_violations
+    }
+
+    // ツモ番に選択されたアクションが有効であることを検証
+    fn validate_turn_action(
+        &self,
+        turn: Seat,
+        acts: &[Action],
+        act: &Action,
+    ) -> Result<(), String> {
+        let stg = self.get_stage();
+        let pl = &stg.players[turn];
+        let find = |tp: ActionType| acts.iter().find(|a| a.ty == tp);
+        match act.ty {
+            Discard => {
+                let Some(a) = find(Discard) else {
+                    return Err("discard is not allowed".into());
+                };
+                let [t] = act.tiles[..] else {
+                    return Err("discard requires exactly one tile".into());
+                };
+                if t.0 >= TYPE || t.1 >= TNUM || count_tile(&pl.hand, t) == 0 {
+                    return Err(format!("tile {} not in hand", t));
+                }
+                if a.tiles.contains(&t) {
+                    return Err(format!("tile {} is restricted (kuikae)", t));
+                }
+            }
+            Riichi => {
+                let Some(a) = find(Riichi) else {
+                    return Err("riichi is not allowed".into());
+                };
+                let t = match act.tiles[..] {
+                    [] => pl.drawn,
+                    [t] => Some(t),
+                    _ => None,
+                };
+                if !t.is_some_and(|t| a.tiles.contains(&t)) {
+                    return Err("invalid riichi discard".into());
+                }
+            }
+            _ => {
+                if !acts.contains(act) {
+                    return Err(format!("action not found in {}", vec_to_string(acts)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // 不正なアクションの回数を記録してActorに通知
+    fn reject_action(&mut self, seat: Seat, action: Action, fallback: Action, reason: String) {
+        self.violations[seat] += 1;
+        let error = ActionError {
+            seat,
+            action,
+            fallback,
+            reason,
+            violations: self.violations[seat],
+            is_forfeited: self.is_forfeited(seat),
+        };
+        error!("{}", error);
+        self.ctrl.reject_action(seat, &error);
+    }
+
+    // 時間切れの場合の既定のアクション
+    // 通常はツモ切り, 鳴いた後はツモ牌がないため喰い替えにならない牌を打牌
+    fn default_turn_action(&self, turn: Seat, acts: &[Action]) -> Action {
+        if acts.iter().any(|a| a.ty == Nop) {
+            return Action::nop();
+        }
+        let stg = self.get_stage();
+        let restricted = &acts.iter().find(|a| a.ty == Discard).unwrap().tiles;
+        let hand = tiles_from_tile_table(&stg.players[turn].hand);
+        let t = hand
+            .iter()
+            .rev()
+            .find(|t| !restricted.contains(t))
+            .unwrap_or(&hand[0]);
+        Action::discard(*t)
     }
 
     fn check_abortive_draw(&mut self) {
-        self.check_suufuurenda();
+        // 四風連打と四家立直は4麻のみ
+        if !self.rule.is_sanma {
+            self.check_suufuurenda();
+        }
         self.check_suukansanra();
-        self.check_suuchariichi();
+        if !self.rule.is_sanma {
+            self.check_suuchariichi();
+        }
     }
 
     fn check_suufuurenda(&mut self) {
@@ -926,3 +1158,524 @@ impl MahjongEngine {
         }
     }
 }
+
+// 責任払いの対象となる役満の倍率を責任者ごとに集計 [(責任者の座席, 役満倍率)]
+fn calc_pao_yakuman(
+    pao: Option<Seat>,
+    pao_kan: Option<Seat>,
+    score_ctx: &ScoreContext,
+) -> Vec<(Seat, usize)> {
+    let mut paos: Vec<(Seat, usize)> = vec![];
+    for y in &score_ctx.yakus {
+        let p = match y.name.as_str() {
+            "大三元" | "大四喜" => pao,
+            "四槓子" => pao_kan,
+            _ => None,
+        };
+        if let Some(p) = p {
+            if let Some(pm) = paos.iter_mut().find(|pm| pm.0 == p) {
+                pm.1 += y.fan; // 大四喜と四槓子の包が同時に発生した場合は合算
+            } else {
+                paos.push((p, y.fan));
+            }
+        }
+    }
+
+    // 役満の複合なしや倍率の上限によって合計倍率が減っている場合は責任払いの対象を優先
+    let mut remain = score_ctx.yakuman;
+    for pm in &mut paos {
+        pm.1 = pm.1.min(remain);
+        remain -= pm.1;
+    }
+    paos.retain(|pm| pm.1 != 0);
+    paos
+}
+
+// 和了による得点変動を計算 (供託は含まない)
+// winner == fromの場合はツモ和了, fromは放銃者
+// 責任払いの対象の役満は, ツモの場合は責任者が全額, ロンの場合は責任者と放銃者が折半して支払う
+// 対象外の役満と通常役は通常の和了と同様に支払う 積み棒は責任払い優先
+fn calc_win_delta_scores(
+    n_seat: usize,
+    dealer: Seat,
+    winner: Seat,
+    from: Seat,
+    score_ctx: &ScoreContext,
+    paos: &[(Seat, usize)],
+    honba: usize,
+    rule: &Rule,
+) -> [Point; SEAT] {
+    let is_dealer = winner == dealer;
+    let mut d_scores = [0; SEAT]; // 得点変動
+    let honba_points = honba as i32 * 100 * (n_seat as i32 - 1);
+
+    // 責任払いの対象となる役満
+    let mut pao_ron = 0;
+    for &(pao, m) in paos {
+        let ((ron, _, _), _) = calc_points(is_dealer, 0, 0, m, rule);
+        pao_ron += ron;
+        if winner == from {
+            d_scores[pao] -= ron;
+        } else {
+            d_scores[pao] -= ron / 2;
+            d_scores[from] -= ron - ron / 2;
+        }
+        d_scores[winner] += ron;
+    }
+    if let Some(&(pao, _)) = paos.first() {
+        d_scores[pao] -= honba_points;
+        d_scores[winner] += honba_points;
+    }
+
+    // 責任払いの対象外
+    if winner == from {
+        let (_, mut non_dealer, mut dealer_pay) = if paos.is_empty() {
+            score_ctx.points
+        } else {
+            let m = score_ctx.yakuman - paos.iter().map(|pm| pm.1).sum::<usize>();
+            calc_points(is_dealer, 0, 0, m, rule).0
+        };
+        if paos.is_empty() {
+            // 積み棒
+            non_dealer += honba as i32 * 100;
+            dealer_pay += honba as i32 * 100;
+        }
+
+        // 3麻の場合は空席の支払い分がなくなる (ツモ損)
+        for s in 0..n_seat {
+            if s != winner {
+                let pay = if s != dealer {
+                    non_dealer // 子の支払い
+                } else {
+                    dealer_pay // 親の支払い
+                };
+                d_scores[s] -= pay;
+                d_scores[winner] += pay;
+            }
+        }
+    } else {
+        let ron = score_ctx.points.0 - pao_ron;
+        d_scores[from] -= ron; // 直撃を受けたプレイヤー
+        d_scores[winner] += ron; // 和了ったプレイヤー
+        if paos.is_empty() {
+            d_scores[from] -= honba_points;
+            d_scores[winner] += honba_points;
+        }
+    }
+
+    d_scores
+}
+
+#[test]
+fn test_timeout() {
+    use super::{
+        actor::Config,
+        testing::{Recorder, config, east_rule},
+    };
+
+    // 一切応答しないActor
+    #[derive(Clone)]
+    struct Pending(Config);
+    impl Listener for Pending {}
+    impl Actor for Pending {
+        fn select(&mut self, _acts: &[Action], _tenpais: &[Tenpai]) -> super::actor::ActionFuture {
+            Box::pin(std::future::pending())
+        }
+        fn get_config(&self) -> &Config {
+            &self.0
+        }
+    }
+
+    let rule = Rule {
+        time_base: 0.001,
+        ..east_rule()
+    };
+    let pending = Pending(config("Pending"));
+    let actors: [Box<dyn Actor>; SEAT] = [
+        Box::new(pending.clone()),
+        Box::new(pending.clone()),
+        Box::new(pending.clone()),
+        Box::new(pending),
+    ];
+    let recorder = Recorder::default();
+    let mut game = MahjongEngine::new(
+        0,
+        rule,
+        0.0,
+        actors,
+        vec![Box::new(recorder.clone())],
+        [false; SEAT],
+    );
+    game.run(); // 全員が時間切れでツモ切りを続けて終局する
+
+    // ツモ後の時間切れには必ずTimeoutが発生し,ツモ牌がそのまま打牌される
+    let events = recorder.events();
+    let mut n_checked = 0;
+    for w in events.windows(3) {
+        if let Event::Deal(deal) = &w[0] {
+            let Event::Timeout(timeout) = &w[1] else {
+                panic!("Timeout is expected after {:?}, but {:?}", deal, w[1]);
+            };
+            assert_eq!(timeout.seat, deal.seat);
+            let Event::Discard(discard) = &w[2] else {
+                panic!("Discard is expected after {:?}, but {:?}", timeout, w[2]);
+            };
+            assert_eq!(discard.seat, deal.seat);
+            assert_eq!(discard.tile, deal.tile);
+            assert!(discard.is_drawn && !discard.is_riichi);
+            n_checked += 1;
+        }
+    }
+    assert!(n_checked > 0);
+}
+
+#[test]
+fn test_invalid_action() {
+    use super::{
+        actor::Config,
+        listener::Listener,
+        testing::{config, east_rule},
+    };
+
+    // 常に手牌に存在しない牌を打牌するActor
+    #[derive(Clone)]
+    struct Invalid(Config);
+    impl Listener for Invalid {}
+    impl Actor for Invalid {
+        fn select(&mut self, _acts: &[Action], _tenpais: &[Tenpai]) -> super::actor::ActionFuture {
+            ready(Action::discard(Z8))
+        }
+        fn get_config(&self) -> &Config {
+            &self.0
+        }
+    }
+
+    let mut rule = east_rule();
+    let invalid = Invalid(config("Invalid"));
+    let actors: [Box<dyn Actor>; SEAT] = [
+        Box::new(invalid.clone()),
+        Box::new(invalid.clone()),
+        Box::new(invalid.clone()),
+        Box::new(invalid.clone()),
+    ];
+    let mut game = MahjongEngine::new(0, rule.clone(), 0.0, actors, vec![], [false; SEAT]);
+    game.run(); // 不正なアクションはツモ切りに置き換えられて終局する
+    assert!(game.get_violations().iter().all(|&n| n > 0));
+
+    // 上限に達した後は問い合わせずに自動で打牌
+    rule.max_violations = 3;
+    let actors: [Box<dyn Actor>; SEAT] = [
+        Box::new(invalid.clone()),
+        Box::new(invalid.clone()),
+        Box::new(invalid.clone()),
+        Box::new(invalid),
+    ];
+    let mut game = MahjongEngine::new(0, rule, 0.0, actors, vec![], [false; SEAT]);
+    game.run();
+    assert_eq!(game.get_violations(), [3; SEAT]);
+}
+
+#[test]
+fn test_pao() {
+    use std::{
+        fs::File,
+        io::{BufRead, BufReader},
+    };
+
+    use super::string::{meld_from_string, tiles_from_string};
+    use crate::hand::{YakuFlags, evaluate_hand};
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tests/pao_hands.txt");
+    let lines = BufReader::new(File::open(path).unwrap()).lines();
+    for line in lines.map_while(Result::ok) {
+        let exp = line.split('#').next().unwrap().replace(' ', "");
+        if exp.is_empty() {
+            continue; // 空行とコメント行はスキップ
+        }
+        let exps: Vec<&str> = exp.split('/').collect();
+        let mut it = exps[0].split(',');
+        let tiles = tiles_from_string(it.next().unwrap()).unwrap();
+        let melds: Vec<Meld> = it.map(|m| meld_from_string(m).unwrap()).collect();
+        let seats: Vec<Option<Seat>> = exps[1].split(',').map(|s| s.parse().ok()).collect();
+        let (winner, from) = (seats[0].unwrap(), seats[1].unwrap());
+        let honba = exps[2].parse().unwrap();
+        let expected: Vec<Point> = exps[3].split(',').map(|p| p.parse().unwrap()).collect();
+
+        let rule = Rule::default();
+        let (score_ctx, _) = evaluate_hand(
+            &tiles_to_tile_table(&tiles),
+            &melds,
+            &[],
+            &[],
+            0,
+            *tiles.last().unwrap(),
+            winner == from,
+            winner == 0,
+            WE,
+            calc_seat_wind(0, winner),
+            &YakuFlags::default(),
+            &rule,
+        )
+        .unwrap();
+        let paos = calc_pao_yakuman(seats[2], seats[3], &score_ctx);
+        let d_scores =
+            calc_win_delta_scores(SEAT, 0, winner, from, &score_ctx, &paos, honba, &rule);
+        assert_eq!(d_scores.to_vec(), expected, "{}", line);
+    }
+}
+
+#[test]
+fn test_seat_projection() {
+    use std::sync::{Arc, Mutex};
+
+    use super::{
+        actor::Config,
+        listener::Listener,
+        stage_controller::StageRef,
+        testing::{config, east_rule},
+    };
+
+    // 他家の手牌や牌山が見えていないかを確認するActor (omniscientの座席の場合はすべて見えているか)
+    #[derive(Clone)]
+    struct Peeker {
+        config: Config,
+        seat: Seat,
+        omniscient: bool,
+        n_checked: Arc<Mutex<usize>>,
+    }
+    impl Listener for Peeker {
+        fn notify_event(&mut self, stg: &Stage, event: &Event) {
+            if self.omniscient {
+                if let Event::New(ev) = event {
+                    assert!(!ev.wall.is_empty());
+                    assert!(stg.players.iter().all(|pl| pl.is_shown));
+                    *self.n_checked.lock().unwrap() += 1;
+                }
+                return;
+            }
+            match event {
+                Event::New(ev) => {
+                    assert!(ev.wall.is_empty() && ev.ura_dora_wall.is_empty());
+                    for s in 0..SEAT {
+                        if s != self.seat {
+                            assert!(ev.hands[s].iter().all(|&t| t == Z8));
+                        }
+                    }
+                }
+                Event::Deal(ev) if ev.seat != self.seat => assert_eq!(ev.tile, Z8),
+                _ => {}
+            }
+            for s in 0..SEAT {
+                assert!(s == self.seat || !stg.players[s].is_shown);
+            }
+            *self.n_checked.lock().unwrap() += 1;
+        }
+    }
+    impl Actor for Peeker {
+        fn init(&mut self, _stage: StageRef, seat: Seat) {
+            self.seat = seat;
+        }
+        fn select(&mut self, _acts: &[Action], _tenpais: &[Tenpai]) -> super::actor::ActionFuture {
+            ready(Action::nop())
+        }
+        fn get_config(&self) -> &Config {
+            &self.config
+        }
+    }
+
+    let rule = east_rule();
+    let n_checked = Arc::new(Mutex::new(0));
+    let peeker = Peeker {
+        config: config("Peeker"),
+        seat: NO_SEAT,
+        omniscient: false,
+        n_checked: n_checked.clone(),
+    };
+    let actors: [Box<dyn Actor>; SEAT] = [
+        Box::new(Peeker {
+            omniscient: true,
+            ..peeker.clone()
+        }),
+        Box::new(peeker.clone()),
+        Box::new(peeker.clone()),
+        Box::new(peeker),
+    ];
+    let omniscient = [true, false, false, false];
+    let mut game = MahjongEngine::new(0, rule, 0.0, actors, vec![], omniscient);
+    game.run();
+    assert!(*n_checked.lock().unwrap() > 0);
+    // エンジン側のStageにはすべての手牌が含まれる
+    assert!(game.get_stage().players.iter().all(|pl| pl.is_shown));
+}
+
+#[test]
+fn test_bust() {
+    use std::sync::{Arc, Mutex};
+
+    use super::testing::{FnListener, Greedy, east_rule};
+
+    // 局ごとの終了時の点数を記録して対戦を実行
+    let run = |rule: Rule| {
+        let rounds = Arc::new(Mutex::new(vec![]));
+        let r = rounds.clone();
+        let recorder = FnListener(move |stg: &Stage, event: &Event| {
+            if let Event::Win(_) | Event::Draw(_) = event {
+                r.lock().unwrap().push(get_scores(stg));
+            }
+        });
+        let actors: [Box<dyn Actor>; SEAT] = [
+            Box::new(Greedy::new(true)),
+            Box::new(Greedy::new(false)),
+            Box::new(Greedy::new(true)),
+            Box::new(Greedy::new(false)),
+        ];
+        let mut game = MahjongEngine::new(
+            1,
+            rule,
+            0.0,
+            actors,
+            vec![Box::new(recorder)],
+            [false; SEAT],
+        );
+        game.run();
+        rounds.lock().unwrap().clone()
+    };
+    // 誰かの点数がthresholdを下回った最初の局
+    let first_below = |rounds: &[[Score; SEAT]], threshold: Score| {
+        rounds
+            .iter()
+            .position(|sc| sc.iter().any(|&s| s < threshold))
+    };
+
+    // 飛びなしの場合は負の点数になっても続行
+    let rule = Rule {
+        initial_score: 1000,
+        bust: false,
+        ..east_rule()
+    };
+    let rounds_no_bust = run(rule.clone());
+    let i = first_below(&rounds_no_bust, 0).expect("no negative score");
+    assert!(i + 1 < rounds_no_bust.len(), "{:?}", rounds_no_bust);
+
+    // 飛びありの場合は0点未満になった局で終了
+    let rounds = run(Rule {
+        bust: true,
+        ..rule.clone()
+    });
+    assert_eq!(
+        first_below(&rounds, 0),
+        Some(rounds.len() - 1),
+        "{:?}",
+        rounds
+    );
+
+    // bust_scoreで飛びになる点数を変更
+    // 飛びなしの対戦で最初に負の点数になった局の最低点を閾値にすると,その局では終了しない
+    let min_score = *rounds_no_bust[i].iter().min().unwrap();
+    for bust_score in [1000, min_score] {
+        let rounds = run(Rule {
+            bust: true,
+            bust_score,
+            ..rule.clone()
+        });
+        let j = first_below(&rounds, bust_score);
+        if bust_score < 0 {
+            assert!(rounds.len() > i + 1, "{}: {:?}", bust_score, rounds);
+            assert!(j.is_none_or(|j| j == rounds.len() - 1));
+        } else {
+            assert_eq!(j, Some(rounds.len() - 1), "{}: {:?}", bust_score, rounds);
+        }
+    }
+}
+
+#[test]
+fn test_multiple_ron() {
+    use super::{
+        string::tiles_from_string,
+        testing::{Greedy, Recorder, east_rule},
+        wall::create_wall_preset,
+    };
+
+    // 親の1打目(東)に3人が七対子の単騎待ちでロン可能な牌山
+    let hands = [
+        "m789p789s789z2345",
+        "m112233445566z1",
+        "p112233445566z1",
+        "s112233445566z1",
+    ]
+    .map(|h| tiles_from_string(h).unwrap());
+    let wall = create_wall_preset(0, 0, &hands, &[Tile(TZ, 1)]);
+    let run = |rule: Rule, honba: usize| {
+        let actors: [Box<dyn Actor>; SEAT] = [
+            Box::new(Greedy::new(false)),
+            Box::new(Greedy::new(false)),
+            Box::new(Greedy::new(false)),
+            Box::new(Greedy::new(false)),
+        ];
+        let recorder = Recorder::default();
+        let mut game = MahjongEngine::new(
+            0,
+            rule,
+            0.0,
+            actors,
+            vec![Box::new(recorder.clone())],
+            [false; SEAT],
+        );
+        game.preset_walls = vec![wall.clone()];
+        game.next_round_info.honba = honba;
+        game.run();
+        // 最初の局の結果
+        recorder
+            .events()
+            .into_iter()
+            .find(|e| matches!(e, Event::Win(_) | Event::Draw(_)))
+            .unwrap()
+    };
+    let win = |rule: Rule, honba: usize| match run(rule, honba) {
+        Event::Win(ev) => ev,
+        e => panic!("win is expected, but {:?}", e),
+    };
+    let rule = east_rule();
+
+    // トリロン: 放銃者から近い順に全員が和了
+    let ev = win(rule.clone(), 0);
+    let seats: Vec<Seat> = ev.contexts.iter().map(|ctx| ctx.seat).collect();
+    assert_eq!(seats, [1, 2, 3]);
+    let base = ev.delta_scores;
+
+    // 頭ハネ: 放銃者の下家のみ和了
+    let ev = win(
+        Rule {
+            atamahane: true,
+            ..rule.clone()
+        },
+        0,
+    );
+    let seats: Vec<Seat> = ev.contexts.iter().map(|ctx| ctx.seat).collect();
+    assert_eq!(seats, [1]);
+
+    // 三家和: 3人同時のロンは途中流局
+    let sanchahou = Rule {
+        sanchahou: true,
+        ..rule.clone()
+    };
+    match run(sanchahou, 0) {
+        Event::Draw(ev) => assert_eq!(ev.draw_type, DrawType::Sanchaho),
+        e => panic!("draw is expected, but {:?}", e),
+    }
+
+    // 積み棒: 上家取りの場合は下家のみ,honba_per_winnerの場合は和了者全員が受け取る
+    let ev = win(rule.clone(), 2);
+    assert_eq!(ev.delta_scores[0], base[0] - 600);
+    assert_eq!(ev.delta_scores[1], base[1] + 600);
+    assert_eq!(ev.delta_scores[2..], base[2..]);
+    let honba_per_winner = Rule {
+        honba_per_winner: true,
+        ..rule
+    };
+    let ev = win(honba_per_winner, 2);
+    assert_eq!(ev.delta_scores[0], base[0] - 600 * 3);
+    for s in 1..SEAT {
+        assert_eq!(ev.delta_scores[s], base[s] + 600);
+    }
+}
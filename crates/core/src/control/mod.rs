@@ -4,6 +4,12 @@ pub mod common;
 pub mod engine;
 pub mod listener;
 pub mod possible_actions;
+pub mod rating;
+pub mod safety;
+pub mod sprt;
 pub mod stage_controller;
+pub mod stats;
 pub mod string;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod wall;
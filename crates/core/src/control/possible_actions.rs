@@ -183,8 +183,14 @@ fn check_nukidora(stg: &Stage) -> Vec<Action> {
         return vec![];
     }
 
+    let pl = &stg.players[stg.turn];
     let mut acts = vec![];
-    if stg.players[stg.turn].hand[TZ][WN] != 0 {
+    if pl.is_riichi {
+        // リーチ中はツモってきた北のみ抜くことが可能
+        if pl.drawn == Some(Tile(TZ, WN)) {
+            acts.push(Action::nukidora());
+        }
+    } else if pl.hand[TZ][WN] != 0 {
         acts.push(Action::nukidora());
     }
 
@@ -203,8 +209,11 @@ pub fn calc_possible_call_actions(stg: &Stage, can_meld: bool) -> [Vec<Action>;
     }
     // 打牌以外(加槓, 暗槓など), 牌山なし , 四槓散了(!can_meld)の場合は鳴き操作不可
     if stg.last_tile.unwrap().1 == ActionType::Discard && stg.wall_count != 0 && can_meld {
-        for (s, act) in check_chi(stg) {
-            acts_list[s].push(act);
+        // 3麻はチーなし
+        if !stg.rule.is_sanma {
+            for (s, act) in check_chi(stg) {
+                acts_list[s].push(act);
+            }
         }
         for (s, act) in check_pon(stg) {
             acts_list[s].push(act);
@@ -260,7 +269,7 @@ fn check_pon(stg: &Stage) -> Vec<(Seat, Action)> {
     let d = stg.last_tile.unwrap().2;
     let t = d.to_normal();
     let mut acts = vec![];
-    for s in 0..SEAT {
+    for s in 0..get_n_seat(stg) {
         let pl = &stg.players[s];
         if pl.hand[t.0][t.1] < 2 || stg.turn == s || pl.is_riichi {
             continue;
@@ -293,7 +302,7 @@ fn check_minkan(stg: &Stage) -> Vec<(Seat, Action)> {
     let d = stg.last_tile.unwrap().2;
     let t = d.to_normal();
     let mut acts = vec![];
-    for s in 0..SEAT {
+    for s in 0..get_n_seat(stg) {
         let pl = &stg.players[s];
         if pl.hand[t.0][t.1] != 3 || stg.turn == s || pl.is_riichi {
             continue;
@@ -311,7 +320,7 @@ fn check_minkan(stg: &Stage) -> Vec<(Seat, Action)> {
 
 fn check_ron(stg: &Stage) -> Vec<(Seat, Action)> {
     let mut acts = vec![];
-    for s in 0..SEAT {
+    for s in 0..get_n_seat(stg) {
         if evaluate_hand_ron(stg, &[], s).is_some() {
             acts.push((s, Action::ron()));
         }
@@ -372,6 +381,7 @@ pub fn calc_possible_tenpai_discards(
     pl: &Player,
     prevalent_wind: Tnum,
     seat_wind: Tnum,
+    rule: &Rule,
 ) -> Vec<Tenpai> {
     let mut comb: Vec<(Tile, Tile)> = vec![]; // (打牌, 和了牌)の組み合わせ
     for (d, wts) in calc_discards_to_win(&pl.hand) {
@@ -407,12 +417,14 @@ pub fn calc_possible_tenpai_discards(
             &pl.melds,
             &[],
             &[],
+            0,
             wt,
             false,
             false,
             prevalent_wind,
             seat_wind,
             &yf,
+            rule,
         );
         dec_tile(&mut hand, wt);
         inc_tile(&mut hand, d);
@@ -440,44 +452,13 @@ pub fn calc_possible_tenpai_discards(
 
 // 鳴き操作を適用前のStageに対して責任払いがその操作により発生するかどうかを判定
 pub fn check_pao_for_selected_action(stg: &Stage, seat: Seat, act: &Action) -> bool {
-    let pl = &stg.players[seat];
-    let t = act.tiles[0];
-
-    // 大三元
-    if t.is_doragon() {
-        let mut cnt = 0;
-        for m in &pl.melds {
-            if m.tiles[0].is_doragon() {
-                cnt += 1;
-            }
-        }
-        return cnt == 2;
-    }
-
-    // 大四喜
-    if t.is_wind() {
-        let mut cnt = 0;
-        for m in &pl.melds {
-            if m.tiles[0].is_wind() {
-                cnt += 1;
-            }
-        }
-        return cnt == 3;
-    }
-
-    // 四槓子
-    if act.ty == ActionType::Minkan {
-        let mut cnt = 0;
-        for m in &pl.melds {
-            match m.meld_type {
-                MeldType::Ankan | MeldType::Kakan | MeldType::Minkan => cnt += 1,
-                _ => {}
-            }
-        }
-        return cnt == 3;
-    }
-
-    false
+    let meld_type = match act.ty {
+        ActionType::Pon => MeldType::Pon,
+        ActionType::Minkan => MeldType::Minkan,
+        _ => return false,
+    };
+    let (is_pao, is_pao_kan) = check_pao(&stg.players[seat], meld_type, act.tiles[0]);
+    is_pao || is_pao_kan
 }
 
 #[test]
@@ -485,21 +466,23 @@ fn test_tenpai_discards() {
     use super::string::tiles_from_string;
     let tiles = tiles_from_string("m11235s123999p123").unwrap();
     println!("{:?}", tiles);
-    let mut pl = Player::default();
-    pl.hand = tiles_to_tile_table(&tiles);
-    pl.melds = vec![];
-    pl.discards = tiles_from_string("p1")
-        .unwrap()
-        .iter()
-        .map(|t| Discard {
-            step: 0,
-            tile: *t,
-            is_drawn: false,
-            meld: None,
-        })
-        .collect();
+    let pl = Player {
+        hand: tiles_to_tile_table(&tiles),
+        melds: vec![],
+        discards: tiles_from_string("p1")
+            .unwrap()
+            .iter()
+            .map(|t| Discard {
+                step: 0,
+                tile: *t,
+                is_drawn: false,
+                meld: None,
+            })
+            .collect(),
+        ..Default::default()
+    };
     let prevalent_wind = WE;
     let seat_wind = WE;
-    let tenpais = calc_possible_tenpai_discards(&pl, prevalent_wind, seat_wind);
+    let tenpais = calc_possible_tenpai_discards(&pl, prevalent_wind, seat_wind, &Rule::default());
     println!("{:#?}", tenpais);
 }
@@ -0,0 +1,295 @@
+use std::{collections::BTreeMap, fs, io::ErrorKind, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use super::actor::Actor;
+use crate::{model::*, util::misc::*};
+
+// 天鳳方式のレーティング(R)を永続化して管理
+// 変動値 = 試合数補正 * (順位点 + (卓平均R - 自分のR) / 40)
+// 試合数補正: 400試合未満は 1 - 試合数 * 0.002, 400試合以上は 0.2
+
+pub const INITIAL_RATE: f64 = 1500.0;
+
+// これより古いロックファイルは異常終了したプロセスが残したものとみなして削除
+const STALE_LOCK: Duration = Duration::from_secs(10);
+
+// キーから除外するActorの引数 (認証用の値がファイルに保存されないように)
+const SECRET_ARGS: &[&str] = &["token"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rating {
+    pub rate: f64,
+    pub n_game: usize,              // 対局数 (複数の座席に存在する場合も1試合で1回)
+    pub rank_counts: [usize; SEAT], // 座席ごとの順位の回数
+    pub sum_delta_score: i64,
+    pub last_played: u64, // 最後に対局したUnixTime(秒)
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rate: INITIAL_RATE,
+            n_game: 0,
+            rank_counts: [0; SEAT],
+            sum_delta_score: 0,
+            last_played: 0,
+        }
+    }
+}
+
+impl Rating {
+    pub fn avg_rank(&self) -> f64 {
+        let sum: usize = (0..SEAT).map(|r| (r + 1) * self.rank_counts[r]).sum();
+        sum as f64 / self.n_seat().max(1) as f64
+    }
+
+    pub fn avg_delta_score(&self) -> i64 {
+        self.sum_delta_score / self.n_seat().max(1) as i64
+    }
+
+    // 座席数の合計 (平均は座席単位)
+    fn n_seat(&self) -> usize {
+        self.rank_counts.iter().sum()
+    }
+}
+
+// 試合の結果 (キー, 順位(0~), 持ち点の増減)
+pub type GameResult = (String, usize, Score);
+
+// Actorのキー (Actorの式からtokenなどの引数を除外)
+pub fn actor_key(actor: &dyn Actor) -> String {
+    let conf = actor.get_config();
+    let arg_str = conf
+        .args
+        .iter()
+        .filter(|arg| !SECRET_ARGS.contains(&arg.name.as_str()))
+        .map(|arg| format!("{}={}", arg.name, arg.value))
+        .collect::<Vec<String>>()
+        .join(",");
+    format!("{}({})", conf.name, arg_str)
+}
+
+// 同じファイルを複数のプロセス(E -g, Sなど)が同時に更新する場合がある
+// saveではロック中にファイルを読み込み直して未保存の試合結果を適用してから一時ファイル経由で置き換える
+#[derive(Debug, Default)]
+pub struct RatingStore {
+    path: String,
+    ratings: BTreeMap<String, Rating>,
+    unsaved: Vec<(Vec<GameResult>, bool, u64)>, // (試合の結果, 3麻, 対局時刻)
+}
+
+impl RatingStore {
+    // ファイルが存在しない場合は空のRatingStoreを返却
+    pub fn load(path: &str) -> Res<Self> {
+        let ratings = match fs::read_to_string(path) {
+            Ok(s) => serde_json::from_str(&s)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            path: path.into(),
+            ratings,
+            unsaved: vec![],
+        })
+    }
+
+    pub fn save(&mut self) -> Res {
+        let _lock = FileLock::acquire(&format!("{}.lock", self.path))?;
+        let mut latest = Self::load(&self.path)?;
+        for (results, is_sanma, time) in &self.unsaved {
+            latest.apply(results, *is_sanma, *time);
+        }
+        let tmp = format!("{}.{}.tmp", self.path, std::process::id());
+        write_to_file(&tmp, &serde_json::to_string_pretty(&latest.ratings)?)?;
+        fs::rename(&tmp, &self.path)?;
+        self.ratings = latest.ratings;
+        self.unsaved.clear();
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Rating> {
+        self.ratings.get(key)
+    }
+
+    // 1試合の結果を反映 同じキーが複数の座席に存在する場合は試合前のRで計算した変動値をすべて加算
+    // (対局数は1回のみ加算,順位と持ち点の増減は座席ごとに加算)
+    pub fn update(&mut self, results: &[GameResult], is_sanma: bool) {
+        let now = unixtime_now() as u64;
+        self.apply(results, is_sanma, now);
+        self.unsaved.push((results.to_vec(), is_sanma, now));
+    }
+
+    fn apply(&mut self, results: &[GameResult], is_sanma: bool, time: u64) {
+        let rates: Vec<f64> = results
+            .iter()
+            .map(|(key, _, _)| self.get(key).map_or(INITIAL_RATE, |r| r.rate))
+            .collect();
+        let table_avg = rates.iter().sum::<f64>() / rates.len() as f64;
+        let deltas: Vec<f64> = results
+            .iter()
+            .zip(&rates)
+            .map(|((key, rank, _), &rate)| {
+                let n_game = self.get(key).map_or(0, |r| r.n_game);
+                calc_rate_delta(rate, table_avg, n_game, *rank, is_sanma)
+            })
+            .collect();
+
+        for (i, ((key, rank, delta_score), delta)) in results.iter().zip(deltas).enumerate() {
+            let r = self.ratings.entry(key.clone()).or_default();
+            r.rate += delta;
+            if results[..i].iter().all(|(k, _, _)| k != key) {
+                r.n_game += 1;
+            }
+            r.rank_counts[*rank] += 1;
+            r.sum_delta_score += *delta_score as i64;
+            r.last_played = time;
+        }
+    }
+
+    // Rの降順
+    pub fn leaderboard(&self) -> Vec<(&String, &Rating)> {
+        let mut res: Vec<_> = self.ratings.iter().collect();
+        res.sort_by(|(_, r0), (_, r1)| r1.rate.total_cmp(&r0.rate));
+        res
+    }
+}
+
+// ロックファイルによる排他制御 (dropで解除)
+struct FileLock {
+    path: String,
+}
+
+impl FileLock {
+    fn acquire(path: &str) -> Res<Self> {
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+            {
+                Ok(_) => return Ok(Self { path: path.into() }),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    let is_stale = fs::metadata(path)
+                        .and_then(|m| m.modified())
+                        .is_ok_and(|t| t.elapsed().is_ok_and(|d| d > STALE_LOCK));
+                    if is_stale {
+                        fs::remove_file(path).ok();
+                    } else {
+                        sleep(0.01);
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+pub fn calc_rate_delta(
+    rate: f64,
+    table_avg: f64,
+    n_game: usize,
+    rank: usize,
+    is_sanma: bool,
+) -> f64 {
+    let rank_point = if is_sanma {
+        [30.0, 0.0, -30.0, 0.0][rank]
+    } else {
+        [30.0, 10.0, -10.0, -30.0][rank]
+    };
+    let correction = if n_game < 400 {
+        1.0 - n_game as f64 * 0.002
+    } else {
+        0.2
+    };
+    correction * (rank_point + (table_avg - rate) / 40.0)
+}
+
+#[test]
+fn test_rating() {
+    let result = |key: &str, rank| (key.to_string(), rank, 0);
+    let mut store = RatingStore::default();
+    store.update(
+        &[
+            result("a", 0),
+            result("b", 1),
+            result("c", 2),
+            result("d", 3),
+        ],
+        false,
+    );
+    let rate = |store: &RatingStore, key| store.get(key).unwrap().rate;
+    assert_eq!(rate(&store, "a"), 1530.0);
+    assert_eq!(rate(&store, "d"), 1470.0);
+
+    // 卓平均より高いRのプレイヤーは同じ順位でも変動値が小さい
+    store.update(
+        &[
+            result("a", 0),
+            result("b", 1),
+            result("c", 2),
+            result("d", 3),
+        ],
+        false,
+    );
+    assert!(rate(&store, "a") - 1530.0 < 30.0);
+    assert!(rate(&store, "d") - 1470.0 > -30.0);
+
+    // 同じキーが複数の座席に存在する場合
+    store.update(&[result("e", 0), result("e", 1), result("e", 2)], true);
+    assert_eq!(store.get("e").unwrap().n_game, 1);
+    assert_eq!(store.get("e").unwrap().rank_counts, [1, 1, 1, 0]);
+    assert_eq!(rate(&store, "e"), INITIAL_RATE);
+    assert_eq!(store.get("e").unwrap().avg_rank(), 2.0);
+}
+
+#[test]
+fn test_rating_save() {
+    let dir = std::env::temp_dir().join(format!("rating_save_{}", std::process::id()));
+    let path = dir.join("rating.json").to_str().unwrap().to_string();
+    let result = |key: &str, rank| (key.to_string(), rank, 0);
+    let game = [
+        result("a", 0),
+        result("b", 1),
+        result("c", 2),
+        result("d", 3),
+    ];
+
+    // 複数のプロセスが同じファイルを読み込んで交互に保存しても更新が失われない
+    let n_thread = 4;
+    let n_game = 10;
+    let handles: Vec<_> = (0..n_thread)
+        .map(|_| {
+            let path = path.clone();
+            let game = game.clone();
+            std::thread::spawn(move || {
+                let mut store = RatingStore::load(&path).unwrap();
+                for _ in 0..n_game {
+                    store.update(&game, false);
+                    store.save().unwrap();
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    let store = RatingStore::load(&path).unwrap();
+    let files: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+    fs::remove_dir_all(&dir).unwrap();
+    for key in ["a", "b", "c", "d"] {
+        assert_eq!(store.get(key).unwrap().n_game, n_thread * n_game);
+    }
+    assert!(store.get("a").unwrap().rate > store.get("b").unwrap().rate);
+    assert_eq!(files.len(), 1); // ロックファイルと一時ファイルは残らない
+}
@@ -0,0 +1,187 @@
+use super::common::*;
+use crate::model::*;
+
+// 守備(放銃回避)のための牌の安全度の判定
+// 危険度はリーチ者に対する放銃率(%)の目安で,一般的な統計値をもとにした概算値
+
+// 早い巡目の捨て牌として扱う枚数 (早切りの外側の判定用)
+const EARLY_DISCARDS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafetyType {
+    Genbutsu,     // 現物 (リーチ後に他家が通した牌を含む)
+    Jihai,        // 字牌 (危険度は見えている枚数で変動)
+    NoChance,     // ノーチャンス (両面待ちに必要な牌が4枚見えている)
+    Suji,         // 筋 (4~6は両筋)
+    OneChance,    // ワンチャンス (両面待ちに必要な牌が3枚見えている)
+    EarlyOutside, // 早い巡目に手出しされた牌の外側
+    Dangerous,    // 上記以外の数牌 (無筋,片筋)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileSafety {
+    pub tile: Tile,
+    pub safety_type: SafetyType,
+    pub danger: f32, // 放銃率(%)の目安
+}
+
+// targetのプレイヤーに対する牌の安全度
+// 壁の判定に使用する見えている牌はstgの視点に依存 (Actorに渡されるStageの場合は自分の手牌を含む)
+// 複数の分類に該当する場合は危険度の最も低いものを返却
+pub fn calc_tile_safety(stg: &Stage, target: Seat, tile: Tile) -> TileSafety {
+    let t = tile.to_normal();
+    let visible = get_visible_tiles(stg);
+    let safeties = get_safe_tiles(stg, target);
+    let res = |safety_type, danger| TileSafety {
+        tile: t,
+        safety_type,
+        danger,
+    };
+
+    if safeties[t.0][t.1] > 0 {
+        return res(SafetyType::Genbutsu, 0.0);
+    }
+
+    if t.is_hornor() {
+        let n_live = TILE - visible[t.0][t.1].min(TILE);
+        return res(SafetyType::Jihai, [0.0, 0.8, 2.0, 3.5, 4.5][n_live]);
+    }
+
+    // 無筋の危険度 (1,9 | 2,8 | 3,7 | 4,5,6)
+    let ni = t.1;
+    let dist = ni.min(10 - ni);
+    let musuji = [0.0, 5.5, 7.5, 8.5, 12.0, 12.0][dist];
+
+    let mut cands = vec![res(SafetyType::Dangerous, musuji)];
+
+    // 筋
+    let is_safe = |n: usize| (1..TNUM).contains(&n) && safeties[t.0][n] > 0;
+    let suji_low = ni <= 3 || is_safe(ni - 3);
+    let suji_high = ni >= 7 || is_safe(ni + 3);
+    if suji_low && suji_high {
+        let danger = [0.0, 1.8, 3.8, 5.1, 3.0, 3.0][dist];
+        cands.push(res(SafetyType::Suji, danger));
+    } else if (4..=6).contains(&ni) && (suji_low || suji_high) {
+        cands.push(res(SafetyType::Dangerous, 7.5)); // 片筋
+    }
+
+    // 壁 (両面待ちのターツ[ni+1,ni+2]と[ni-2,ni-1]のそれぞれに対して最も見えている牌の枚数)
+    let n_visible = |n: usize| visible[t.0][n];
+    let mut wall = TILE;
+    if ni + 2 < TNUM {
+        wall = wall.min(n_visible(ni + 1).max(n_visible(ni + 2)));
+    }
+    if ni >= 3 {
+        wall = wall.min(n_visible(ni - 1).max(n_visible(ni - 2)));
+    }
+    if wall >= TILE {
+        let danger = if dist == 1 { 1.0 } else { 1.5 }; // 単騎,シャンポン,嵌張のみ
+        cands.push(res(SafetyType::NoChance, danger));
+    } else if wall == TILE - 1 {
+        cands.push(res(SafetyType::OneChance, musuji * 0.5));
+    }
+
+    // 早切りの外側
+    let is_early_outside = stg.players[target]
+        .discards
+        .iter()
+        .take(EARLY_DISCARDS)
+        .filter(|d| !d.is_drawn)
+        .map(|d| d.tile.to_normal())
+        .any(|d| d.0 == t.0 && d.is_simple() && (ni < d.1 && d.1 <= 5 || 5 <= d.1 && d.1 < ni));
+    if is_early_outside {
+        cands.push(res(SafetyType::EarlyOutside, musuji * 0.7));
+    }
+
+    cands
+        .into_iter()
+        .min_by(|a, b| a.danger.total_cmp(&b.danger))
+        .unwrap()
+}
+
+// targetのプレイヤーに対するすべての牌の安全度 (字牌は白發中まで)
+pub fn calc_safety_table(stg: &Stage, target: Seat) -> Vec<TileSafety> {
+    let mut res = vec![];
+    for ti in 0..TYPE {
+        for ni in 1..TNUM {
+            if ti == TZ && ni > DR {
+                break;
+            }
+            res.push(calc_tile_safety(stg, target, Tile(ti, ni)));
+        }
+    }
+    res
+}
+
+// seat以外のリーチ者全員に対する放銃率の目安 (%) リーチ者がいない場合は0
+pub fn calc_riichi_danger(stg: &Stage, seat: Seat, tile: Tile) -> f32 {
+    let mut safe = 1.0;
+    for s in 0..get_n_seat(stg) {
+        if s != seat && stg.players[s].is_riichi {
+            safe *= 1.0 - calc_tile_safety(stg, s, tile).danger / 100.0;
+        }
+    }
+    (1.0 - safe) * 100.0
+}
+
+// targetのプレイヤーに対する現物 (自身の捨て牌とリーチ後に他家が捨てた牌)
+fn get_safe_tiles(stg: &Stage, target: Seat) -> TileTable {
+    let pl = &stg.players[target];
+    let riichi_step = pl.riichi.map(|i| pl.discards[i].step);
+    let mut tt = TileTable::default();
+    for p in &stg.players {
+        for d in &p.discards {
+            let is_passed = riichi_step.is_some_and(|step| d.step > step);
+            if p.seat == target || is_passed {
+                let t = d.tile.to_normal();
+                tt[t.0][t.1] += 1;
+            }
+        }
+    }
+    tt
+}
+
+#[test]
+fn test_safety() {
+    let mut stg = Stage::default();
+    for s in 0..SEAT {
+        stg.players[s].seat = s;
+    }
+    let discard = |step, tile, is_drawn| Discard {
+        step,
+        tile,
+        is_drawn,
+        meld: None,
+    };
+    // 座席1: p3(手出し), m4, z1 でリーチ
+    let pl = &mut stg.players[1];
+    pl.discards = vec![
+        discard(1, Tile(TP, 3), false),
+        discard(5, Tile(TM, 4), true),
+        discard(9, Tile(TZ, 1), false),
+    ];
+    pl.riichi = Some(2);
+    pl.is_riichi = true;
+    // 座席2がリーチ後にs9を通す
+    stg.players[2].discards = vec![discard(10, Tile(TS, 9), false)];
+    // 座席0の手牌にs3が4枚
+    stg.tile_states[TS][3] = [TileState::H(0); TILE];
+
+    let safety = |t: Tile| calc_tile_safety(&stg, 1, t).safety_type;
+    assert_eq!(safety(Tile(TM, 4)), SafetyType::Genbutsu);
+    assert_eq!(safety(Tile(TS, 9)), SafetyType::Genbutsu);
+    assert_eq!(safety(Tile(TM, 1)), SafetyType::Suji);
+    assert_eq!(safety(Tile(TM, 7)), SafetyType::Suji);
+    assert_eq!(safety(Tile(TM, 5)), SafetyType::Dangerous);
+    assert_eq!(safety(Tile(TS, 1)), SafetyType::NoChance);
+    assert_eq!(safety(Tile(TS, 2)), SafetyType::NoChance);
+    assert_eq!(safety(Tile(TP, 2)), SafetyType::EarlyOutside);
+    assert_eq!(safety(Tile(TZ, 2)), SafetyType::Jihai);
+
+    // 危険度の大小関係
+    let danger = |t: Tile| calc_riichi_danger(&stg, 0, t);
+    assert_eq!(danger(Tile(TM, 4)), 0.0);
+    assert!(danger(Tile(TM, 1)) < danger(Tile(TM, 5)));
+    assert!(danger(Tile(TS, 1)) < danger(Tile(TS, 4)));
+    assert_eq!(calc_riichi_danger(&stg, 1, Tile(TM, 5)), 0.0);
+}
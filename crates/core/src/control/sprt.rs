@@ -0,0 +1,123 @@
+// 逐次確率比検定(SPRT)
+// 1試合ごとの差 x (正の値ほど候補が優れている) の平均μについて H0: μ = 0, H1: μ = delta を検定
+// 分散は標本分散で近似し,対数尤度比 LLR = delta / σ^2 * (Σx - n * delta / 2) が境界を超えた時点で判定
+
+// 標本分散が安定するまでは判定しない
+pub const SPRT_MIN_SAMPLES: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Undecided,
+    H0, // 差はdelta未満
+    H1, // 差はdelta以上
+}
+
+#[derive(Debug)]
+pub struct Sprt {
+    pub delta: f64,
+    pub lower: f64, // ln(β / (1 - α))
+    pub upper: f64, // ln((1 - β) / α)
+    n: usize,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl Sprt {
+    // 第1種,第2種の過誤の確率はどちらも 1 - confidence
+    pub fn new(delta: f64, confidence: f64) -> Self {
+        let alpha = 1.0 - confidence;
+        let beta = alpha;
+        Self {
+            delta,
+            lower: (beta / (1.0 - alpha)).ln(),
+            upper: ((1.0 - beta) / alpha).ln(),
+            n: 0,
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, x: f64) {
+        self.n += 1;
+        self.sum += x;
+        self.sum_sq += x * x;
+    }
+
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.sum / self.n as f64
+    }
+
+    pub fn variance(&self) -> f64 {
+        let n = self.n as f64;
+        (self.sum_sq - n * self.mean() * self.mean()) / (n - 1.0)
+    }
+
+    pub fn llr(&self) -> f64 {
+        if self.n < 2 {
+            return 0.0;
+        }
+        let var = self.variance();
+        if var <= 0.0 {
+            // 差が一定の場合はどちらの仮説に近いかで判定
+            return if self.mean() > self.delta / 2.0 {
+                f64::INFINITY
+            } else {
+                f64::NEG_INFINITY
+            };
+        }
+        self.delta / var * (self.sum - self.n as f64 * self.delta / 2.0)
+    }
+
+    pub fn verdict(&self) -> Verdict {
+        if self.n < SPRT_MIN_SAMPLES {
+            return Verdict::Undecided;
+        }
+        let llr = self.llr();
+        if llr >= self.upper {
+            Verdict::H1
+        } else if llr <= self.lower {
+            Verdict::H0
+        } else {
+            Verdict::Undecided
+        }
+    }
+}
+
+#[test]
+fn test_sprt() {
+    // 交互に delta ± 1 を与えると平均はdelta
+    let mut sprt = Sprt::new(0.5, 0.95);
+    let mut n = 0;
+    while sprt.verdict() == Verdict::Undecided {
+        sprt.push(if n % 2 == 0 { 1.5 } else { -0.5 });
+        n += 1;
+        assert!(n < 10000);
+    }
+    assert_eq!(sprt.verdict(), Verdict::H1);
+
+    // 平均0
+    let mut sprt = Sprt::new(0.5, 0.95);
+    let mut n = 0;
+    while sprt.verdict() == Verdict::Undecided {
+        sprt.push(if n % 2 == 0 { 1.0 } else { -1.0 });
+        n += 1;
+        assert!(n < 10000);
+    }
+    assert_eq!(sprt.verdict(), Verdict::H0);
+    assert!(sprt.len() >= SPRT_MIN_SAMPLES);
+
+    // 差が一定
+    let mut sprt = Sprt::new(0.5, 0.95);
+    for _ in 0..SPRT_MIN_SAMPLES {
+        sprt.push(1.0);
+    }
+    assert_eq!(sprt.verdict(), Verdict::H1);
+}
@@ -31,15 +31,29 @@ impl StageRef {
 #[derive(Debug)]
 pub struct StageController {
     stage: Arc<RwLock<Stage>>,
+    seat_stages: [Arc<RwLock<Stage>>; SEAT], // 各Actorから見えるStage (他家の手牌と牌山は不明, omniscientの座席はstageを共有)
     actors: [Box<dyn Actor>; SEAT],
     listeners: Vec<Box<dyn Listener>>,
 }
 
 impl StageController {
-    pub fn new(actors: [Box<dyn Actor>; SEAT], listeners: Vec<Box<dyn Listener>>) -> Self {
+    // omniscientがtrueの座席のActorには秘匿情報(他家の手牌,牌山)を取り除かないStageとEventを通知 (デバッグ用)
+    pub fn new(
+        actors: [Box<dyn Actor>; SEAT],
+        listeners: Vec<Box<dyn Listener>>,
+        omniscient: [bool; SEAT],
+    ) -> Self {
         let stage = Arc::new(RwLock::new(Stage::default()));
+        let seat_stages = std::array::from_fn(|s| {
+            if omniscient[s] {
+                stage.clone()
+            } else {
+                Arc::new(RwLock::new(Stage::default()))
+            }
+        });
         Self {
             stage,
+            seat_stages,
             actors,
             listeners,
         }
@@ -66,7 +80,7 @@ impl StageController {
         if let Event::New(_) = event {
             for s in 0..SEAT {
                 let stgref = StageRef {
-                    stage: Some(self.stage.clone()),
+                    stage: Some(self.seat_stages[s].clone()),
                 };
                 self.actors[s].init(stgref, s);
             }
@@ -76,6 +90,23 @@ impl StageController {
         // これはActorがStageRefから獲得したGuardをドロップし忘れた場合や
         // 非同期で動作しているActorの反応を待たずに他の高優先度のactionが選択された場合に起こる
         apply_event(&mut self.stage.try_write().unwrap(), event);
+        // Actorには座席ごとに秘匿情報を取り除いたイベントのみを適用
+        // 秘匿情報を含まないイベントは複製せずにそのまま使用
+        let seat_events: [Option<Event>; SEAT] = std::array::from_fn(|s| {
+            if self.is_shared(s) || !has_secret(event) {
+                None
+            } else {
+                Some(project_event(event, s))
+            }
+        });
+        for s in 0..SEAT {
+            if !self.is_shared(s) {
+                apply_event(
+                    &mut self.seat_stages[s].try_write().unwrap(),
+                    seat_events[s].as_ref().unwrap_or(event),
+                );
+            }
+        }
 
         let stg = self.stage.try_read().unwrap();
         // Actorより先にListenrsにイベントを通知
@@ -83,11 +114,21 @@ impl StageController {
         for listener in &mut self.listeners {
             listener.notify_event(&stg, event);
         }
-        for actor in &mut self.actors {
-            actor.notify_event(&stg, event);
+        for (s, actor) in self.actors.iter_mut().enumerate() {
+            if Arc::ptr_eq(&self.seat_stages[s], &self.stage) {
+                actor.notify_event(&stg, event); // omniscient
+            } else {
+                let stg = self.seat_stages[s].try_read().unwrap();
+                actor.notify_event(&stg, seat_events[s].as_ref().unwrap_or(event));
+            }
         }
     }
 
+    #[inline]
+    fn is_shared(&self, seat: Seat) -> bool {
+        Arc::ptr_eq(&self.seat_stages[seat], &self.stage)
+    }
+
     pub fn query_action(
         &mut self,
         seat: Seat,
@@ -100,6 +141,10 @@ impl StageController {
     pub fn expire_action(&mut self, seat: Seat) {
         self.actors[seat].expire();
     }
+
+    pub fn reject_action(&mut self, seat: Seat, error: &ActionError) {
+        self.actors[seat].reject(error);
+    }
 }
 
 pub fn apply_event(stg: &mut Stage, event: &Event) {
@@ -114,11 +159,40 @@ pub fn apply_event(stg: &mut Stage, event: &Event) {
         Event::Win(ev) => event_win(stg, ev),
         Event::Draw(ev) => event_draw(stg, ev),
         Event::End(ev) => event_end(stg, ev),
+        Event::Timeout(ev) => event_timeout(stg, ev),
     }
     update_after_turn_action(stg, event);
     stg.step += 1;
 }
 
+// project_eventで内容が変化する可能性のあるイベント
+#[inline]
+fn has_secret(event: &Event) -> bool {
+    matches!(event, Event::New(_) | Event::Deal(_))
+}
+
+// 座席seatのプレイヤーから見えるイベントを生成
+// 他家の手牌とツモ牌は不明な牌(Z8)になり,牌山は削除される (NO_SEATの場合はすべての手牌が不明)
+pub fn project_event(event: &Event, seat: Seat) -> Event {
+    let mut event = event.clone();
+    match &mut event {
+        Event::New(ev) => {
+            for s in 0..SEAT {
+                if s != seat {
+                    ev.hands[s].fill(Z8);
+                }
+            }
+            ev.wall = vec![];
+            ev.dora_wall = vec![];
+            ev.ura_dora_wall = vec![];
+            ev.replacement_wall = vec![];
+        }
+        Event::Deal(ev) if ev.seat != seat => ev.tile = Z8,
+        _ => {}
+    }
+    event
+}
+
 // [Event]
 fn event_begin(_stg: &mut Stage, _event: &EventBegin) {}
 
@@ -129,13 +203,14 @@ fn event_new(stg: &mut Stage, event: &EventNew) {
     stg.dealer = event.dealer;
     stg.honba = event.honba;
     stg.riichi_sticks = event.riichi_sticks;
-    stg.turn = (event.dealer + 3) % SEAT; // 親の14枚目(ツモ)でturn=dealerになる
+    let n_seat = get_n_seat(stg);
+    stg.turn = (event.dealer + n_seat - 1) % n_seat; // 親の14枚目(ツモ)でturn=dealerになる
     stg.wall_count = event.wall_count;
     stg.doras = event.doras.clone();
     update_scores(stg, &event.scores);
 
-    // プレイヤー情報
-    for s in 0..SEAT {
+    // プレイヤー情報 (3麻の場合,座席3は空席)
+    for s in 0..n_seat {
         let ph = &event.hands[s];
         let pl = &mut stg.players[s];
         pl.seat = s;
@@ -160,7 +235,7 @@ fn event_new(stg: &mut Stage, event: &EventNew) {
     for &d in &event.doras {
         table_edit(stg, d, U, R);
     }
-    for s in 0..SEAT {
+    for s in 0..n_seat {
         let ph = &event.hands[s];
         if stg.players[s].is_shown {
             for &t in ph {
@@ -270,11 +345,20 @@ fn event_meld(stg: &mut Stage, event: &EventMeld) {
     }
 
     let s = event.seat;
-    stg.turn = s;
-    let pl = &mut stg.players[s];
     if event.is_pao {
-        pl.pao = Some(stg.turn);
+        // 責任払いの対象は鳴かれた牌を捨てたプレイヤー
+        let from = stg.last_tile.unwrap().0;
+        let pl = &mut stg.players[s];
+        let (is_pao, is_pao_kan) = check_pao(pl, event.meld_type, event.consumed[0]);
+        if is_pao {
+            pl.pao = Some(from);
+        }
+        if is_pao_kan {
+            pl.pao_kan = Some(from);
+        }
     }
+    stg.turn = s;
+    let pl = &mut stg.players[s];
 
     let mut idx; // Vec<Meld>のインデックス
     match event.meld_type {
@@ -325,7 +409,8 @@ fn event_meld(stg: &mut Stage, event: &EventMeld) {
             idx = 0;
             let t = event.consumed[0];
             for m in &mut pl.melds {
-                if m.tiles[0] == t || m.tiles[1] == t {
+                // 赤5を含むポンに通常の5を加槓する場合(またはその逆)も考慮して正規化して比較
+                if m.meld_type == MeldType::Pon && m.tiles[0].to_normal() == t.to_normal() {
                     m.step = stg.step;
                     m.meld_type = MeldType::Kakan;
                     m.tiles.push(t);
@@ -390,6 +475,8 @@ fn event_draw(stg: &mut Stage, event: &EventDraw) {
 
 fn event_end(_stg: &mut Stage, _event: &EventEnd) {}
 
+fn event_timeout(_stg: &mut Stage, _event: &EventTimeout) {}
+
 // [Utility]
 fn table_edit(stg: &mut Stage, tile: Tile, old: TileState, new: TileState) {
     let tn = tile.to_normal();
@@ -439,7 +526,10 @@ fn update_after_turn_action(stg: &mut Stage, event: &Event) {
 
 fn update_after_discard_completed(stg: &mut Stage) {
     // 他のプレイヤーの捨て牌,または加槓した牌の見逃しフリテン
-    if let Some((s, ActionType::Discard | ActionType::Kakan, t)) = stg.last_tile {
+    // 3麻の北抜きも同様 (北でのロンが可能)
+    if let Some((s, ActionType::Discard | ActionType::Kakan | ActionType::Nukidora, t)) =
+        stg.last_tile
+    {
         for s2 in 0..SEAT {
             let pl = &mut stg.players[s2];
             if pl.winning_tiles.contains(&t) {
@@ -465,10 +555,12 @@ fn update_scores(stg: &mut Stage, points: &[Point; SEAT]) {
         stg.players[s].score += points[s];
     }
 
-    let scores: Vec<i32> = stg.players.iter().map(|pl| pl.score).collect();
+    // 3麻の場合,空席(座席3)は順位の計算から除外して最下位扱い
+    let n_seat = get_n_seat(stg);
+    let scores: Vec<i32> = stg.players[..n_seat].iter().map(|pl| pl.score).collect();
     let ranks = rank_by_rank_vec(&scores);
     for s in 0..SEAT {
-        stg.players[s].rank = ranks[s];
+        stg.players[s].rank = if s < n_seat { ranks[s] } else { s };
     }
 }
 
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::*;
+
+// 1人のプレイヤーの対局統計 (集計はStatsCollector(Listener)が行う)
+// 局単位の指標は同じ試合内の局を独立とみなして標準誤差を計算しているため,実際の誤差よりやや小さくなる
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub n_game: usize,
+    pub n_kyoku: usize,
+    pub n_win: usize,
+    pub n_tsumo: usize,
+    pub n_deal_in: usize,
+    pub n_riichi: usize,     // 立直した局の数
+    pub n_call: usize,       // 副露(暗槓を除く)した局の数
+    pub n_exhaustive: usize, // 荒牌平局の数
+    pub n_tenpai: usize,     // 荒牌平局時に聴牌していた数
+    pub sum_win_score: f64,  // 和了による収入 (本場,供託を含む)
+    pub sum_sq_win_score: f64,
+    pub sum_deal_in_score: f64, // 放銃による支出 (ダブロンの場合は合計)
+    pub sum_sq_deal_in_score: f64,
+    pub rank_counts: [usize; SEAT],           // 順位ごとの回数
+    pub yaku_counts: BTreeMap<String, usize>, // 和了に含まれていた役(ドラを含む)の回数
+}
+
+impl Stats {
+    pub fn merge(&mut self, other: &Stats) {
+        self.n_game += other.n_game;
+        self.n_kyoku += other.n_kyoku;
+        self.n_win += other.n_win;
+        self.n_tsumo += other.n_tsumo;
+        self.n_deal_in += other.n_deal_in;
+        self.n_riichi += other.n_riichi;
+        self.n_call += other.n_call;
+        self.n_exhaustive += other.n_exhaustive;
+        self.n_tenpai += other.n_tenpai;
+        self.sum_win_score += other.sum_win_score;
+        self.sum_sq_win_score += other.sum_sq_win_score;
+        self.sum_deal_in_score += other.sum_deal_in_score;
+        self.sum_sq_deal_in_score += other.sum_sq_deal_in_score;
+        for r in 0..SEAT {
+            self.rank_counts[r] += other.rank_counts[r];
+        }
+        for (name, count) in &other.yaku_counts {
+            *self.yaku_counts.entry(name.clone()).or_default() += count;
+        }
+    }
+
+    pub fn add_win(&mut self, score: Point, is_drawn: bool, yakus: &[Yaku]) {
+        let score = score as f64;
+        self.n_win += 1;
+        if is_drawn {
+            self.n_tsumo += 1;
+        }
+        self.sum_win_score += score;
+        self.sum_sq_win_score += score * score;
+        for y in yakus {
+            *self.yaku_counts.entry(y.name.clone()).or_default() += 1;
+        }
+    }
+
+    pub fn add_deal_in(&mut self, score: Point) {
+        let score = score as f64;
+        self.n_deal_in += 1;
+        self.sum_deal_in_score += score;
+        self.sum_sq_deal_in_score += score * score;
+    }
+
+    // (指標名, 値, 標準誤差) の一覧
+    // 割合は二項分布,平均は標本分散から標準誤差を計算 (標本がない場合はNaN)
+    pub fn metrics(&self, n_seat: usize) -> Vec<(String, f64, f64)> {
+        let mut res = vec![
+            ("win".into(), ratio(self.n_win, self.n_kyoku)),
+            ("tsumo".into(), ratio(self.n_tsumo, self.n_win)),
+            ("deal_in".into(), ratio(self.n_deal_in, self.n_kyoku)),
+            ("riichi".into(), ratio(self.n_riichi, self.n_kyoku)),
+            ("call".into(), ratio(self.n_call, self.n_kyoku)),
+            ("tenpai".into(), ratio(self.n_tenpai, self.n_exhaustive)),
+            (
+                "win_score".into(),
+                average(self.sum_win_score, self.sum_sq_win_score, self.n_win),
+            ),
+            (
+                "deal_in_score".into(),
+                average(
+                    self.sum_deal_in_score,
+                    self.sum_sq_deal_in_score,
+                    self.n_deal_in,
+                ),
+            ),
+        ];
+        for r in 0..n_seat {
+            res.push((
+                format!("rank{}", r + 1),
+                ratio(self.rank_counts[r], self.n_game),
+            ));
+        }
+        for (name, &count) in &self.yaku_counts {
+            res.push((format!("yaku:{}", name), ratio(count, self.n_win)));
+        }
+        res.into_iter().map(|(k, (v, se))| (k, v, se)).collect()
+    }
+}
+
+fn ratio(k: usize, n: usize) -> (f64, f64) {
+    let n = n as f64;
+    let p = k as f64 / n;
+    (p, (p * (1.0 - p) / n).sqrt())
+}
+
+fn average(sum: f64, sum_sq: f64, n: usize) -> (f64, f64) {
+    let n = n as f64;
+    let mean = sum / n;
+    let var = (sum_sq - n * mean * mean) / (n - 1.0);
+    (mean, (var.max(0.0) / n).sqrt())
+}
+
+#[test]
+fn test_stats() {
+    let yaku = |name: &str| Yaku {
+        name: name.into(),
+        fan: 1,
+    };
+    let mut a = Stats {
+        n_game: 1,
+        n_kyoku: 4,
+        ..Default::default()
+    };
+    a.add_win(1000, true, &[yaku("立直"), yaku("門前清自摸和")]);
+    a.add_win(3000, false, &[yaku("立直")]);
+    a.add_deal_in(2000);
+    a.rank_counts[0] += 1;
+    let mut b = a.clone();
+    b.merge(&a);
+    assert_eq!(b.n_kyoku, 8);
+    assert_eq!(b.yaku_counts["立直"], 4);
+
+    let metrics = b.metrics(4);
+    let get = |name: &str| metrics.iter().find(|m| m.0 == name).unwrap();
+    assert_eq!(get("win").1, 0.5);
+    assert_eq!(get("tsumo").1, 0.5);
+    assert_eq!(get("win_score").1, 2000.0);
+    assert!((get("win_score").2 - (4000000.0f64 / 3.0 / 4.0).sqrt()).abs() < 1e-6);
+    assert_eq!(get("rank1"), &("rank1".into(), 1.0, 0.0));
+    assert_eq!(get("yaku:立直").1, 1.0);
+    assert!(get("tenpai").1.is_nan()); // 荒牌平局なし
+}
@@ -190,7 +190,7 @@ pub fn meld_to_string(m: &Meld, s: Seat) -> String {
 fn test_tiletable() {
     use super::common::{tiles_from_tile_table, tiles_to_tile_table};
     let hand_str = "p34777s1230567z66";
-    let hand = tiles_from_string(&hand_str).unwrap();
+    let hand = tiles_from_string(hand_str).unwrap();
     let tt = tiles_to_tile_table(&hand);
     let hand2 = tiles_from_tile_table(&tt);
     assert_eq!(hand, hand2);
@@ -199,7 +199,7 @@ fn test_tiletable() {
 #[test]
 fn test_tiles_to_string() {
     let hand_str = "p34777s1230567z66";
-    let hand = tiles_from_string(&hand_str).unwrap();
+    let hand = tiles_from_string(hand_str).unwrap();
     let hand_str2 = tiles_to_string(&hand);
     assert_eq!(hand_str, hand_str2);
 }
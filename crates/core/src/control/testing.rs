@@ -0,0 +1,144 @@
+// テスト用のActor,Listenerとヘルパー関数
+// 他クレートのテストからはfeature "testing"を有効にして使用する.
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use super::{
+    actor::{ActionFuture, Actor, Config, ready},
+    common::tiles_from_tile_table,
+    listener::Listener,
+    stage_controller::StageRef,
+};
+use crate::{
+    model::*,
+    util::{
+        connection::{Connection, Message},
+        misc::sleep,
+    },
+};
+
+// 東風戦,飛びありのルール
+pub fn east_rule() -> Rule {
+    Rule {
+        round: 1,
+        initial_score: 25000,
+        settlement_score: 30000,
+        bust: true,
+        ..Default::default()
+    }
+}
+
+pub fn config(name: &str) -> Config {
+    Config {
+        name: name.into(),
+        args: vec![],
+    }
+}
+
+// 和了,立直を常に選択し,callがtrueの場合は可能な限り鳴き,槓も選択するActor
+// 打牌はツモ切りのみ (チー,ポンの後は喰い替えにならない牌を打牌)
+#[derive(Clone)]
+pub struct Greedy {
+    config: Config,
+    stage: StageRef,
+    call: bool,
+}
+
+impl Greedy {
+    pub fn new(call: bool) -> Self {
+        Self {
+            config: config("Greedy"),
+            stage: StageRef::default(),
+            call,
+        }
+    }
+}
+
+impl Listener for Greedy {}
+
+impl Actor for Greedy {
+    fn init(&mut self, stage: StageRef, _seat: Seat) {
+        self.stage = stage;
+    }
+
+    fn select(&mut self, acts: &[Action], _tenpais: &[Tenpai]) -> ActionFuture {
+        use ActionType::*;
+        if let Some(act) = acts.iter().find(|a| matches!(a.ty, Tsumo | Ron)) {
+            return ready(act.clone());
+        }
+        if let Some(act) = acts.iter().find(|a| a.ty == Riichi) {
+            return ready(Action::riichi(*act.tiles.last().unwrap()));
+        }
+        if self.call
+            && let Some(act) = acts
+                .iter()
+                .rev()
+                .find(|a| !matches!(a.ty, Nop | Discard | Kyushukyuhai))
+        {
+            return ready(act.clone());
+        }
+        if acts.iter().any(|a| a.ty == Nop) {
+            return ready(Action::nop()); // ツモ切り or スキップ
+        }
+        // チー,ポンの後の打牌 (喰い替えになる牌は除外)
+        let stg = self.stage.lock().unwrap();
+        let restricted = &acts.iter().find(|a| a.ty == Discard).unwrap().tiles;
+        let hand = tiles_from_tile_table(&stg.players[stg.turn].hand);
+        let t = *hand.iter().rev().find(|t| !restricted.contains(t)).unwrap();
+        ready(Action::discard(t))
+    }
+
+    fn get_config(&self) -> &Config {
+        &self.config
+    }
+}
+
+// 通知されたイベントを記録するListener
+#[derive(Clone, Default)]
+pub struct Recorder(pub Arc<Mutex<Vec<Event>>>);
+
+impl Recorder {
+    pub fn events(&self) -> Vec<Event> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Listener for Recorder {
+    fn notify_event(&mut self, _stg: &Stage, event: &Event) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
+
+// イベントごとにクロージャを呼び出すListener (Stageを参照する記録用)
+pub struct FnListener<F>(pub F);
+
+impl<F: FnMut(&Stage, &Event) + Send> Listener for FnListener<F> {
+    fn notify_event(&mut self, stg: &Stage, event: &Event) {
+        (self.0)(stg, event);
+    }
+}
+
+// Nop以外のメッセージを受信するまで待機 (最大3秒, タイムアウトの場合はNoConnection)
+pub fn wait_message(conn: &mut dyn Connection) -> Message {
+    for _ in 0..300 {
+        match conn.recv() {
+            Message::Nop | Message::NoConnection => sleep(0.01),
+            msg => return msg,
+        }
+    }
+    Message::NoConnection
+}
+
+// Actorが選択したアクションを待機 (最大5秒)
+pub fn wait_action(mut fut: ActionFuture) -> Action {
+    let mut cx = Context::from_waker(Waker::noop());
+    for _ in 0..500 {
+        if let Poll::Ready(act) = fut.as_mut().poll(&mut cx) {
+            return act;
+        }
+        sleep(0.01);
+    }
+    panic!("no response from actor");
+}
@@ -3,7 +3,8 @@ use rand::prelude::*;
 use super::{common::dec_tile, string::tiles_from_string};
 use crate::model::*;
 
-pub fn create_wall(seed: u64, n_red5: usize) -> Vec<Tile> {
+// 3麻の場合は萬子の2~8を除いた108枚
+pub fn create_wall(seed: u64, n_red5: usize, is_sanma: bool) -> Vec<Tile> {
     assert!(n_red5 <= 4);
     let mut wall = Vec::new();
     for ti in 0..TYPE {
@@ -11,6 +12,9 @@ pub fn create_wall(seed: u64, n_red5: usize) -> Vec<Tile> {
             if ti == TZ && ni > DR {
                 break;
             }
+            if is_sanma && ti == TM && 1 < ni && ni < 9 {
+                continue;
+            }
             for n in 0..TILE {
                 let ni2 = if ti != TZ && ni == 5 && n < n_red5 {
                     0
@@ -30,10 +34,6 @@ pub fn create_wall(seed: u64, n_red5: usize) -> Vec<Tile> {
 // デバッグ用に作為的な牌山を生成 指定がない場所はシード値に従ってランダムに生成
 #[allow(dead_code)]
 pub fn create_wall_debug(seed: u64, n_red5: usize) -> Vec<Tile> {
-    assert!(n_red5 <= 4);
-    let dora = vec![]; // 最大5
-    let ura_dora = vec![]; // 最大5
-    let replacement = vec![]; // 最大4
     let hands = [
         // 最大13x4
         vec![],                                        // seat0
@@ -42,6 +42,22 @@ pub fn create_wall_debug(seed: u64, n_red5: usize) -> Vec<Tile> {
         tiles_from_string("s112233445566z1").unwrap(), // seat3
     ];
     let deal = vec![Tile(TZ, 1)]; // ツモ山 最初の牌は親番の14枚目
+    let wall = create_wall_preset(seed, n_red5, &hands, &deal);
+    println!("{wall:?}");
+    wall
+}
+
+// 配牌とツモ山の先頭を指定して牌山を生成 (4人麻雀のみ)
+pub fn create_wall_preset(
+    seed: u64,
+    n_red5: usize,
+    hands: &[Vec<Tile>; SEAT],
+    deal: &[Tile],
+) -> Vec<Tile> {
+    assert!(n_red5 <= 4);
+    let dora = vec![]; // 最大5
+    let ura_dora = vec![]; // 最大5
+    let replacement = vec![]; // 最大4
 
     let mut tt: TileTable = [[4; 10]; 4];
     tt[TM][0] = n_red5;
@@ -54,15 +70,14 @@ pub fn create_wall_debug(seed: u64, n_red5: usize) -> Vec<Tile> {
     subtract_tile_table(&mut tt, &dora);
     subtract_tile_table(&mut tt, &ura_dora);
     subtract_tile_table(&mut tt, &replacement);
-    for h in &hands {
+    for h in hands {
         subtract_tile_table(&mut tt, h);
         assert!(h.len() < 14);
     }
-    subtract_tile_table(&mut tt, &deal);
+    subtract_tile_table(&mut tt, deal);
 
     // 余った牌をランダムにシャッフル
     let mut remain = Vec::new();
-    println!("{tt:?}");
     for ti in 0..TYPE {
         for ni in 0..TNUM {
             while tt[ti][ni] != 0 {
@@ -81,15 +96,13 @@ pub fn create_wall_debug(seed: u64, n_red5: usize) -> Vec<Tile> {
     move_tiles(&mut remain, &mut wall, 5 - ura_dora.len());
     append_tiles(&mut wall, &replacement);
     move_tiles(&mut remain, &mut wall, 4 - replacement.len());
-    for h in &hands {
+    for h in hands {
         append_tiles(&mut wall, h);
         move_tiles(&mut remain, &mut wall, 13 - h.len());
     }
-    append_tiles(&mut wall, &deal);
+    append_tiles(&mut wall, deal);
     move_tiles(&mut remain, &mut wall, 136 - 14 - 13 * 4 - deal.len());
 
-    println!("{wall:?}");
-    println!("{remain:?}");
     assert!(remain.is_empty());
     wall
 }
@@ -119,3 +132,10 @@ fn test_debug_wall() {
         println!("{:2}: {}", i, t);
     }
 }
+
+#[test]
+fn test_sanma_wall() {
+    let wall = create_wall(0, 1, true);
+    assert_eq!(wall.len(), 108);
+    assert!(wall.iter().all(|t| t.0 != TM || t.1 == 1 || t.1 == 9));
+}
@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 
-use crate::model::*;
+use crate::{
+    control::{common::*, stage_controller::apply_event},
+    hand::{YakuDefine, calc_points},
+    model::*,
+    util::misc::Res,
+};
 
 // [MjaiEvent]
 // サーバ側から送信する情報
@@ -15,11 +20,17 @@ pub enum MjaiEvent {
         protocol: String,
         protocol_version: usize,
     },
+    // 牌譜の読み込み時に省略されている可能性があるフィールドには#[serde(default)]を指定
     StartGame {
+        #[serde(default)]
         id: Seat,
         names: [String; SEAT],
+        #[serde(default)]
         kyoku_first: usize, // 0: 4人南, 4: 4人東 (EventNew.modeとは割当が異なることに注意)
-        aka_flag: bool,     // true: 赤ドラあり
+        #[serde(default)]
+        aka_flag: bool, // true: 赤ドラあり
+        #[serde(default)]
+        bust_score: Option<Score>, // 飛びになる点数 (mjaiの拡張, 飛びなしの場合はnull)
     },
     StartKyoku {
         bakaze: String,
@@ -67,6 +78,10 @@ pub enum MjaiEvent {
         actor: Seat,
         consumed: Vec<String>,
     },
+    Nukidora {
+        actor: Seat,
+        pai: String,
+    },
     Dora {
         dora_marker: String,
     },
@@ -75,31 +90,48 @@ pub enum MjaiEvent {
     },
     ReachAccepted {
         actor: Seat,
+        #[serde(default)]
         deltas: [Point; SEAT],
+        #[serde(default)]
         scores: [Score; SEAT],
     },
     Hora {
         actor: Seat,
         target: Seat,
+        #[serde(default)]
         pai: String,
+        #[serde(default, alias = "ura_markers")]
         uradora_markers: Vec<String>,
+        #[serde(default)]
         hora_tehais: Vec<String>,
-        yakus: Vec<String>,
+        #[serde(default)]
+        yakus: Vec<(String, usize)>,
+        #[serde(default)]
         fu: usize,
+        #[serde(default)]
         fan: usize,
+        #[serde(default)]
         hora_points: Point,
+        #[serde(default)]
         deltas: [Point; SEAT],
+        #[serde(default)]
         scores: [Score; SEAT],
     },
     Ryukyoku {
+        #[serde(default)]
         reason: String,
+        #[serde(default)]
         tehais: Vec<Vec<String>>,
+        #[serde(default)]
         tenpais: [bool; SEAT],
+        #[serde(default)]
         deltas: [Point; SEAT],
+        #[serde(default)]
         scores: [Score; SEAT],
     },
     EndKyoku {},
     EndGame {
+        #[serde(default)]
         scores: [Score; SEAT],
     },
     None {},
@@ -113,8 +145,8 @@ impl MjaiEvent {
         }
     }
 
-    pub fn start_game(id: Seat, round: usize) -> Self {
-        let kyoku_first = match round {
+    pub fn start_game(id: Seat, rule: &Rule) -> Self {
+        let kyoku_first = match rule.round {
             1 => 4, // 4人東
             2 => 0, // 4人南
             _ => 4, // 不明な場合は4人東にしておく
@@ -128,7 +160,12 @@ impl MjaiEvent {
                 "Player3".into(),
             ],
             kyoku_first,
-            aka_flag: true,
+            aka_flag: rule.red5 > 0,
+            bust_score: if rule.bust {
+                Some(rule.bust_score)
+            } else {
+                None
+            },
         }
     }
 
@@ -157,7 +194,7 @@ impl MjaiEvent {
     }
 
     pub fn tsumo(id: Seat, seat: Seat, tile: Tile) -> Self {
-        let t = if id == seat {
+        let t = if id == seat || id == NO_SEAT {
             tile_to_mjai(tile)
         } else {
             "?".into()
@@ -232,6 +269,14 @@ impl MjaiEvent {
         }
     }
 
+    // 3麻の北抜き (mjaiの拡張)
+    pub fn nukidora(seat: Seat) -> Self {
+        Self::Nukidora {
+            actor: seat,
+            pai: tile_to_mjai(Tile(TZ, WN)),
+        }
+    }
+
     pub fn dora(tile: Tile) -> Self {
         Self::Dora {
             dora_marker: tile_to_mjai(tile),
@@ -239,44 +284,64 @@ impl MjaiEvent {
     }
 
     pub fn hora(
-        seat: Seat,
         target: Seat,
-        tile: Tile,
+        context: &WinContext,
         ura_doras: &[Tile],
-        context: &ScoreContext,
-        deltas: &[Point; SEAT],
         scores: &[Score; SEAT],
     ) -> Self {
+        // ツモ和了の場合は和了牌を含めた14枚
+        let mut hand = tiles_to_tile_table(&context.hand);
+        if context.is_drawn {
+            inc_tile(&mut hand, context.winning_tile);
+        }
+
+        // 役満は倍率1につき13飜として出力
+        let sc = &context.score_context;
+        let (yakus, fan) = if sc.yakuman > 0 {
+            let yakus = sc
+                .yakus
+                .iter()
+                .map(|y| (yaku_to_mjai(&y.name), y.fan * 13))
+                .collect();
+            (yakus, sc.yakuman * 13)
+        } else {
+            let yakus = sc
+                .yakus
+                .iter()
+                .map(|y| (yaku_to_mjai(&y.name), y.fan))
+                .collect();
+            (yakus, sc.fan)
+        };
+
         Self::Hora {
-            actor: seat,
+            actor: context.seat,
             target,
-            pai: tile_to_mjai(tile),
+            pai: tile_to_mjai(context.winning_tile),
             uradora_markers: tiles_to_mjai(ura_doras),
-            hora_tehais: vec![], // TODO
-            yakus: vec![],       // TODO
-            fu: context.fu,
-            fan: context.fan,
-            hora_points: context.points.0,
-            deltas: *deltas,
+            hora_tehais: tiles_to_mjai(&tiles_from_tile_table(&hand)),
+            yakus,
+            fu: sc.fu,
+            fan,
+            hora_points: sc.points.0,
+            deltas: context.delta_scores,
             scores: *scores,
         }
     }
 
-    pub fn ryukyoku(
-        type_: DrawType,
-        is_tenpai: &[bool; SEAT],
-        deltas: &[Point; SEAT],
-        scores: &[Score; SEAT],
-    ) -> Self {
-        let reason = match type_ {
-            DrawType::Kouhaiheikyoku => "fanpai",
-            _ => "",
-        };
+    // tehais: 聴牌者の手牌 (それ以外は枚数分の"?")
+    pub fn ryukyoku(draw: &EventDraw, tehais: Vec<Vec<String>>, scores: &[Score; SEAT]) -> Self {
+        let is_nagashimangan = draw.nagashimangan_scores.iter().any(|&s| s != 0);
+        let mut tenpais = [false; SEAT];
+        if draw.draw_type == DrawType::Kouhaiheikyoku {
+            for s in 0..SEAT {
+                tenpais[s] = !draw.hands[s].is_empty();
+            }
+        }
         Self::Ryukyoku {
-            reason: reason.into(), // TODO
-            tehais: vec![],        // TODO
-            tenpais: *is_tenpai,
-            deltas: *deltas,
+            reason: draw_type_to_mjai(draw.draw_type, is_nagashimangan).into(),
+            tehais,
+            tenpais,
+            deltas: draw.delta_scores,
             scores: *scores,
         }
     }
@@ -290,6 +355,129 @@ impl MjaiEvent {
     }
 }
 
+// [MjaiSerializer]
+// Eventをid視点のMjaiEventに変換 (start_gameは含まない)
+// id = NO_SEATの場合はすべての手牌とツモ牌を公開 (牌譜用)
+#[derive(Debug)]
+pub struct MjaiSerializer {
+    id: Seat,
+    try_riichi: Option<Seat>, // reach_acceptedが未確定のプレイヤー
+}
+
+impl MjaiSerializer {
+    pub fn new(id: Seat) -> Self {
+        Self {
+            id,
+            try_riichi: None,
+        }
+    }
+
+    pub fn push_event(&mut self, stg: &Stage, event: &Event) -> Vec<MjaiEvent> {
+        let mut res = vec![];
+        match event {
+            Event::Begin(_) | Event::Timeout(_) => {}
+            Event::New(ev) => {
+                self.try_riichi = None;
+                res.push(MjaiEvent::start_kyoku(
+                    self.id,
+                    ev.round,
+                    ev.dealer,
+                    ev.honba,
+                    ev.riichi_sticks,
+                    &ev.doras,
+                    &ev.hands,
+                    &get_scores(stg),
+                ));
+            }
+            Event::Deal(ev) => {
+                self.confirm_riichi_accepted(stg, &mut res);
+                res.push(MjaiEvent::tsumo(self.id, ev.seat, ev.tile));
+            }
+            Event::Discard(ev) => {
+                if ev.is_riichi {
+                    res.push(MjaiEvent::reach(ev.seat));
+                }
+                res.push(MjaiEvent::dahai(ev.seat, ev.tile, ev.is_drawn));
+                if ev.is_riichi {
+                    self.try_riichi = Some(ev.seat);
+                }
+            }
+            Event::Meld(ev) => {
+                self.confirm_riichi_accepted(stg, &mut res);
+                res.push(match ev.meld_type {
+                    MeldType::Chi => {
+                        let lt = stg.last_tile.unwrap();
+                        MjaiEvent::chi(ev.seat, &ev.consumed, lt.2, lt.0)
+                    }
+                    MeldType::Pon => {
+                        let lt = stg.last_tile.unwrap();
+                        MjaiEvent::pon(ev.seat, &ev.consumed, lt.2, lt.0)
+                    }
+                    MeldType::Minkan => {
+                        let lt = stg.last_tile.unwrap();
+                        MjaiEvent::daiminkan(ev.seat, &ev.consumed, lt.2, lt.0)
+                    }
+                    MeldType::Ankan => MjaiEvent::ankan(ev.seat, &ev.consumed),
+                    MeldType::Kakan => {
+                        let c = ev.consumed[0];
+                        let t = c.to_normal();
+                        let t0 = if t.is_suit() && t.1 == 5 && c.1 != 0 {
+                            Tile(t.0, 0)
+                        } else {
+                            t
+                        };
+                        MjaiEvent::kakan(ev.seat, &ev.consumed, &[t, t, t0])
+                    }
+                });
+            }
+            Event::Nukidora(ev) => {
+                self.confirm_riichi_accepted(stg, &mut res);
+                res.push(MjaiEvent::nukidora(ev.seat));
+            }
+            Event::Dora(ev) => {
+                res.push(MjaiEvent::dora(ev.tile));
+            }
+            Event::Win(ev) => {
+                for ctx in &ev.contexts {
+                    res.push(MjaiEvent::hora(
+                        stg.turn,
+                        ctx,
+                        &ev.ura_doras,
+                        &get_scores(stg),
+                    ));
+                }
+                res.push(MjaiEvent::end_kyoku());
+            }
+            Event::Draw(ev) => {
+                let n_seat = get_n_seat(stg);
+                let tehais = (0..n_seat)
+                    .map(|s| {
+                        if ev.hands[s].is_empty() {
+                            let n = tiles_from_tile_table(&stg.players[s].hand).len();
+                            vec!["?".to_string(); n]
+                        } else {
+                            tiles_to_mjai(&ev.hands[s])
+                        }
+                    })
+                    .collect();
+                res.push(MjaiEvent::ryukyoku(ev, tehais, &get_scores(stg)));
+                res.push(MjaiEvent::end_kyoku());
+            }
+            Event::End(_) => {
+                res.push(MjaiEvent::end_game(&get_scores(stg)));
+            }
+        }
+        res
+    }
+
+    fn confirm_riichi_accepted(&mut self, stg: &Stage, res: &mut Vec<MjaiEvent>) {
+        if let Some(s) = self.try_riichi {
+            self.try_riichi = None;
+            res.push(MjaiEvent::reach_accepted(s, &get_scores(stg)));
+        }
+    }
+}
+
 // [MjaiAction]
 // MjaiEvent内のpossible_actionの中身とクライアント側の応答
 #[derive(Debug, Serialize, Deserialize)]
@@ -332,6 +520,10 @@ pub enum MjaiAction {
         actor: Seat,
         consumed: Vec<String>,
     },
+    Nukidora {
+        actor: Seat,
+        pai: String,
+    },
     Reach {
         actor: Seat,
     },
@@ -387,7 +579,10 @@ impl MjaiAction {
                 actor: seat,
                 reason: "kyushukyuhai".into(),
             },
-            ActionType::Nukidora => panic!(),
+            ActionType::Nukidora => Self::Nukidora {
+                actor: seat,
+                pai: tile_to_mjai(Tile(TZ, WN)),
+            },
             ActionType::Chi => {
                 let (target_seat, _, target_tile) = stg.last_tile.unwrap();
                 Self::Chi {
@@ -441,6 +636,7 @@ impl MjaiAction {
             Self::Kakan { pai, .. } => Action::kakan(tile_from_mjai(pai)),
             Self::Daiminkan { consumed, .. } => Action::minkan(tiles_from_mjai(consumed)),
             Self::Ankan { consumed, .. } => Action::ankan(tiles_from_mjai(consumed)),
+            Self::Nukidora { .. } => Action::nukidora(),
             Self::Reach { .. } => panic!(),
             Self::Hora { .. } => {
                 if is_turn {
@@ -455,6 +651,549 @@ impl MjaiAction {
     }
 }
 
+// [MjaiDeserializer]
+// mjai形式の牌譜(1行に1つのMjaiEventを記録したJSON Lines)を局ごとのイベント列(EventNew ~ EventWin | EventDraw)に変換
+// 他家の手牌が"?"で隠された牌譜の場合,その手牌は不明な牌(Z8)として扱う
+pub fn mjai_to_events(lines: &str) -> Res<Vec<Vec<Event>>> {
+    let mut de = MjaiDeserializer::new();
+    for (i, line) in lines.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let ev: MjaiEvent =
+            serde_json::from_str(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        de.push_mjai_event(ev)
+            .map_err(|e| format!("line {}: {}", i + 1, e))?;
+    }
+    de.end_kyoku();
+    Ok(de.rounds)
+}
+
+struct MjaiDeserializer {
+    rule: Rule,
+    names: [String; SEAT],
+    is_first_kyoku: bool,
+    stage: Stage, // 手牌や副露の状態を追跡するための卓情報
+    events: Vec<Event>,
+    rounds: Vec<Vec<Event>>,
+    riichi: Option<Seat>, // reachの直後の打牌をリーチ宣言牌として扱う
+    is_rinshan: bool,     // 槓・北抜きの直後のツモを嶺上牌として扱う
+    ura_doras: Vec<Tile>,
+    wins: Vec<WinContext>, // ダブロンに対応するためend_kyokuでまとめてEventWinに変換
+}
+
+impl MjaiDeserializer {
+    fn new() -> Self {
+        Self {
+            rule: Rule {
+                round: 2,
+                ..Default::default()
+            },
+            names: Default::default(),
+            is_first_kyoku: true,
+            stage: Stage::default(),
+            events: vec![],
+            rounds: vec![],
+            riichi: None,
+            is_rinshan: false,
+            ura_doras: vec![],
+            wins: vec![],
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        apply_event(&mut self.stage, &event);
+        self.events.push(event);
+    }
+
+    fn push_mjai_event(&mut self, ev: MjaiEvent) -> Res {
+        if !matches!(
+            ev,
+            MjaiEvent::StartGame { .. }
+                | MjaiEvent::StartKyoku { .. }
+                | MjaiEvent::EndGame { .. }
+                | MjaiEvent::Hello { .. }
+                | MjaiEvent::None {}
+        ) && self.events.is_empty()
+        {
+            return Err("event before start_kyoku".into());
+        }
+
+        match ev {
+            MjaiEvent::Hello { .. } | MjaiEvent::ReachAccepted { .. } | MjaiEvent::None {} => {}
+            MjaiEvent::StartGame {
+                names,
+                kyoku_first,
+                aka_flag,
+                bust_score,
+                ..
+            } => {
+                self.names = names;
+                self.rule.round = if kyoku_first == 4 { 1 } else { 2 };
+                self.rule.red5 = if aka_flag { 1 } else { 0 };
+                self.rule.bust = bust_score.is_some();
+                self.rule.bust_score = bust_score.unwrap_or(0);
+                self.is_first_kyoku = true;
+            }
+            MjaiEvent::StartKyoku {
+                bakaze,
+                dora_marker,
+                kyoku,
+                honba,
+                kyotaku,
+                oya,
+                tehais,
+                scores,
+            } => {
+                self.end_kyoku();
+                if self.is_first_kyoku {
+                    // 3麻の場合は座席3が空席
+                    self.rule.is_sanma = tehais[3].is_empty();
+                    let n_seat = calc_n_seat(self.rule.is_sanma);
+                    self.rule.initial_score =
+                        scores[..n_seat].iter().sum::<Score>() / n_seat as Score;
+                    self.is_first_kyoku = false;
+                }
+                let round = ["E", "S", "W", "N"]
+                    .iter()
+                    .position(|&w| w == bakaze)
+                    .ok_or_else(|| format!("invalid bakaze: {}", bakaze))?;
+                let n_seat = calc_n_seat(self.rule.is_sanma);
+                if oya >= n_seat || kyoku != oya + 1 {
+                    return Err(format!("invalid kyoku: {}, oya: {}", kyoku, oya).into());
+                }
+                let mut hands = [vec![], vec![], vec![], vec![]];
+                for s in 0..n_seat {
+                    hands[s] = tiles_from_mjai_checked(&tehais[s])?;
+                    if hands[s].len() != 13 {
+                        return Err(format!("invalid hand size: seat {}", s).into());
+                    }
+                }
+                // 王牌14枚と配牌を除いた牌山の残り枚数
+                let wall_count = if self.rule.is_sanma { 55 } else { 70 };
+                self.push(Event::new(
+                    self.rule.clone(),
+                    round,
+                    oya,
+                    honba,
+                    kyotaku,
+                    vec![tile_from_mjai_checked(&dora_marker)?],
+                    self.names.clone(),
+                    scores,
+                    hands,
+                    wall_count,
+                    0,
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![],
+                ));
+            }
+            MjaiEvent::Tsumo { actor, pai } => {
+                self.check_seat(actor)?;
+                if self.stage.wall_count == 0 {
+                    return Err("no tiles left in wall".into());
+                }
+                let t = tile_from_mjai_checked(&pai)?;
+                let is_replacement = std::mem::take(&mut self.is_rinshan);
+                self.push(Event::deal(actor, t, is_replacement));
+            }
+            MjaiEvent::Reach { actor } => {
+                self.check_seat(actor)?;
+                self.riichi = Some(actor);
+            }
+            MjaiEvent::Dahai {
+                actor,
+                pai,
+                tsumogiri,
+            } => {
+                self.check_seat(actor)?;
+                let t = tile_from_mjai_checked(&pai)?;
+                self.check_in_hand(actor, &[t])?;
+                let is_riichi = self.riichi.take() == Some(actor);
+                self.push(Event::discard(actor, t, tsumogiri, is_riichi));
+            }
+            MjaiEvent::Chi {
+                actor,
+                target,
+                pai,
+                consumed,
+            } => self.call(actor, target, &pai, &consumed, MeldType::Chi)?,
+            MjaiEvent::Pon {
+                actor,
+                target,
+                pai,
+                consumed,
+            } => self.call(actor, target, &pai, &consumed, MeldType::Pon)?,
+            MjaiEvent::Daiminkan {
+                actor,
+                target,
+                pai,
+                consumed,
+            } => self.call(actor, target, &pai, &consumed, MeldType::Minkan)?,
+            MjaiEvent::Kakan { actor, pai, .. } => {
+                self.check_seat(actor)?;
+                let t = tile_from_mjai_checked(&pai)?;
+                self.check_in_hand(actor, &[t])?;
+                let has_pon = self.stage.players[actor].melds.iter().any(|m| {
+                    m.meld_type == MeldType::Pon && m.tiles[0].to_normal() == t.to_normal()
+                });
+                if !has_pon {
+                    return Err(format!("kakan without pon: {}", pai).into());
+                }
+                self.is_rinshan = true;
+                self.push(Event::meld(actor, MeldType::Kakan, vec![t], false));
+            }
+            MjaiEvent::Ankan { actor, consumed } => {
+                self.check_seat(actor)?;
+                let consumed = tiles_from_mjai_checked(&consumed)?;
+                if consumed.len() != 4 {
+                    return Err("ankan requires 4 tiles".into());
+                }
+                self.check_in_hand(actor, &consumed)?;
+                self.is_rinshan = true;
+                self.push(Event::meld(actor, MeldType::Ankan, consumed, false));
+            }
+            MjaiEvent::Nukidora { actor, .. } => {
+                self.check_seat(actor)?;
+                self.check_in_hand(actor, &[Tile(TZ, WN)])?;
+                self.is_rinshan = true;
+                self.push(Event::nukidora(actor, false));
+            }
+            MjaiEvent::Dora { dora_marker } => {
+                let t = tile_from_mjai_checked(&dora_marker)?;
+                self.push(Event::dora(t));
+            }
+            MjaiEvent::Hora {
+                actor,
+                target,
+                pai,
+                uradora_markers,
+                hora_tehais,
+                yakus,
+                fu,
+                fan,
+                hora_points,
+                deltas,
+                ..
+            } => {
+                self.check_seat(actor)?;
+                self.check_seat(target)?;
+                self.ura_doras = tiles_from_mjai_checked(&uradora_markers)?;
+                let prevalent_wind = get_prevalent_wind(&self.stage);
+                let seat_wind = get_seat_wind(&self.stage, actor);
+                let yakus = yakus
+                    .into_iter()
+                    .map(|(name, fan)| Yaku {
+                        name: yaku_from_mjai(&name, prevalent_wind, seat_wind),
+                        fan,
+                    })
+                    .collect();
+                let ctx = self.win_context(
+                    actor,
+                    target,
+                    &pai,
+                    &hora_tehais,
+                    yakus,
+                    fu,
+                    fan,
+                    hora_points,
+                    deltas,
+                )?;
+                self.wins.push(ctx);
+            }
+            MjaiEvent::Ryukyoku {
+                reason,
+                tehais,
+                deltas,
+                ..
+            } => {
+                let draw_type = draw_type_from_mjai(&reason);
+                let mut nm_scores = [0; SEAT];
+                if reason == "nagashimangan" {
+                    nm_scores = deltas.map(|d| d.max(0));
+                }
+                // 手牌の記録がない場合は局の進行から復元
+                let hands = if tehais.is_empty() {
+                    get_draw_hands(&self.stage, draw_type)
+                } else {
+                    let mut hands = [vec![], vec![], vec![], vec![]];
+                    for (s, tehai) in tehais.iter().enumerate().take(SEAT) {
+                        if !tehai.is_empty() && tehai.iter().all(|t| t != "?") {
+                            hands[s] = tiles_from_mjai_checked(tehai)?;
+                        }
+                    }
+                    hands
+                };
+                self.push(Event::draw(draw_type, deltas, nm_scores, hands));
+            }
+            MjaiEvent::EndKyoku {} | MjaiEvent::EndGame { .. } => self.end_kyoku(),
+        }
+        Ok(())
+    }
+
+    // 局の終了 (和了がある場合はEventWinを追加)
+    fn end_kyoku(&mut self) {
+        if !self.wins.is_empty() {
+            let contexts = std::mem::take(&mut self.wins);
+            let mut delta_scores = [0; SEAT];
+            for ctx in &contexts {
+                for s in 0..SEAT {
+                    delta_scores[s] += ctx.delta_scores[s];
+                }
+            }
+            let ura_doras = std::mem::take(&mut self.ura_doras);
+            self.push(Event::win(ura_doras, delta_scores, contexts));
+        }
+        if !self.events.is_empty() {
+            self.rounds.push(std::mem::take(&mut self.events));
+        }
+        self.riichi = None;
+        self.is_rinshan = false;
+    }
+
+    fn call(
+        &mut self,
+        actor: Seat,
+        target: Seat,
+        pai: &str,
+        consumed: &[String],
+        meld_type: MeldType,
+    ) -> Res {
+        self.check_seat(actor)?;
+        let t = tile_from_mjai_checked(pai)?;
+        match self.stage.last_tile {
+            Some((s, ActionType::Discard, d)) if s == target && d == t && s != actor => {}
+            _ => return Err(format!("{:?} target not found: {}", meld_type, pai).into()),
+        }
+        let consumed = tiles_from_mjai_checked(consumed)?;
+        self.check_in_hand(actor, &consumed)?;
+        let (is_pao, is_pao_kan) = check_pao(&self.stage.players[actor], meld_type, t);
+        if meld_type == MeldType::Minkan {
+            self.is_rinshan = true;
+        }
+        self.push(Event::meld(
+            actor,
+            meld_type,
+            consumed,
+            is_pao || is_pao_kan,
+        ));
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn win_context(
+        &self,
+        seat: Seat,
+        target: Seat,
+        pai: &str,
+        hora_tehais: &[String],
+        mut yakus: Vec<Yaku>,
+        fu: usize,
+        mut fan: usize,
+        hora_points: Point,
+        delta_scores: [Point; SEAT],
+    ) -> Res<WinContext> {
+        let stg = &self.stage;
+        let pl = &stg.players[seat];
+        let is_drawn = seat == target;
+        let is_dealer = seat == stg.dealer;
+
+        let winning_tile = if !pai.is_empty() {
+            tile_from_mjai_checked(pai)?
+        } else if is_drawn {
+            pl.drawn.ok_or("tsumo without drawn tile")?
+        } else {
+            stg.last_tile.ok_or("ron without discarded tile")?.2
+        };
+        let mut hand = if pl.is_shown {
+            pl.hand
+        } else {
+            tiles_to_tile_table(&tiles_from_mjai_checked(hora_tehais)?)
+        };
+        if is_drawn && count_tile(&hand, winning_tile) > 0 {
+            dec_tile(&mut hand, winning_tile);
+        }
+
+        // 役満は倍率1につき13飜として記録されているので倍率に戻す
+        // 役の情報がない牌譜で飜数が0の場合は和了点から倍率を復元
+        let is_yakuman = yakus
+            .iter()
+            .any(|y| YakuDefine::get_from_name(&y.name).is_some_and(|d| d.yakuman > 0));
+        let yakuman = if is_yakuman {
+            for y in &mut yakus {
+                y.fan = (y.fan / 13).max(1);
+            }
+            (std::mem::take(&mut fan) / 13).max(1)
+        } else if fan == 0 && hora_points > 0 {
+            let unit = if is_dealer { 48000 } else { 32000 };
+            (hora_points / unit).max(1) as usize
+        } else {
+            0
+        };
+        let (points, title) = calc_points(is_dealer, fu, fan, yakuman, &self.rule);
+        let n_payer = calc_n_seat(self.rule.is_sanma) as Point - 1; // 3麻はツモ損
+        let score = if is_drawn {
+            if is_dealer {
+                points.1 * n_payer
+            } else {
+                points.1 * (n_payer - 1) + points.2
+            }
+        } else {
+            points.0
+        };
+
+        Ok(WinContext {
+            seat,
+            hand: tiles_from_tile_table(&hand),
+            winning_tile,
+            melds: pl.melds.clone(),
+            is_dealer,
+            is_drawn,
+            is_riichi: pl.is_riichi,
+            pao: pl.pao.or(pl.pao_kan),
+            delta_scores,
+            score_context: ScoreContext {
+                yakus,
+                fu,
+                fan,
+                yakuman,
+                score,
+                points,
+                title,
+            },
+        })
+    }
+
+    fn check_seat(&self, seat: Seat) -> Res {
+        if seat < calc_n_seat(self.rule.is_sanma) {
+            Ok(())
+        } else {
+            Err(format!("invalid seat: {}", seat).into())
+        }
+    }
+
+    // 手牌が公開されている場合のみ確認
+    fn check_in_hand(&self, seat: Seat, tiles: &[Tile]) -> Res {
+        let pl = &self.stage.players[seat];
+        if !pl.is_shown {
+            return Ok(());
+        }
+        let mut hand = pl.hand;
+        for &t in tiles {
+            if count_tile(&hand, t) == 0 {
+                return Err(format!("tile {} not in hand: seat {}", t, seat).into());
+            }
+            dec_tile(&mut hand, t);
+        }
+        Ok(())
+    }
+}
+
+// mjaiのryukyoku.reason
+fn draw_type_to_mjai(draw_type: DrawType, is_nagashimangan: bool) -> &'static str {
+    match draw_type {
+        DrawType::Kouhaiheikyoku if is_nagashimangan => "nagashimangan",
+        DrawType::Kouhaiheikyoku => "fanpai",
+        DrawType::Kyushukyuhai => "kyushukyuhai",
+        DrawType::Suufuurenda => "suufonrenda",
+        DrawType::Suukansanra => "suukaikan",
+        DrawType::Suuchariichi => "suuchariichi",
+        DrawType::Sanchaho => "sanchaho",
+        DrawType::Unknown => "",
+    }
+}
+
+fn draw_type_from_mjai(reason: &str) -> DrawType {
+    match reason {
+        "fanpai" | "nagashimangan" | "" => DrawType::Kouhaiheikyoku,
+        "kyushukyuhai" | "kyusyukyuhai" => DrawType::Kyushukyuhai,
+        "suufonrenda" | "sufonrenda" => DrawType::Suufuurenda,
+        "suukaikan" | "sukaikan" => DrawType::Suukansanra,
+        "suuchariichi" | "sucharichi" => DrawType::Suuchariichi,
+        "sanchaho" => DrawType::Sanchaho,
+        _ => DrawType::Unknown,
+    }
+}
+
+// 役名 (天鳳の名称, mjaiの名称)
+// 場風と自風はmjaiでは風の種類を区別しないため別途変換
+const MJAI_YAKU_NAMES: &[(&str, &str)] = &[
+    ("立直", "reach"),
+    ("両立直", "double_reach"),
+    ("一発", "ippatsu"),
+    ("門前清自摸和", "menzenchin_tsumoho"),
+    ("海底摸月", "haiteiraoyue"),
+    ("河底撈魚", "hoteiraoyui"),
+    ("嶺上開花", "rinshankaiho"),
+    ("槍槓", "chankan"),
+    ("天和", "tenho"),
+    ("地和", "chiho"),
+    ("役牌 白", "haku"),
+    ("役牌 發", "hatsu"),
+    ("役牌 中", "chun"),
+    ("平和", "pinfu"),
+    ("断幺九", "tanyaochu"),
+    ("一盃口", "ipeko"),
+    ("二盃口", "ryanpeko"),
+    ("一気通貫", "ikkitsukan"),
+    ("三色同順", "sanshokudojun"),
+    ("三色同刻", "sanshokudoko"),
+    ("混全帯幺九", "honchantaiyao"),
+    ("純全帯幺九", "junchantaiyao"),
+    ("混老頭", "honroto"),
+    ("清老頭", "chinroto"),
+    ("対々和", "toitoiho"),
+    ("三暗刻", "sananko"),
+    ("四暗刻", "suanko"),
+    ("四暗刻単騎", "suanko_tanki"),
+    ("三槓子", "sankantsu"),
+    ("四槓子", "sukantsu"),
+    ("混一色", "honiso"),
+    ("清一色", "chiniso"),
+    ("小三元", "shosangen"),
+    ("大三元", "daisangen"),
+    ("小四喜", "shosushi"),
+    ("大四喜", "daisushi"),
+    ("緑一色", "ryuiso"),
+    ("字一色", "tsuiso"),
+    ("九蓮宝燈", "churenpoton"),
+    ("純正九蓮宝燈", "junsei_churenpoton"),
+    ("国士無双", "kokushimuso"),
+    ("国士無双１３面", "kokushimuso_jusanmen"),
+    ("七対子", "chitoitsu"),
+    ("ドラ", "dora"),
+    ("赤ドラ", "akadora"),
+    ("裏ドラ", "uradora"),
+    ("抜きドラ", "nukidora"),
+];
+const WIND_NAMES: [&str; 5] = ["", "東", "南", "西", "北"];
+
+fn yaku_to_mjai(name: &str) -> String {
+    if name.starts_with("場風") {
+        return "bakaze".into();
+    }
+    if name.starts_with("自風") {
+        return "jikaze".into();
+    }
+    match MJAI_YAKU_NAMES.iter().find(|(jp, _)| *jp == name) {
+        Some((_, en)) => en.to_string(),
+        None => name.into(),
+    }
+}
+
+// 対応する名称がない場合はそのまま
+fn yaku_from_mjai(name: &str, prevalent_wind: Tnum, seat_wind: Tnum) -> String {
+    match name {
+        "bakaze" => format!("場風 {}", WIND_NAMES[prevalent_wind]),
+        "jikaze" => format!("自風 {}", WIND_NAMES[seat_wind]),
+        _ => match MJAI_YAKU_NAMES.iter().find(|(_, en)| *en == name) {
+            Some((jp, _)) => jp.to_string(),
+            None => name.into(),
+        },
+    }
+}
+
 // [Utility]
 pub fn tile_to_mjai(t: Tile) -> String {
     if t.is_hornor() {
@@ -500,6 +1239,26 @@ pub fn tile_from_mjai(sym: &str) -> Tile {
     }
 }
 
+// 牌譜の読み込み用 (不正な牌の場合はエラー)
+fn tile_from_mjai_checked(sym: &str) -> Res<Tile> {
+    let b = sym.as_bytes();
+    let is_valid = match b {
+        b"?" | b"E" | b"S" | b"W" | b"N" | b"P" | b"F" | b"C" => true,
+        [n, t] => (b'1'..=b'9').contains(n) && b"mps".contains(t),
+        [n, t, b'r'] => *n == b'5' && b"mps".contains(t),
+        _ => false,
+    };
+    if is_valid {
+        Ok(tile_from_mjai(sym))
+    } else {
+        Err(format!("invalid tile: {}", sym).into())
+    }
+}
+
+fn tiles_from_mjai_checked(v: &[String]) -> Res<Vec<Tile>> {
+    v.iter().map(|t| tile_from_mjai_checked(t)).collect()
+}
+
 fn tiles_to_mjai(v: &[Tile]) -> Vec<String> {
     v.iter().map(|&t| tile_to_mjai(t)).collect()
 }
@@ -515,7 +1274,7 @@ fn create_tehais(hands: &[Vec<Tile>; SEAT], seat: usize) -> [Vec<String>; SEAT]
     for (seat2, hand) in hands.iter().enumerate() {
         let mut mjai_hand = vec![];
         for &t in hand {
-            if seat == seat2 {
+            if seat == seat2 || seat == NO_SEAT {
                 mjai_hand.push(tile_to_mjai(t));
             } else {
                 mjai_hand.push("?".into());
@@ -545,3 +1304,79 @@ fn test_mjai_message() {
         println!("{:?}", act);
     }
 }
+
+#[test]
+fn test_mjai_to_events() {
+    // 省略可能なフィールドを含まない牌譜
+    let log = r#"
+{"type":"start_game","names":["A","B","C","D"]}
+{"type":"start_kyoku","bakaze":"E","dora_marker":"2s","kyoku":1,"honba":0,"kyotaku":0,"oya":0,"scores":[25000,25000,25000,25000],"tehais":[["1m","9m","1p","9p","1s","9s","E","S","W","N","P","2m","3m"],["?","?","?","?","?","?","?","?","?","?","?","?","?"],["?","?","?","?","?","?","?","?","?","?","?","?","?"],["?","?","?","?","?","?","?","?","?","?","?","?","?"]]}
+{"type":"tsumo","actor":0,"pai":"5mr"}
+{"type":"ryukyoku","reason":"kyushukyuhai","deltas":[0,0,0,0]}
+{"type":"end_kyoku"}
+{"type":"end_game"}
+"#;
+    let rounds = mjai_to_events(log).unwrap();
+    assert_eq!(rounds.len(), 1);
+    let events = &rounds[0];
+    assert_eq!(events.len(), 3);
+    let Event::Deal(deal) = &events[1] else {
+        panic!();
+    };
+    assert_eq!(deal.tile, Tile(TM, 0));
+    let Event::Draw(draw) = &events[2] else {
+        panic!();
+    };
+    assert_eq!(draw.draw_type, DrawType::Kyushukyuhai);
+    assert_eq!(draw.hands[0].len(), 14);
+
+    // 手牌に存在しない牌の打牌
+    let log = log.replace(
+        r#"{"type":"ryukyoku","reason":"kyushukyuhai","deltas":[0,0,0,0]}"#,
+        r#"{"type":"dahai","actor":0,"pai":"7m","tsumogiri":false}"#,
+    );
+    assert!(mjai_to_events(&log).is_err());
+}
+
+#[test]
+fn test_mjai_hora_yakuman() {
+    // 役満は倍率1につき13飜として記録
+    let log = r#"
+{"type":"start_game","names":["A","B","C","D"]}
+{"type":"start_kyoku","bakaze":"E","dora_marker":"2s","kyoku":1,"honba":0,"kyotaku":0,"oya":0,"scores":[25000,25000,25000,25000],"tehais":[["1m","2m","3m","4m","5m","6m","7m","8m","9m","1p","1p","1p","E"],["?","?","?","?","?","?","?","?","?","?","?","?","?"],["?","?","?","?","?","?","?","?","?","?","?","?","?"],["?","?","?","?","?","?","?","?","?","?","?","?","?"]]}
+{"type":"tsumo","actor":0,"pai":"E"}
+{"type":"hora","actor":0,"target":0,"pai":"E","uradora_markers":[],"hora_tehais":["1m","2m","3m","4m","5m","6m","7m","8m","9m","1p","1p","1p","E","E"],"yakus":[["tenho",13]],"fu":40,"fan":13,"hora_points":48000,"deltas":[48000,-16000,-16000,-16000]}
+{"type":"end_kyoku"}
+{"type":"end_game"}
+"#;
+    let rounds = mjai_to_events(log).unwrap();
+    let Some(Event::Win(win)) = rounds[0].last() else {
+        panic!();
+    };
+    let ctx = &win.contexts[0];
+    let sc = &ctx.score_context;
+    assert_eq!(sc.yakuman, 1);
+    assert_eq!(sc.fan, 0);
+    assert_eq!(sc.yakus[0].name, "天和");
+    assert_eq!(sc.yakus[0].fan, 1);
+    assert_eq!(sc.score, 48000);
+    assert_eq!(ctx.hand.len(), 13);
+
+    let MjaiEvent::Hora {
+        hora_tehais,
+        yakus,
+        fan,
+        ..
+    } = MjaiEvent::hora(0, ctx, &[], &[0; SEAT])
+    else {
+        panic!();
+    };
+    assert_eq!(hora_tehais.len(), 14);
+    assert_eq!(yakus, vec![("tenho".to_string(), 13)]);
+    assert_eq!(fan, 13);
+
+    // 場風と自風は局の状態から復元
+    assert_eq!(yaku_from_mjai("bakaze", WS, WW), "場風 南");
+    assert_eq!(yaku_from_mjai("jikaze", WS, WW), "自風 西");
+    assert_eq!(yaku_to_mjai("自風 西"), "jikaze");
+}
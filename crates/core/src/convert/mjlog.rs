@@ -0,0 +1,705 @@
+use crate::{control::common::*, convert::tenhou::calc_sc, error, model::*};
+
+// 天鳳の役ID
+// 抜きドラは天鳳ではドラとして集計されるためドラと同じIDを使用
+const MJLOG_YAKU_IDS: &[(&str, usize)] = &[
+    ("門前清自摸和", 0),
+    ("立直", 1),
+    ("一発", 2),
+    ("槍槓", 3),
+    ("嶺上開花", 4),
+    ("海底摸月", 5),
+    ("河底撈魚", 6),
+    ("平和", 7),
+    ("断幺九", 8),
+    ("一盃口", 9),
+    ("自風 東", 10),
+    ("自風 南", 11),
+    ("自風 西", 12),
+    ("自風 北", 13),
+    ("場風 東", 14),
+    ("場風 南", 15),
+    ("場風 西", 16),
+    ("場風 北", 17),
+    ("役牌 白", 18),
+    ("役牌 發", 19),
+    ("役牌 中", 20),
+    ("両立直", 21),
+    ("七対子", 22),
+    ("混全帯幺九", 23),
+    ("一気通貫", 24),
+    ("三色同順", 25),
+    ("三色同刻", 26),
+    ("三槓子", 27),
+    ("対々和", 28),
+    ("三暗刻", 29),
+    ("小三元", 30),
+    ("混老頭", 31),
+    ("二盃口", 32),
+    ("純全帯幺九", 33),
+    ("混一色", 34),
+    ("清一色", 35),
+    ("天和", 37),
+    ("地和", 38),
+    ("大三元", 39),
+    ("四暗刻", 40),
+    ("四暗刻単騎", 41),
+    ("字一色", 42),
+    ("緑一色", 43),
+    ("清老頭", 44),
+    ("九蓮宝燈", 45),
+    ("純正九蓮宝燈", 46),
+    ("国士無双", 47),
+    ("国士無双１３面", 48),
+    ("大四喜", 49),
+    ("小四喜", 50),
+    ("四槓子", 51),
+    ("ドラ", 52),
+    ("裏ドラ", 53),
+    ("赤ドラ", 54),
+    ("抜きドラ", 52),
+];
+
+#[derive(Debug, Clone)]
+struct MjlogMeld {
+    meld_type: MeldType,
+    ids: Vec<usize>, // 鳴いた牌を含む (加槓の場合,末尾が加槓した牌)
+    called: usize,   // 鳴いた牌 (暗槓の場合は先頭の牌)
+    kui: usize,      // 鳴いた相手の相対位置 (0: なし, 1: 下家, 2: 対面, 3: 上家)
+}
+
+impl MjlogMeld {
+    // <N m=..>のビット表現
+    fn encode(&self) -> usize {
+        let kind = self.ids[0] / 4;
+        match self.meld_type {
+            MeldType::Chi => {
+                let mut ids = self.ids.clone();
+                ids.sort();
+                let base = ids[0] / 4;
+                let t = base / 9 * 7 + base % 9;
+                let pos = ids.iter().position(|&i| i == self.called).unwrap();
+                let copies = (ids[0] % 4) << 3 | (ids[1] % 4) << 5 | (ids[2] % 4) << 7;
+                (t * 3 + pos) << 10 | copies | 0x4 | self.kui
+            }
+            MeldType::Pon | MeldType::Kakan => {
+                let mut ids = self.ids[..3].to_vec();
+                ids.sort();
+                let pos = ids.iter().position(|&i| i == self.called).unwrap();
+                // ポンの場合は使用していない牌, 加槓の場合は加槓した牌
+                let unused = if self.meld_type == MeldType::Kakan {
+                    self.ids[3] % 4
+                } else {
+                    (0..4).find(|&c| ids.iter().all(|&i| i % 4 != c)).unwrap()
+                };
+                let flag = if self.meld_type == MeldType::Kakan {
+                    0x10
+                } else {
+                    0x8
+                };
+                (kind * 3 + pos) << 9 | unused << 5 | flag | self.kui
+            }
+            MeldType::Minkan | MeldType::Ankan => self.called << 8 | self.kui,
+        }
+    }
+}
+
+// [MjlogSerializer]
+// 天鳳のmjlog(XML)形式で1試合分の牌譜を記録
+// 136形式の牌IDは牌が初めて見えた時点で割り当てるため,全員の手牌が見えている必要がある
+#[derive(Debug)]
+pub struct MjlogSerializer {
+    tags: Vec<String>,
+    used: [bool; 136],                 // 割り当て済みの牌ID
+    hands: [Vec<(usize, Tile)>; SEAT], // (牌ID, 牌)
+    melds: [Vec<MjlogMeld>; SEAT],
+    doras: Vec<usize>,
+    last_id: usize,       // 直前に捨てられた牌(加槓,暗槓,北抜きを含む)のID
+    riichi: Option<Seat>, // 宣言牌が通った時点でリーチ成立(step=2)を出力
+}
+
+impl MjlogSerializer {
+    pub fn new() -> Self {
+        Self {
+            tags: vec![],
+            used: [false; 136],
+            hands: Default::default(),
+            melds: Default::default(),
+            doras: vec![],
+            last_id: 0,
+            riichi: None,
+        }
+    }
+
+    pub fn push_event(&mut self, stg: &Stage, event: &Event) {
+        match event {
+            Event::Begin(_) => {
+                self.tags.clear();
+            }
+            Event::New(ev) => {
+                if self.tags.is_empty() {
+                    self.push_header(&ev.rule, &ev.names);
+                }
+                self.used = [false; 136];
+                self.hands = Default::default();
+                self.melds = Default::default();
+                self.riichi = None;
+                self.doras = ev.doras.iter().map(|&t| self.alloc(t)).collect();
+
+                let d = ev.dice.max(2) - 2; // サイコロの目は0~5で記録
+                let seed = [
+                    ev.round * 4 + ev.dealer,
+                    ev.honba,
+                    ev.riichi_sticks,
+                    d / 2,
+                    d - d / 2,
+                    self.doras[0],
+                ];
+                let mut tag = format!(
+                    r#"<INIT seed="{}" ten="{}" oya="{}""#,
+                    join(&seed),
+                    join(&ev.scores.map(|s| s / 100)),
+                    ev.dealer,
+                );
+                for s in 0..SEAT {
+                    let ids: Vec<usize> = ev.hands[s].iter().map(|&t| self.alloc(t)).collect();
+                    self.hands[s] = ids
+                        .iter()
+                        .copied()
+                        .zip(ev.hands[s].iter().copied())
+                        .collect();
+                    tag += &format!(r#" hai{}="{}""#, s, join(&ids));
+                }
+                self.tags.push(tag + "/>");
+            }
+            Event::Deal(ev) => {
+                self.flush_riichi(stg);
+                let id = self.alloc(ev.tile);
+                self.hands[ev.seat].push((id, ev.tile));
+                self.tags
+                    .push(format!("<{}{}/>", ['T', 'U', 'V', 'W'][ev.seat], id));
+            }
+            Event::Discard(ev) => {
+                if ev.is_riichi {
+                    self.tags
+                        .push(format!(r#"<REACH who="{}" step="1"/>"#, ev.seat));
+                    self.riichi = Some(ev.seat);
+                }
+                let id = self.take(ev.seat, ev.tile);
+                self.last_id = id;
+                self.tags
+                    .push(format!("<{}{}/>", ['D', 'E', 'F', 'G'][ev.seat], id));
+            }
+            Event::Meld(ev) => {
+                self.flush_riichi(stg);
+                let s = ev.seat;
+                let meld = match ev.meld_type {
+                    MeldType::Chi | MeldType::Pon | MeldType::Minkan => {
+                        let from = stg.last_tile.unwrap().0;
+                        let mut ids: Vec<usize> =
+                            ev.consumed.iter().map(|&t| self.take(s, t)).collect();
+                        ids.push(self.last_id);
+                        let m = MjlogMeld {
+                            meld_type: ev.meld_type,
+                            ids,
+                            called: self.last_id,
+                            kui: calc_seat_offset(s, from),
+                        };
+                        self.melds[s].push(m.clone());
+                        m
+                    }
+                    MeldType::Ankan => {
+                        let ids: Vec<usize> =
+                            ev.consumed.iter().map(|&t| self.take(s, t)).collect();
+                        self.last_id = ids[0]; // 国士無双の暗槓ロン
+                        let m = MjlogMeld {
+                            meld_type: ev.meld_type,
+                            called: ids.iter().map(|&i| i / 4 * 4).min().unwrap(),
+                            ids,
+                            kui: 0,
+                        };
+                        self.melds[s].push(m.clone());
+                        m
+                    }
+                    MeldType::Kakan => {
+                        let t = ev.consumed[0];
+                        let id = self.take(s, t);
+                        self.last_id = id; // 槍槓
+                        let m = self.melds[s]
+                            .iter_mut()
+                            .find(|m| m.meld_type == MeldType::Pon && m.ids[0] / 4 == id / 4)
+                            .unwrap();
+                        m.meld_type = MeldType::Kakan;
+                        m.ids.push(id);
+                        m.clone()
+                    }
+                };
+                self.tags
+                    .push(format!(r#"<N who="{}" m="{}"/>"#, s, meld.encode()));
+            }
+            Event::Nukidora(ev) => {
+                let id = self.take(ev.seat, Tile(TZ, WN));
+                self.last_id = id;
+                self.tags
+                    .push(format!(r#"<N who="{}" m="{}"/>"#, ev.seat, id << 8 | 0x20));
+            }
+            Event::Dora(ev) => {
+                let id = self.alloc(ev.tile);
+                self.doras.push(id);
+                self.tags.push(format!(r#"<DORA hai="{}"/>"#, id));
+            }
+            Event::Win(ev) => {
+                self.riichi = None; // 宣言牌でのロン
+                let ura_doras: Vec<usize> = ev.ura_doras.iter().map(|&t| self.alloc(t)).collect();
+
+                // ダブロンの場合は先の和了の点数移動を反映した点数を和了前の点数とする
+                let mut scores = get_scores(stg);
+                for ctx in &ev.contexts {
+                    for s in 0..SEAT {
+                        scores[s] -= ctx.delta_scores[s];
+                    }
+                }
+                for ctx in &ev.contexts {
+                    let tag = self.agari_tag(stg, ctx, &ura_doras, &scores);
+                    self.tags.push(tag);
+                    for s in 0..SEAT {
+                        scores[s] += ctx.delta_scores[s];
+                    }
+                }
+            }
+            Event::Draw(ev) => {
+                if ev.draw_type == DrawType::Suuchariichi {
+                    self.flush_riichi(stg);
+                }
+                self.riichi = None;
+                let mut scores = get_scores(stg);
+                for s in 0..SEAT {
+                    scores[s] -= ev.delta_scores[s];
+                }
+                let mut tag = format!(
+                    r#"<RYUUKYOKU ba="{},{}" sc="{}""#,
+                    stg.honba,
+                    stg.riichi_sticks,
+                    join_sc(&scores, &ev.delta_scores),
+                );
+                for s in 0..SEAT {
+                    if !ev.hands[s].is_empty() {
+                        tag += &format!(r#" hai{}="{}""#, s, join(&self.hand_ids(s)));
+                    }
+                }
+                let type_ = match ev.draw_type {
+                    DrawType::Kouhaiheikyoku => {
+                        if ev.nagashimangan_scores.iter().any(|&s| s != 0) {
+                            "nm"
+                        } else {
+                            ""
+                        }
+                    }
+                    DrawType::Kyushukyuhai => "yao9",
+                    DrawType::Suufuurenda => "kaze4",
+                    DrawType::Suuchariichi => "reach4",
+                    DrawType::Suukansanra => "kan4",
+                    DrawType::Sanchaho => "ron3",
+                    DrawType::Unknown => "",
+                };
+                if !type_.is_empty() {
+                    tag += &format!(r#" type="{}""#, type_);
+                }
+                self.tags.push(tag + "/>");
+            }
+            Event::End(_) => {
+                let owari: Vec<String> = calc_sc(stg).iter().map(|v| v.to_string()).collect();
+                if let Some(tag) = self.tags.last_mut()
+                    && let Some(t) = tag.strip_suffix("/>")
+                {
+                    *tag = format!(r#"{} owari="{}"/>"#, t, owari.join(","));
+                }
+            }
+            Event::Timeout(_) => {}
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        format!(r#"<mjloggm ver="2.3">{}</mjloggm>"#, self.tags.concat())
+    }
+
+    fn push_header(&mut self, rule: &Rule, names: &[String; SEAT]) {
+        // 0x01: 対人戦, 0x02: 赤なし, 0x04: 喰いなし, 0x08: 東南戦, 0x10: 三麻
+        let mut type_ = 0x01;
+        if rule.red5 == 0 {
+            type_ |= 0x02;
+        }
+        if !rule.kuitan {
+            type_ |= 0x04;
+        }
+        if rule.round == 2 {
+            type_ |= 0x08;
+        }
+        if rule.is_sanma {
+            type_ |= 0x10;
+        }
+        self.tags
+            .push(format!(r#"<GO type="{}" lobby="0"/>"#, type_));
+        let mut un = "<UN".to_string();
+        for (s, name) in names.iter().enumerate() {
+            un += &format!(r#" n{}="{}""#, s, encode_name(name));
+        }
+        un += r#" dan="0,0,0,0" rate="1500.00,1500.00,1500.00,1500.00" sx="C,C,C,C"/>"#;
+        self.tags.push(un);
+        self.tags.push(r#"<TAIKYOKU oya="0"/>"#.into());
+    }
+
+    fn agari_tag(
+        &mut self,
+        stg: &Stage,
+        ctx: &WinContext,
+        ura_doras: &[usize],
+        scores: &[Score; SEAT],
+    ) -> String {
+        let s = ctx.seat;
+        let sc = &ctx.score_context;
+        let machi = if ctx.is_drawn {
+            let &(id, _) = self.hands[s]
+                .iter()
+                .rev()
+                .find(|&&(_, t)| t == ctx.winning_tile)
+                .unwrap();
+            id
+        } else {
+            self.last_id
+        };
+        let mut hai = self.hand_ids(s);
+        if !ctx.is_drawn {
+            hai.push(machi);
+            hai.sort();
+        }
+        let limit = match sc.title.as_str() {
+            "満貫" => 1,
+            "跳満" => 2,
+            "倍満" => 3,
+            "三倍満" => 4,
+            "" => 0,
+            _ => 5, // 役満, 数え役満
+        };
+
+        let mut tag = format!(
+            r#"<AGARI ba="{},{}" hai="{}""#,
+            stg.honba,
+            stg.riichi_sticks,
+            join(&hai)
+        );
+        if !self.melds[s].is_empty() {
+            let m: Vec<usize> = self.melds[s].iter().map(|m| m.encode()).collect();
+            tag += &format!(r#" m="{}""#, join(&m));
+        }
+        tag += &format!(
+            r#" machi="{}" ten="{},{},{}""#,
+            machi, sc.fu, sc.score, limit
+        );
+        let yaku_id = |name: &str| {
+            MJLOG_YAKU_IDS
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|&(_, id)| id)
+        };
+        if sc.yakuman > 0 {
+            let ids: Vec<usize> = sc.yakus.iter().filter_map(|y| yaku_id(&y.name)).collect();
+            tag += &format!(r#" yakuman="{}""#, join(&ids));
+        } else {
+            let mut yakus = vec![];
+            for y in &sc.yakus {
+                if let Some(id) = yaku_id(&y.name) {
+                    yakus.extend([id, y.fan]);
+                }
+            }
+            tag += &format!(r#" yaku="{}""#, join(&yakus));
+        }
+        tag += &format!(r#" doraHai="{}""#, join(&self.doras));
+        if ctx.is_riichi {
+            tag += &format!(r#" doraHaiUra="{}""#, join(ura_doras));
+        }
+        tag += &format!(r#" who="{}" fromWho="{}""#, s, stg.turn);
+        if let Some(pao) = ctx.pao {
+            tag += &format!(r#" paoWho="{}""#, pao);
+        }
+        tag += &format!(r#" sc="{}"/>"#, join_sc(scores, &ctx.delta_scores));
+        tag
+    }
+
+    fn flush_riichi(&mut self, stg: &Stage) {
+        if let Some(s) = self.riichi.take() {
+            let ten = get_scores(stg).map(|s| s / 100);
+            self.tags.push(format!(
+                r#"<REACH who="{}" ten="{}" step="2"/>"#,
+                s,
+                join(&ten)
+            ));
+        }
+    }
+
+    // 未使用の牌IDを割り当て (赤5は各色の0番目のIDを優先)
+    fn alloc(&mut self, t: Tile) -> usize {
+        let kind = tile_to_kind(t);
+        let order = if t.is_suit() && t.1 == 0 {
+            [0, 1, 2, 3]
+        } else if t.is_suit() && t.1 == 5 {
+            [1, 2, 3, 0]
+        } else {
+            [0, 1, 2, 3]
+        };
+        let id = order
+            .iter()
+            .map(|c| kind * 4 + c)
+            .find(|&id| !self.used[id]);
+        // 同じ牌が5枚以上現れるのはイベント列が不正な場合のみ
+        debug_assert!(id.is_some(), "no free tile id: {}", t);
+        let id = id.unwrap_or_else(|| {
+            error!("no free tile id: {}", t);
+            kind * 4
+        });
+        self.used[id] = true;
+        id
+    }
+
+    // 手牌から牌を取り除いてIDを返却
+    fn take(&mut self, seat: Seat, t: Tile) -> usize {
+        let i = self.hands[seat].iter().position(|&(_, t2)| t2 == t);
+        // 手牌に存在しない牌を取り除くのはイベント列が不正な場合のみ
+        debug_assert!(i.is_some(), "tile not in hand: seat={}, tile={}", seat, t);
+        match i {
+            Some(i) => self.hands[seat].remove(i).0,
+            None => {
+                error!("tile not in hand: seat={}, tile={}", seat, t);
+                self.alloc(t)
+            }
+        }
+    }
+
+    fn hand_ids(&self, seat: Seat) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.hands[seat].iter().map(|&(id, _)| id).collect();
+        ids.sort();
+        ids
+    }
+}
+
+impl Default for MjlogSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// [Utility]
+// 牌の種類 (0~33: 萬子, 筒子, 索子, 字牌の順)
+fn tile_to_kind(t: Tile) -> usize {
+    let t = t.to_normal();
+    t.0 * 9 + t.1 - 1
+}
+
+fn join<T: ToString>(v: &[T]) -> String {
+    v.iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// [点数/100, 点数変動/100] * 座席数
+fn join_sc(scores: &[Score; SEAT], deltas: &[Point; SEAT]) -> String {
+    let sc: Vec<i32> = (0..SEAT)
+        .flat_map(|s| [scores[s] / 100, deltas[s] / 100])
+        .collect();
+    join(&sc)
+}
+
+// 天鳳と同様にプレイヤー名はURLエンコード
+fn encode_name(name: &str) -> String {
+    let mut res = String::new();
+    for b in name.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'_' || b == b'-' {
+            res.push(b as char);
+        } else {
+            res += &format!("%{:02X}", b);
+        }
+    }
+    res
+}
+
+#[test]
+fn test_mjlog_meld() {
+    // 天鳳の牌譜ビューアと同じ手順でデコードして確認
+    let chi = MjlogMeld {
+        meld_type: MeldType::Chi,
+        ids: vec![12 * 4 + 1, 13 * 4 + 2, 11 * 4], // 4p, 5p, 3p
+        called: 11 * 4,
+        kui: 3,
+    };
+    let m = chi.encode();
+    assert_eq!(m & 0x7, 0x4 | 3);
+    let t = (m >> 10) / 3;
+    assert_eq!((m >> 10) % 3, 0); // 鳴いた牌は最小の牌
+    assert_eq!(t / 7 * 9 + t % 7, 11); // 3p
+    assert_eq!((m >> 5) & 3, 1);
+    assert_eq!((m >> 7) & 3, 2);
+
+    let mut pon = MjlogMeld {
+        meld_type: MeldType::Pon,
+        ids: vec![16, 18, 19], // 赤5mを含むポン
+        called: 19,
+        kui: 2,
+    };
+    let m = pon.encode();
+    assert_eq!(m & 0x1f, 0x8 | 2);
+    assert_eq!((m >> 5) & 3, 1); // 使用していない牌
+    assert_eq!((m >> 9) / 3, 4);
+    assert_eq!((m >> 9) % 3, 2);
+
+    pon.meld_type = MeldType::Kakan;
+    pon.ids.push(17);
+    let m = pon.encode();
+    assert_eq!(m & 0x1f, 0x10 | 2);
+    assert_eq!((m >> 5) & 3, 1);
+
+    assert_eq!(encode_name("A あ"), "A%20%E3%81%82");
+}
+
+#[test]
+fn test_mjlog_game() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::control::{
+        actor::Actor,
+        engine::MahjongEngine,
+        testing::{FnListener, Greedy},
+    };
+
+    // 牌譜と和了イベント,終局時の点数を記録
+    #[derive(Default)]
+    struct Record {
+        xml: String,
+        wins: Vec<EventWin>,
+        scores: [Score; SEAT],
+    }
+
+    // tag内の属性値をカンマ区切りで取得
+    fn attr(tag: &str, name: &str) -> Vec<f64> {
+        let key = format!(r#" {}=""#, name);
+        let i = tag
+            .find(&key)
+            .unwrap_or_else(|| panic!("{} not in {}", name, tag))
+            + key.len();
+        let v = &tag[i..i + tag[i..].find('"').unwrap()];
+        v.split(',').map(|x| x.parse().unwrap()).collect()
+    }
+    fn tags<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+        let start = format!("<{} ", name);
+        xml.split_inclusive("/>")
+            .filter(|t| t.starts_with(&start))
+            .collect()
+    }
+
+    let rule = Rule {
+        round: 1,
+        initial_score: 25000,
+        settlement_score: 30000,
+        red5: 1,
+        bust: true,
+        ..Default::default()
+    };
+    let record = Arc::new(Mutex::new(Record::default()));
+    let mut serializer = MjlogSerializer::new();
+    let r = record.clone();
+    let recorder = FnListener(move |stg: &Stage, event: &Event| {
+        serializer.push_event(stg, event);
+        let mut r = r.lock().unwrap();
+        match event {
+            Event::Win(ev) => r.wins.push(ev.clone()),
+            Event::End(_) => {
+                r.xml = serializer.serialize();
+                r.scores = get_scores(stg);
+            }
+            _ => {}
+        }
+    });
+    let actors: [Box<dyn Actor>; SEAT] = [
+        Box::new(Greedy::new(true)),
+        Box::new(Greedy::new(false)),
+        Box::new(Greedy::new(true)),
+        Box::new(Greedy::new(false)),
+    ];
+    let mut game = MahjongEngine::new(
+        1,
+        rule,
+        0.0,
+        actors,
+        vec![Box::new(recorder)],
+        [false; SEAT],
+    );
+    game.run();
+
+    let r = record.lock().unwrap();
+    let xml = &r.xml;
+    assert!(xml.starts_with(r#"<mjloggm ver="2.3"><GO type="1" lobby="0"/>"#));
+    assert!(!tags(xml, "N").is_empty(), "no call: {}", xml);
+    assert!(xml.contains(r#"step="2"/>"#), "no riichi: {}", xml);
+
+    // 配牌: 牌IDは重複せず,各13枚
+    let inits = tags(xml, "INIT");
+    assert!(!inits.is_empty());
+    let init = inits[0];
+    let seed = attr(init, "seed");
+    assert_eq!(seed.len(), 6);
+    assert_eq!(&seed[..3], &[0.0, 0.0, 0.0]);
+    assert_eq!(attr(init, "ten"), vec![250.0; 4]);
+    assert_eq!(attr(init, "oya"), vec![0.0]);
+    let mut ids = vec![seed[5] as usize]; // ドラ表示牌
+    for s in 0..SEAT {
+        let hai = attr(init, &format!("hai{}", s));
+        assert_eq!(hai.len(), 13);
+        ids.extend(hai.iter().map(|&id| id as usize));
+    }
+    assert!(ids.iter().all(|&id| id < 136));
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 13 * 4 + 1);
+
+    // 和了: 和了者,放銃者,点数,点数移動が和了イベントと一致
+    let agaris = tags(xml, "AGARI");
+    let ctxs: Vec<&WinContext> = r.wins.iter().flat_map(|ev| &ev.contexts).collect();
+    assert!(!agaris.is_empty(), "no win: {}", xml);
+    assert_eq!(agaris.len(), ctxs.len());
+    for (agari, ctx) in agaris.iter().zip(ctxs) {
+        assert_eq!(attr(agari, "who"), vec![ctx.seat as f64]);
+        if ctx.is_drawn {
+            assert_eq!(attr(agari, "fromWho"), vec![ctx.seat as f64]);
+        }
+        assert_eq!(attr(agari, "ten")[1], ctx.score_context.score as f64);
+        let hai = attr(agari, "hai");
+        assert!(hai.contains(&attr(agari, "machi")[0]));
+        let sc = attr(agari, "sc");
+        for s in 0..SEAT {
+            assert_eq!(
+                sc[s * 2 + 1],
+                (ctx.delta_scores[s] / 100) as f64,
+                "{}",
+                agari
+            );
+        }
+        if ctx.is_riichi {
+            assert!(agari.contains("doraHaiUra="));
+        }
+    }
+
+    // 終局: 最後のタグに最終点数とポイントを出力
+    let last = xml
+        .strip_suffix("</mjloggm>")
+        .unwrap()
+        .rsplit('<')
+        .next()
+        .unwrap();
+    let owari = attr(last, "owari");
+    assert_eq!(owari.len(), 8);
+    for s in 0..SEAT {
+        assert_eq!(owari[s * 2], (r.scores[s] / 100) as f64);
+    }
+    let points: f64 = (0..SEAT).map(|s| owari[s * 2 + 1]).sum();
+    assert!(points.abs() < 1e-6);
+}
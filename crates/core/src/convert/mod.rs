@@ -1,3 +1,4 @@
 // Mjaiや天鳳などの外部ツール用に出力を変換するためのモジュール
 pub mod mjai;
+pub mod mjlog;
 pub mod tenhou;
@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 
-use crate::{control::common::calc_seat_offset, model::*};
+use crate::{
+    control::{common::*, stage_controller::apply_event},
+    hand::calc_points,
+    model::*,
+    util::misc::Res,
+};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct TenhouLog {
@@ -19,6 +24,8 @@ pub struct TenhouLog {
     pub rate: Option<[f32; SEAT]>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sx: Option<[String; SEAT]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sc: Option<Vec<Value>>, // 終局時の[点数/100, ウマ・オカ込みのポイント] * 座席数
 }
 
 impl TenhouLog {
@@ -28,9 +35,16 @@ impl TenhouLog {
         log.rule.aka = 1;
         log
     }
+
+    pub fn set_rule(&mut self, rule: &Rule) {
+        self.rule.disp = rule_to_disp(rule);
+        self.rule.aka = if rule.red5 > 0 { 1 } else { 0 };
+    }
 }
 
+// 天鳳の牌譜は赤ドラを"aka51"~"aka53"のみで記録する場合がある
 #[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TenhouRule {
     pub disp: String,
     pub aka: usize,
@@ -82,6 +96,7 @@ impl TenhouRound {
 }
 
 // [TenhouSerializer]
+// 1試合分の牌譜を記録 (logには終了した局のみを追加)
 #[derive(Debug)]
 pub struct TenhouSerializer {
     log: TenhouLog,
@@ -99,8 +114,12 @@ impl TenhouSerializer {
     pub fn push_event(&mut self, stg: &Stage, event: &Event) {
         let k = &mut self.dealer;
         match event {
-            Event::Begin(_) => {}
+            Event::Begin(_) => {
+                self.log = TenhouLog::new();
+            }
             Event::New(ev) => {
+                self.log.set_rule(&ev.rule);
+                self.log.name = ev.names.clone();
                 self.dealer = TenhouRound::default();
                 let k = &mut self.dealer;
                 k.dealer = ev.round * 4 + ev.dealer;
@@ -152,7 +171,7 @@ impl TenhouSerializer {
                     let t = ev.consumed[0];
                     let tn = t.to_normal();
                     for m in &stg.players[ev.seat].melds {
-                        if m.tiles[0].to_normal() == tn {
+                        if m.meld_type == MeldType::Kakan && m.tiles[0].to_normal() == tn {
                             let mut meld = String::new();
                             for i in 0..3 {
                                 if m.froms[i] != ev.seat {
@@ -175,7 +194,10 @@ impl TenhouSerializer {
                     k.players[ev.seat].discards.push(json!(meld.concat()));
                 }
             },
-            Event::Nukidora(_) => panic!(),
+            Event::Nukidora(ev) => {
+                // 北抜きは捨て牌の列に"f44"として記録
+                k.players[ev.seat].discards.push(json!("f44"));
+            }
             Event::Dora(ev) => {
                 k.doras.push(tile_to_tenhou(ev.tile));
             }
@@ -187,11 +209,15 @@ impl TenhouSerializer {
                     k.result_detail
                         .push(ctx.delta_scores.iter().map(|&p| json!(p)).collect());
                     // detail = [和了者, 放銃者, 責任払い?]
-                    let mut detail = vec![json!(ctx.seat), json!(stg.turn), json!(ctx.seat)];
+                    let mut detail = vec![
+                        json!(ctx.seat),
+                        json!(stg.turn),
+                        json!(ctx.pao.unwrap_or(ctx.seat)),
+                    ];
                     let title = if score_ctx.title.is_empty() {
                         format!("{}符{}飜", score_ctx.fu, score_ctx.fan)
                     } else {
-                        if score_ctx.yakuman != 0 || score_ctx.fan >= 13 {
+                        if score_ctx.yakuman != 0 || score_ctx.title == "数え役満" {
                             "役満"
                         } else {
                             &score_ctx.title
@@ -210,24 +236,56 @@ impl TenhouSerializer {
                     } else {
                         detail.push(json!(format!("{}{}点", title, score_ctx.points.0)));
                     }
+                    // 役満の役は天鳳と同様に"(役満)"とする (ダブル役満以上は"(2倍役満)"など)
                     for y in &score_ctx.yakus {
-                        detail.push(json!(format!("{}({}飜)", y.name, y.fan)));
+                        detail.push(json!(match (score_ctx.yakuman, y.fan) {
+                            (0, _) => format!("{}({}飜)", y.name, y.fan),
+                            (_, 1) => format!("{}(役満)", y.name),
+                            (_, n) => format!("{}({}倍役満)", y.name, n),
+                        }));
                     }
                     k.result_detail.push(detail);
                 }
+                self.log.log.push(k.to_log());
             }
             Event::Draw(ev) => {
-                // TODO
-                k.result = "流局".into();
-                k.result_detail
-                    .push(ev.delta_scores.iter().map(|&p| json!(p)).collect());
+                k.result = match ev.draw_type {
+                    DrawType::Kouhaiheikyoku => {
+                        let n_seat = get_n_seat(stg);
+                        let n_tenpai = ev.hands[..n_seat].iter().filter(|h| !h.is_empty()).count();
+                        if ev.nagashimangan_scores.iter().any(|&s| s != 0) {
+                            "流し満貫"
+                        } else if n_tenpai == n_seat {
+                            "全員聴牌"
+                        } else if n_tenpai == 0 {
+                            "全員不聴"
+                        } else {
+                            "流局"
+                        }
+                    }
+                    DrawType::Kyushukyuhai => "九種九牌",
+                    DrawType::Suufuurenda => "四風連打",
+                    DrawType::Suuchariichi => "四家立直",
+                    DrawType::Suukansanra => "四槓散了",
+                    DrawType::Sanchaho => "三家和了",
+                    DrawType::Unknown => "流局",
+                }
+                .into();
+                // 点数移動がない場合は省略
+                if ev.delta_scores.iter().any(|&p| p != 0) {
+                    k.result_detail
+                        .push(ev.delta_scores.iter().map(|&p| json!(p)).collect());
+                }
+                self.log.log.push(k.to_log());
+            }
+            Event::End(_) => {
+                self.log.sc = Some(calc_sc(stg));
             }
-            Event::End(_) => {}
+            Event::Timeout(_) => {}
         }
     }
 
-    pub fn serialize(&mut self) -> String {
-        self.log.log = vec![self.dealer.to_log()];
+    pub fn serialize(&self) -> String {
         serde_json::to_string(&self.log).unwrap()
     }
 }
@@ -238,8 +296,630 @@ impl Default for TenhouSerializer {
     }
 }
 
+// 終局時の点数とポイント (ウマとオカは天鳳と同じ 4麻: 20-10, 3麻: ±15)
+pub fn calc_sc(stg: &Stage) -> Vec<Value> {
+    let rule = &stg.rule;
+    let n_seat = get_n_seat(stg);
+    let uma: &[f64] = if rule.is_sanma {
+        &[15.0, 0.0, -15.0]
+    } else {
+        &[20.0, 10.0, -10.0, -20.0]
+    };
+    let oka = (rule.settlement_score - rule.initial_score) as f64 * n_seat as f64 / 1000.0;
+    let mut sc = vec![];
+    for s in 0..SEAT {
+        if s >= n_seat {
+            sc.extend([json!(0), json!(0)]);
+            continue;
+        }
+        let pl = &stg.players[s];
+        let mut pt = (pl.score - rule.settlement_score) as f64 / 1000.0 + uma[pl.rank];
+        if pl.rank == 0 {
+            pt += oka;
+        }
+        sc.extend([json!(pl.score / 100), json!((pt * 10.0).round() / 10.0)]);
+    }
+    sc
+}
+
+// 天鳳の牌譜ビューアに表示されるルール名 (例: 東喰赤, 三南喰赤)
+// 天鳳に存在しないルール(飛びなし, 飛びの点数)は末尾に追記
+fn rule_to_disp(rule: &Rule) -> String {
+    let mut disp = String::new();
+    if rule.is_sanma {
+        disp += "三";
+    }
+    disp += if rule.round == 2 { "南" } else { "東" };
+    if rule.kuitan {
+        disp += "喰";
+    }
+    if rule.red5 > 0 {
+        disp += "赤";
+    }
+    if !rule.bust {
+        disp += "飛無";
+    } else if rule.bust_score != 0 {
+        disp += &format!("飛{}", rule.bust_score);
+    }
+    disp
+}
+
 // [TenhouDeserializer]
-// struct TenhouDeserializer {}
+// 天鳳形式の牌譜を局ごとのイベント列(EventNew ~ EventWin | EventDraw)に変換
+// 天鳳の牌譜には牌山の情報がないため, EventNewの牌山関連のフィールドは空になる
+pub fn tenhou_to_events(log: &TenhouLog) -> Res<Vec<Vec<Event>>> {
+    let r = &log.rule;
+    let aka = r.aka.max(r.aka51).max(r.aka52).max(r.aka53);
+    let mut rule = rule_from_disp(&r.disp, aka);
+    let n_seat = calc_n_seat(rule.is_sanma);
+    if let Some(scores) = log
+        .log
+        .first()
+        .and_then(|r| r.get(1))
+        .and_then(|v| v.as_array())
+    {
+        let total: i64 = scores.iter().filter_map(|v| v.as_i64()).sum();
+        rule.initial_score = (total / n_seat as i64) as Score;
+        rule.settlement_score = rule.initial_score + 5000; // 天鳳の返し点 (25000点持ち30000点返し)
+    }
+
+    let mut rounds = vec![];
+    for (i, v) in log.log.iter().enumerate() {
+        let mut de = TenhouDeserializer::new(rule.clone(), log.name.clone());
+        de.parse_round(v)
+            .map_err(|e| format!("round {}: {}", i, e))?;
+        rounds.push(de.events);
+    }
+    Ok(rounds)
+}
+
+struct TenhouDeserializer {
+    rule: Rule,
+    names: [String; SEAT],
+    stage: Stage, // 手牌や副露の状態を追跡するための卓情報
+    events: Vec<Event>,
+    takes: [Vec<Value>; SEAT],    // 配牌以降に手牌に加わった牌 (ツモ, 鳴き)
+    discards: [Vec<Value>; SEAT], // 打牌, 加槓, 暗槓, 北抜き
+    cursors: [(usize, usize); SEAT], // (takes, discards)の読み込み位置
+    doras: Vec<Tile>,
+    n_dora: usize,     // 公開済みのドラ表示牌の数
+    is_kan_dora: bool, // 明槓,加槓の槓ドラを次の打牌後に公開
+}
+
+impl TenhouDeserializer {
+    fn new(rule: Rule, names: [String; SEAT]) -> Self {
+        Self {
+            rule,
+            names,
+            stage: Stage::default(),
+            events: vec![],
+            takes: Default::default(),
+            discards: Default::default(),
+            cursors: [(0, 0); SEAT],
+            doras: vec![],
+            n_dora: 0,
+            is_kan_dora: false,
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        apply_event(&mut self.stage, &event);
+        self.events.push(event);
+    }
+
+    // log: [[局, 本場, 供託], 点数, ドラ, 裏ドラ, (配牌, 取得牌, 捨て牌) * 座席数, 結果]
+    fn parse_round(&mut self, v: &Value) -> Res {
+        let n_seat = calc_n_seat(self.rule.is_sanma);
+        let a = v.as_array().ok_or("round is not an array")?;
+        if a.len() < 4 + 3 * n_seat + 1 {
+            return Err("too few elements in round".into());
+        }
+
+        let info = as_i64_vec(&a[0])?;
+        if info.len() != 3 {
+            return Err("invalid round info".into());
+        }
+        let kyoku = info[0] as usize;
+        let (round, dealer) = (kyoku / 4, kyoku % 4);
+        if dealer >= n_seat {
+            return Err(format!("invalid dealer: {}", dealer).into());
+        }
+        let mut scores = [0; SEAT];
+        for (s, &sc) in as_i64_vec(&a[1])?.iter().take(SEAT).enumerate() {
+            scores[s] = sc as Score;
+        }
+        self.doras = tiles_from_tenhou(&as_i64_vec(&a[2])?)?;
+        if self.doras.is_empty() {
+            return Err("no dora indicator".into());
+        }
+        let mut hands = [vec![], vec![], vec![], vec![]];
+        for s in 0..n_seat {
+            hands[s] = tiles_from_tenhou(&as_i64_vec(&a[4 + 3 * s])?)?;
+            if hands[s].len() != 13 {
+                return Err(format!("invalid hand size: seat {}", s).into());
+            }
+            self.takes[s] = a[5 + 3 * s].as_array().cloned().unwrap_or_default();
+            self.discards[s] = a[6 + 3 * s].as_array().cloned().unwrap_or_default();
+        }
+
+        // 王牌14枚と配牌を除いた牌山の残り枚数
+        let wall_count = if self.rule.is_sanma { 55 } else { 70 };
+        self.n_dora = 1;
+        self.push(Event::new(
+            self.rule.clone(),
+            round,
+            dealer,
+            info[1] as usize,
+            info[2] as usize,
+            vec![self.doras[0]],
+            self.names.clone(),
+            scores,
+            hands,
+            wall_count,
+            0,
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+        ));
+
+        let mut s = dealer;
+        let mut is_playing = self.deal(s, false)?;
+        while is_playing {
+            // ツモ番の操作 (捨て牌の列がなくなった場合はツモ和了または途中流局)
+            let Some(d) = self.next_discard(s) else {
+                break;
+            };
+            if let Value::String(m) = &d
+                && !m.starts_with('r')
+            {
+                is_playing = self.turn_meld(s, m)?;
+                continue;
+            }
+            self.discard(s, &d)?;
+
+            // 鳴き操作 (鳴きがなければ次のプレイヤーのツモ)
+            if let Some((c, meld_type)) = self.call(s)? {
+                s = c;
+                if meld_type == MeldType::Minkan {
+                    is_playing = self.deal(s, true)?;
+                    self.is_kan_dora = true;
+                }
+            } else {
+                s = (s + 1) % n_seat;
+                is_playing = self.deal(s, false)?;
+            }
+        }
+
+        let result = a
+            .last()
+            .unwrap()
+            .as_array()
+            .ok_or("result is not an array")?;
+        self.parse_result(&as_i64_vec(&a[3])?, result)
+    }
+
+    fn next_discard(&mut self, s: Seat) -> Option<Value> {
+        loop {
+            let d = self.discards[s].get(self.cursors[s].1)?.clone();
+            self.cursors[s].1 += 1;
+            // 大明槓の後の捨て牌の列には0が挿入される
+            if d.as_i64() != Some(0) {
+                return Some(d);
+            }
+        }
+    }
+
+    // 取得牌の列から次のツモ牌を読み込み (列がなければfalseを返却)
+    fn deal(&mut self, s: Seat, is_replacement: bool) -> Res<bool> {
+        let Some(v) = self.takes[s].get(self.cursors[s].0) else {
+            return Ok(false);
+        };
+        let n = v
+            .as_i64()
+            .ok_or_else(|| format!("unexpected meld: {}", v))?;
+        let t = tile_from_tenhou(n)?;
+        if self.stage.wall_count == 0 {
+            return Err("no tiles left in wall".into());
+        }
+        self.cursors[s].0 += 1;
+        self.push(Event::deal(s, t, is_replacement));
+        Ok(true)
+    }
+
+    fn discard(&mut self, s: Seat, d: &Value) -> Res {
+        let (n, is_riichi) = match d {
+            Value::String(r) => (r[1..].parse()?, true),
+            _ => (d.as_i64().ok_or("invalid discard")?, false),
+        };
+        let pl = &self.stage.players[s];
+        let is_drawn = n == 60; // ツモ切り
+        let t = if is_drawn {
+            pl.drawn.ok_or("tsumogiri without drawn tile")?
+        } else {
+            tile_from_tenhou(n)?
+        };
+        if count_tile(&pl.hand, t) == 0 {
+            return Err(format!("tile {} not in hand: seat {}", t, s).into());
+        }
+        self.push(Event::discard(s, t, is_drawn, is_riichi));
+        self.flush_kan_dora();
+        Ok(())
+    }
+
+    // 加槓, 暗槓, 北抜き (嶺上牌のツモがなければ槍槓などで局が終了したものとしてfalseを返却)
+    fn turn_meld(&mut self, s: Seat, m: &str) -> Res<bool> {
+        let tiles = parse_meld(m)?;
+        let hand = &self.stage.players[s].hand;
+        let (event, is_ankan) = if m.contains('k') {
+            let &(_, t) = tiles
+                .iter()
+                .find(|(c, _)| *c == Some('k'))
+                .ok_or("invalid kakan")?;
+            let has_pon = self.stage.players[s]
+                .melds
+                .iter()
+                .any(|m| m.meld_type == MeldType::Pon && m.tiles[0].to_normal() == t.to_normal());
+            if !has_pon || count_tile(hand, t) == 0 {
+                return Err(format!("invalid kakan: {}", m).into());
+            }
+            (Event::meld(s, MeldType::Kakan, vec![t], false), false)
+        } else if m.contains('a') {
+            let consumed: Vec<Tile> = tiles.iter().map(|&(_, t)| t).collect();
+            if consumed.len() != 4 || consumed.iter().any(|&t| count_tile(hand, t) == 0) {
+                return Err(format!("invalid ankan: {}", m).into());
+            }
+            (Event::meld(s, MeldType::Ankan, consumed, false), true)
+        } else if m.contains('f') {
+            let t = Tile(TZ, WN);
+            if count_tile(hand, t) == 0 {
+                return Err(format!("invalid nukidora: {}", m).into());
+            }
+            (Event::nukidora(s, false), false)
+        } else {
+            return Err(format!("unknown turn action: {}", m).into());
+        };
+        let is_kan = matches!(event, Event::Meld(_));
+        self.push(event);
+        self.flush_kan_dora();
+        if is_ankan {
+            self.reveal_dora(); // 暗槓の槓ドラは嶺上牌のツモ前
+        }
+
+        let is_dealt = self.deal(s, true)?;
+        if is_dealt && is_kan && !is_ankan {
+            self.is_kan_dora = true;
+        }
+        Ok(is_dealt)
+    }
+
+    // 直前の捨て牌を鳴いたプレイヤーを探索 (ポン・明槓をチーより優先)
+    fn call(&mut self, discarder: Seat) -> Res<Option<(Seat, MeldType)>> {
+        let n_seat = calc_n_seat(self.rule.is_sanma);
+        let (_, _, d) = self.stage.last_tile.unwrap();
+        let mut chi = None;
+        for s in (0..n_seat).filter(|&s| s != discarder) {
+            let Some(Value::String(m)) = self.takes[s].get(self.cursors[s].0) else {
+                continue;
+            };
+            let tiles = parse_meld(m)?;
+            let Some(pos) = tiles.iter().position(|(c, _)| c.is_some()) else {
+                return Err(format!("invalid meld: {}", m).into());
+            };
+            let (marker, t) = tiles[pos];
+            if t != d {
+                continue;
+            }
+            let meld_type = match marker {
+                Some('c') => MeldType::Chi,
+                Some('p') => MeldType::Pon,
+                Some('m') => MeldType::Minkan,
+                _ => return Err(format!("invalid meld: {}", m).into()),
+            };
+            // 鳴いた牌の位置: 上家0, 対面1, 下家2(明槓は3)
+            // 3麻の場合は位置と座席の対応が牌譜の出力元によって異なるため確認しない
+            let from = (s + 3 - pos.min(2)) % SEAT;
+            if from != discarder && !self.rule.is_sanma {
+                continue;
+            }
+            let consumed: Vec<Tile> = tiles
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != pos)
+                .map(|(_, &(_, t))| t)
+                .collect();
+            if meld_type == MeldType::Chi {
+                if s == (discarder + 1) % n_seat {
+                    chi = Some((s, meld_type, consumed));
+                }
+                continue;
+            }
+            return self.meld(s, meld_type, consumed).map(Some);
+        }
+
+        match chi {
+            Some((s, meld_type, consumed)) => self.meld(s, meld_type, consumed).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn meld(&mut self, s: Seat, meld_type: MeldType, consumed: Vec<Tile>) -> Res<(Seat, MeldType)> {
+        let pl = &self.stage.players[s];
+        let mut hand = pl.hand;
+        for &t in &consumed {
+            if count_tile(&hand, t) == 0 {
+                return Err(format!("tile {} not in hand: seat {}", t, s).into());
+            }
+            dec_tile(&mut hand, t);
+        }
+        let (_, _, d) = self.stage.last_tile.unwrap();
+        let (is_pao, is_pao_kan) = check_pao(pl, meld_type, d);
+        self.cursors[s].0 += 1;
+        self.push(Event::meld(s, meld_type, consumed, is_pao || is_pao_kan));
+        Ok((s, meld_type))
+    }
+
+    fn reveal_dora(&mut self) {
+        if let Some(&t) = self.doras.get(self.n_dora) {
+            self.n_dora += 1;
+            self.push(Event::dora(t));
+        }
+    }
+
+    fn flush_kan_dora(&mut self) {
+        if self.is_kan_dora {
+            self.is_kan_dora = false;
+            self.reveal_dora();
+        }
+    }
+
+    // result: ["和了", 点数変動, 和了情報, (ダブロンの場合は点数変動, 和了情報が続く)]
+    //         ["流局", 点数変動] | ["九種九牌"] など
+    fn parse_result(&mut self, ura_doras: &[i64], result: &[Value]) -> Res {
+        let title = result
+            .first()
+            .and_then(|v| v.as_str())
+            .ok_or("invalid result")?;
+        let draw_type = match title {
+            "和了" => {
+                let mut delta_scores = [0; SEAT];
+                let mut contexts = vec![];
+                for pair in result[1..].chunks(2) {
+                    let [d, detail] = pair else {
+                        return Err("invalid win result".into());
+                    };
+                    let d = to_points(&as_i64_vec(d)?);
+                    let ctx = self.parse_win(d, detail)?;
+                    for s in 0..SEAT {
+                        delta_scores[s] += d[s];
+                    }
+                    contexts.push(ctx);
+                }
+                let ura_doras = tiles_from_tenhou(ura_doras)?;
+                self.push(Event::win(ura_doras, delta_scores, contexts));
+                return Ok(());
+            }
+            "流局" | "全員聴牌" | "全員不聴" | "流し満貫" => DrawType::Kouhaiheikyoku,
+            "九種九牌" => DrawType::Kyushukyuhai,
+            "四家立直" => DrawType::Suuchariichi,
+            "三家和了" => DrawType::Sanchaho,
+            "四槓散了" => DrawType::Suukansanra,
+            "四風連打" => DrawType::Suufuurenda,
+            _ => return Err(format!("unknown result: {}", title).into()),
+        };
+
+        let mut delta_scores = [0; SEAT];
+        for v in &result[1..] {
+            let d = to_points(&as_i64_vec(v)?);
+            for s in 0..SEAT {
+                delta_scores[s] += d[s];
+            }
+        }
+        let mut nm_scores = [0; SEAT];
+        if title == "流し満貫" {
+            for s in 0..SEAT {
+                nm_scores[s] = delta_scores[s].max(0);
+            }
+        }
+
+        let hands = get_draw_hands(&self.stage, draw_type);
+        self.push(Event::draw(draw_type, delta_scores, nm_scores, hands));
+        Ok(())
+    }
+
+    // detail: [和了者, 放銃者(ツモの場合は和了者), 責任払い(なければ和了者), 点数, 役...]
+    fn parse_win(&self, delta_scores: [Point; SEAT], detail: &Value) -> Res<WinContext> {
+        let detail = detail.as_array().ok_or("invalid win detail")?;
+        if detail.len() < 4 {
+            return Err("too few elements in win detail".into());
+        }
+        let seat = detail[0].as_u64().ok_or("invalid winner")? as Seat;
+        let from = detail[1].as_u64().ok_or("invalid loser")? as Seat;
+        let pao = detail[2].as_u64().ok_or("invalid pao")? as Seat;
+        if seat >= SEAT || from >= SEAT || pao >= SEAT {
+            return Err("invalid seat in win detail".into());
+        }
+        let is_drawn = seat == from;
+        let is_dealer = seat == self.stage.dealer;
+
+        let pl = &self.stage.players[seat];
+        let mut hand = pl.hand;
+        let winning_tile = if is_drawn {
+            let t = pl.drawn.ok_or("tsumo without drawn tile")?;
+            dec_tile(&mut hand, t);
+            t
+        } else {
+            let (_, _, t) = self.stage.last_tile.ok_or("ron without discarded tile")?;
+            t
+        };
+
+        let (fu, mut fan, title, pays) = parse_points(detail[3].as_str().ok_or("invalid points")?)?;
+        let mut yakus = vec![];
+        let mut n_yakuman = 0;
+        for y in &detail[4..] {
+            let y = y.as_str().ok_or("invalid yaku")?;
+            let (name, rest) = y.split_once('(').ok_or("invalid yaku")?;
+            let fan = if rest.starts_with("役満") {
+                n_yakuman += 1;
+                1
+            } else if let Some(n) = rest.strip_suffix("倍役満)") {
+                let n = n.parse()?;
+                n_yakuman += n;
+                n
+            } else {
+                rest.trim_end_matches(')').trim_end_matches('飜').parse()?
+            };
+            yakus.push(Yaku {
+                name: name.into(),
+                fan,
+            });
+        }
+
+        // 役満の役は"(役満)", "(2倍役満)"または倍率を飜数として"(1飜)"の形式で記録される
+        let fan_sum: usize = yakus.iter().map(|y| y.fan).sum();
+        let mut yakuman = 0;
+        if n_yakuman > 0 {
+            yakuman = n_yakuman;
+        } else if title.contains("役満") && fan_sum < 13 {
+            yakuman = fan_sum;
+        } else if fan == 0 {
+            fan = fan_sum;
+        }
+        if yakuman > 0 {
+            fan = 0;
+        }
+
+        let (calc, calc_title) = calc_points(is_dealer, fu, fan, yakuman, &self.rule);
+        let points = match (is_drawn, is_dealer, pays.as_slice()) {
+            (false, _, &[p]) if fu != 0 => (p, calc.1, calc.2),
+            (false, true, &[p]) => (p, p / 3, 0),
+            (false, false, &[p]) => (p, p / 4, p / 2),
+            (true, true, &[p]) => (if fu != 0 { calc.0 } else { p * 3 }, p, 0),
+            (true, false, &[c, d]) => (if fu != 0 { calc.0 } else { d * 2 }, c, d),
+            _ => return Err(format!("invalid points: {}", detail[3]).into()),
+        };
+        let n_payer = calc_n_seat(self.rule.is_sanma) as Point - 1; // 3麻はツモ損
+        let score = if is_drawn {
+            if is_dealer {
+                points.1 * n_payer
+            } else {
+                points.1 * (n_payer - 1) + points.2
+            }
+        } else {
+            points.0
+        };
+        let title = if yakuman > 0 || title == "役満" || title.is_empty() {
+            calc_title
+        } else {
+            title
+        };
+
+        Ok(WinContext {
+            seat,
+            hand: tiles_from_tile_table(&hand),
+            winning_tile,
+            melds: pl.melds.clone(),
+            is_dealer,
+            is_drawn,
+            is_riichi: pl.is_riichi,
+            pao: if pao != seat { Some(pao) } else { None },
+            delta_scores,
+            score_context: ScoreContext {
+                yakus,
+                fu,
+                fan,
+                yakuman,
+                score,
+                points,
+                title,
+            },
+        })
+    }
+}
+
+// "30符1飜1000点", "満貫8000点", "30符1飜300-500点", "30符2飜500点∀"
+// 返り値: (符, 飜, 満貫などの名称, 支払い点数)
+fn parse_points(s: &str) -> Res<(usize, usize, String, Vec<Point>)> {
+    let s = s.trim_end_matches('∀').trim_end_matches('点');
+    let (fu, fan, rest) = match s.split_once('符') {
+        Some((fu, rest)) => {
+            let (fan, rest) = rest.split_once('飜').ok_or("invalid points")?;
+            (fu.parse()?, fan.parse()?, rest)
+        }
+        None => (0, 0, s),
+    };
+    let i = rest
+        .find(|c: char| c.is_ascii_digit())
+        .ok_or("invalid points")?;
+    let mut pays = vec![];
+    for p in rest[i..].split('-') {
+        pays.push(p.parse()?);
+    }
+    Ok((fu, fan, rest[..i].into(), pays))
+}
+
+// 鳴きの文字列(例: "c275226", "p222222", "424242a42", "15k5155")をパース
+// 返り値: Vec<(記号, 牌)> 記号は直後の牌にのみ付与
+fn parse_meld(m: &str) -> Res<Vec<(Option<char>, Tile)>> {
+    if !m.is_ascii() {
+        return Err(format!("invalid meld: {}", m).into());
+    }
+    let mut res = vec![];
+    let mut marker = None;
+    let mut i = 0;
+    while i < m.len() {
+        let c = m.as_bytes()[i] as char;
+        if c.is_ascii_alphabetic() {
+            marker = Some(c);
+            i += 1;
+        } else {
+            let n = m
+                .get(i..i + 2)
+                .ok_or_else(|| format!("invalid meld: {}", m))?;
+            res.push((marker.take(), tile_from_tenhou(n.parse()?)?));
+            i += 2;
+        }
+    }
+    Ok(res)
+}
+
+fn as_i64_vec(v: &Value) -> Res<Vec<i64>> {
+    let a = v.as_array().ok_or_else(|| format!("not an array: {}", v))?;
+    let mut res = vec![];
+    for x in a {
+        res.push(x.as_i64().ok_or_else(|| format!("not a number: {}", x))?);
+    }
+    Ok(res)
+}
+
+fn to_points(v: &[i64]) -> [Point; SEAT] {
+    let mut ps = [0; SEAT];
+    for (s, &p) in v.iter().take(SEAT).enumerate() {
+        ps[s] = p as Point;
+    }
+    ps
+}
+
+// 天鳳の牌譜ビューアのルール名から対局設定を復元
+fn rule_from_disp(disp: &str, aka: usize) -> Rule {
+    let bust_score = disp
+        .split_once('飛')
+        .and_then(|(_, s)| s.parse().ok())
+        .unwrap_or(0);
+    Rule {
+        round: if disp.contains('南') { 2 } else { 1 },
+        is_sanma: disp.contains('三'),
+        red5: if aka > 0 || disp.contains('赤') {
+            1
+        } else {
+            0
+        },
+        bust: !disp.contains("飛無"),
+        bust_score,
+        kuitan: disp.contains('喰'),
+        ..Default::default()
+    }
+}
 
 fn tile_to_tenhou(t: Tile) -> i64 {
     (match t {
@@ -249,20 +929,93 @@ fn tile_to_tenhou(t: Tile) -> i64 {
     }) as i64
 }
 
-// fn tile_from_tenhou(t: i64) -> Tile {
-//     let t = t as usize;
-//     match t {
-//         0 => Z8,
-//         11..=47 => Tile(t / 10 - 1, t % 10),
-//         51..=53 => Tile(t % 10 - 1, 0),
-//         _ => panic!("invalid tile number: {}", t),
-//     }
-// }
+fn tile_from_tenhou(t: i64) -> Res<Tile> {
+    Ok(match t {
+        0 => Z8,
+        11..=19 | 21..=29 | 31..=39 | 41..=47 => {
+            let t = t as usize;
+            Tile(t / 10 - 1, t % 10)
+        }
+        51..=53 => Tile(t as usize % 10 - 1, 0),
+        _ => return Err(format!("invalid tile number: {}", t).into()),
+    })
+}
 
 fn tiles_to_tenhou(v: &[Tile]) -> Vec<i64> {
     v.iter().map(|&t| tile_to_tenhou(t)).collect()
 }
 
-// fn tiles_from_tenhou(v: &[i64]) -> Vec<Tile> {
-//     v.iter().map(|&t| tile_from_tenhou(t)).collect()
-// }
+fn tiles_from_tenhou(v: &[i64]) -> Res<Vec<Tile>> {
+    v.iter().map(|&t| tile_from_tenhou(t)).collect()
+}
+
+#[test]
+fn test_rule_to_disp() {
+    let mut rule = Rule {
+        round: 2,
+        red5: 1,
+        bust: true,
+        ..Default::default()
+    };
+    assert_eq!(rule_to_disp(&rule), "南喰赤");
+    rule.is_sanma = true;
+    rule.bust_score = 1;
+    assert_eq!(rule_to_disp(&rule), "三南喰赤飛1");
+    rule.bust = false;
+    assert_eq!(rule_to_disp(&rule), "三南喰赤飛無");
+    rule.kuitan = false;
+    assert_eq!(rule_to_disp(&rule), "三南赤飛無");
+}
+
+#[test]
+fn test_tenhou_parse() {
+    let rule = rule_from_disp("三南喰赤飛1", 1);
+    assert_eq!(rule_to_disp(&rule), "三南喰赤飛1");
+    assert!(rule.is_sanma && rule.bust && rule.kuitan);
+
+    // 対面からの赤5のポン, 加槓, 暗槓
+    let m = parse_meld("15p5115").unwrap();
+    assert_eq!(m[1], (Some('p'), Tile(TM, 0)));
+    let m = parse_meld("1515k5115").unwrap();
+    assert_eq!(m[2], (Some('k'), Tile(TM, 0)));
+    let m = parse_meld("424242a42").unwrap();
+    assert_eq!(m.len(), 4);
+    assert!(parse_meld("p99").is_err());
+
+    assert_eq!(
+        parse_points("30符1飜1000点").unwrap(),
+        (30, 1, "".into(), vec![1000])
+    );
+    assert_eq!(
+        parse_points("満貫2000-4000点").unwrap(),
+        (0, 0, "満貫".into(), vec![2000, 4000])
+    );
+    assert_eq!(
+        parse_points("40符3飜2600点∀").unwrap(),
+        (40, 3, "".into(), vec![2600])
+    );
+}
+
+#[test]
+fn test_tenhou_sc() {
+    let mut stg = Stage {
+        rule: Rule {
+            initial_score: 25000,
+            settlement_score: 30000,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    for (s, (score, rank)) in [(23000, 2), (31000, 0), (23000, 3), (23000, 1)]
+        .into_iter()
+        .enumerate()
+    {
+        stg.players[s].score = score;
+        stg.players[s].rank = rank;
+    }
+    let sc = calc_sc(&stg);
+    assert_eq!(
+        serde_json::to_string(&sc).unwrap(),
+        "[230,-17.0,310,41.0,230,-27.0,230,3.0]"
+    );
+}
@@ -39,16 +39,28 @@ pub fn evaluate_hand_tsumo(stg: &Stage, ura_dora_wall: &[Tile]) -> Option<ScoreC
     if let Some((ctx, _)) = evaluate_hand(
         &pl.hand,
         &pl.melds,
-        &stg.doras,
-        &ura_doras,
+        &to_rule_doras(stg, &stg.doras),
+        &to_rule_doras(stg, &ura_doras),
+        pl.nukidoras.len(),
         pl.drawn.unwrap(),
         true,
         is_dealer(stg, pl.seat),
         get_prevalent_wind(stg),
         get_seat_wind(stg, pl.seat),
         &yf,
+        &stg.rule,
     ) && !ctx.yakus.is_empty()
     {
+        let mut ctx = ctx;
+        if stg.rule.is_sanma {
+            // 3麻はツモ損 (北家の支払い分がない)
+            let (_, non_dealer, dealer) = ctx.points;
+            ctx.score = if is_dealer(stg, pl.seat) {
+                non_dealer * 2
+            } else {
+                non_dealer + dealer
+            };
+        }
         return Some(ctx);
     }
 
@@ -100,6 +112,7 @@ pub fn evaluate_hand_ron(stg: &Stage, ura_dora_wall: &[Tile], seat: Seat) -> Opt
                 return None; // 暗槓のロンは国士無双のみ
             }
         }
+        ActionType::Nukidora => {} // 3麻の北抜きに対するロン
         _ => panic!(),
     }
     if is_no_meld_turn1(stg, stg.turn) {
@@ -119,14 +132,16 @@ pub fn evaluate_hand_ron(stg: &Stage, ura_dora_wall: &[Tile], seat: Seat) -> Opt
     if let Some((ctx, _)) = evaluate_hand(
         &hand,
         &pl.melds,
-        &stg.doras,
-        &ura_doras,
+        &to_rule_doras(stg, &stg.doras),
+        &to_rule_doras(stg, &ura_doras),
+        pl.nukidoras.len(),
         t,
         false,
         is_dealer(stg, pl.seat),
         get_prevalent_wind(stg),
         get_seat_wind(stg, pl.seat),
         &yf,
+        &stg.rule,
     ) && !ctx.yakus.is_empty()
     {
         return Some(ctx);
@@ -135,6 +150,76 @@ pub fn evaluate_hand_ron(stg: &Stage, ura_dora_wall: &[Tile], seat: Seat) -> Opt
     None
 }
 
+// 3麻の場合,萬子の2~8が存在しないため一萬の表示牌は九萬がドラになる
+// evaluate_handに渡すために八萬の表示牌に置き換える
+fn to_rule_doras(stg: &Stage, doras: &[Tile]) -> Vec<Tile> {
+    if !stg.rule.is_sanma {
+        return doras.to_vec();
+    }
+    doras
+        .iter()
+        .map(|&d| if d == Tile(TM, 1) { Tile(TM, 8) } else { d })
+        .collect()
+}
+
+// ルールに従った役満の倍率 (ダブル役満なしの場合は1倍)
+fn to_rule_yakuman(y: &YakuDefine, rule: &Rule) -> usize {
+    if rule.double_yakuman {
+        y.yakuman
+    } else {
+        y.yakuman.min(1)
+    }
+}
+
+// 役満の複合なしの場合,倍率が最大の役満のみを残す
+// 同じ倍率の場合は責任払いの対象になりうる役満,次に先に判定されたものを優先
+fn filter_composite_yakuman<'a>(yakus: Vec<&'a YakuDefine>, rule: &Rule) -> Vec<&'a YakuDefine> {
+    if rule.composite_yakuman || yakus.iter().all(|y| y.yakuman == 0) {
+        return yakus;
+    }
+    let key = |y: &YakuDefine| (to_rule_yakuman(y, rule), is_pao_yakuman(y.name));
+    let mut max: Option<&YakuDefine> = None;
+    for y in yakus {
+        if max.is_none_or(|m| key(y) > key(m)) {
+            max = Some(y);
+        }
+    }
+    max.into_iter().collect()
+}
+
+// 責任払いの対象になりうる役満
+fn is_pao_yakuman(name: &str) -> bool {
+    matches!(name, "大三元" | "大四喜" | "四槓子")
+}
+
+// 倍率の上限により切り捨てられた分の役満を役一覧から除く
+// 倍率の高い順(同じ倍率の場合は責任払いの対象になりうる役満を優先)に合計がcappedになるまで残す
+fn trim_capped_yakuman(yakus: &mut Vec<Yaku>, capped: usize) {
+    let mut order: Vec<usize> = (0..yakus.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse((yakus[i].fan, is_pao_yakuman(&yakus[i].name))));
+    let mut remain = capped;
+    for i in order {
+        yakus[i].fan = yakus[i].fan.min(remain);
+        remain -= yakus[i].fan;
+    }
+    yakus.retain(|y| y.fan > 0);
+}
+
+// ルールに従った役満の合計倍率 (複合の有無と倍率の上限)
+fn calc_yakuman_multiplier(yakus: &[&YakuDefine], rule: &Rule) -> usize {
+    let ms = yakus.iter().map(|y| to_rule_yakuman(y, rule));
+    let m = if rule.composite_yakuman {
+        ms.sum()
+    } else {
+        ms.max().unwrap_or(0)
+    };
+    if rule.yakuman_cap != 0 {
+        m.min(rule.yakuman_cap)
+    } else {
+        m
+    }
+}
+
 // 和了形である場合,最も高得点となるような役の組み合わせのSome(Result)を返却
 // 和了形でない場合,Noneを返却
 // 和了形でも無役の場合はResultの中身がyaku: [], points(0, 0, 0)となる.
@@ -144,12 +229,14 @@ pub fn evaluate_hand(
     melds: &[Meld],         // 鳴き
     doras: &[Tile],         // ドラ表示牌 (注:ドラそのものではない)
     ura_doras: &[Tile],     // 裏ドラ表示牌 リーチしていない場合は空
+    nukidora: usize,        // 抜きドラ(3麻の北抜き)の枚数
     winning_tile: Tile,     // 上がり牌
     is_drawn: bool,         // ツモ和了
     is_dealer: bool,        // 親番
     prevalent_wind: Tnum,   // 場風 (東: 1, 南: 2, 西: 3, 北: 4)
     seat_wind: Tnum,        // 自風 (同上)
     yaku_flags: &YakuFlags, // 和了形だった場合に自動的に付与される役(特殊条件役)のフラグ
+    rule: &Rule,            // 喰いタン, 切り上げ満貫などのルール
 ) -> Option<(ScoreContext, YakuContext)> {
     let mut phs = vec![];
     phs.append(&mut parse_into_normal_win(hand));
@@ -174,6 +261,7 @@ pub fn evaluate_hand(
             seat_wind,
             is_drawn,
             yaku_flags.clone(),
+            rule.kuitan,
         ));
     }
 
@@ -181,7 +269,8 @@ pub fn evaluate_hand(
         return None; // 和了形以外
     }
 
-    let n_dora = count_dora(hand, melds, doras);
+    // 抜いた北もドラ表示牌の対象になる
+    let n_dora = count_dora(hand, melds, doras) + create_dora_table(doras)[TZ][WN] * nukidora;
     let mut n_red_dora = hand[0][0] + hand[1][0] + hand[2][0];
     for m in melds {
         for t in &m.tiles {
@@ -191,7 +280,7 @@ pub fn evaluate_hand(
         }
     }
     let n_ura_dora = if yaku_flags.riichi || yaku_flags.dabururiichi {
-        count_dora(hand, melds, ura_doras)
+        count_dora(hand, melds, ura_doras) + create_dora_table(ura_doras)[TZ][WN] * nukidora
     } else {
         0
     };
@@ -204,15 +293,17 @@ pub fn evaluate_hand(
             fu = fu.div_ceil(10) * 10; // 七対子以外なら１の位は切り上げ
         }
 
-        let (yakus, mut fan, yakuman) = yctx.calc_yaku();
+        let (yakus, mut fan, _) = yctx.calc_yaku();
         if yakus.is_empty() {
             continue; // 無役
         }
+        let yakus = filter_composite_yakuman(yakus, rule);
+        let yakuman = calc_yakuman_multiplier(&yakus, rule);
         let mut yakus: Vec<Yaku> = yakus
             .iter()
             .map(|y| {
                 let fan = if y.yakuman > 0 {
-                    y.yakuman
+                    to_rule_yakuman(y, rule)
                 } else {
                     if yctx.is_open() {
                         y.fan_open
@@ -226,8 +317,17 @@ pub fn evaluate_hand(
                 }
             })
             .collect();
+        if yakuman != 0 && rule.yakuman_cap != 0 {
+            trim_capped_yakuman(&mut yakus, yakuman);
+        }
         if yakuman == 0 {
-            fan += n_dora + n_red_dora + n_ura_dora;
+            fan += nukidora + n_dora + n_red_dora + n_ura_dora;
+            if nukidora != 0 {
+                yakus.push(Yaku {
+                    name: "抜きドラ".into(),
+                    fan: nukidora,
+                });
+            }
             if n_dora != 0 {
                 yakus.push(Yaku {
                     name: "ドラ".into(),
@@ -248,7 +348,7 @@ pub fn evaluate_hand(
             }
         }
 
-        let (points, title) = calc_points(is_dealer, fu, fan, yakuman);
+        let (points, title) = calc_points(is_dealer, fu, fan, yakuman, rule);
         let score = if is_drawn {
             if is_dealer {
                 points.1 * 3
@@ -276,3 +376,86 @@ pub fn evaluate_hand(
     ctxs.sort_by_key(|(sctx, _yctx)| (sctx.points.0, sctx.fan, sctx.fu));
     ctxs.pop()
 }
+
+#[test]
+fn test_kuitan() {
+    use crate::control::string::{meld_from_string, tiles_from_string};
+
+    let hand = tiles_to_tile_table(&tiles_from_string("m234p345s234s66").unwrap());
+    let melds = [meld_from_string("p6+66").unwrap()];
+    let wt = Tile(TS, 6);
+    let eval = |rule: &Rule| {
+        let yf = YakuFlags::default();
+        evaluate_hand(
+            &hand,
+            &melds,
+            &[],
+            &[],
+            0,
+            wt,
+            false,
+            false,
+            WE,
+            WS,
+            &yf,
+            rule,
+        )
+        .map(|(ctx, _)| ctx.yakus)
+    };
+
+    let mut rule = Rule::default();
+    assert_eq!(eval(&rule).unwrap()[0].name, "断幺九");
+    rule.kuitan = false;
+    assert!(eval(&rule).is_none()); // 喰いタンなしでは無役
+}
+
+#[test]
+fn test_yakuman_rule() {
+    use crate::control::string::tiles_from_string;
+
+    // 大四喜(2) + 字一色(1) + 四暗刻単騎(2)
+    let hand = tiles_to_tile_table(&tiles_from_string("z11122233344455").unwrap());
+    let wt = Tile(TZ, 5);
+    let eval = |rule: &Rule| {
+        let yf = YakuFlags::default();
+        let (ctx, _) =
+            evaluate_hand(&hand, &[], &[], &[], 0, wt, false, false, WE, WS, &yf, rule).unwrap();
+        let yakus: Vec<(String, usize)> = ctx.yakus.into_iter().map(|y| (y.name, y.fan)).collect();
+        (ctx.yakuman, yakus)
+    };
+    let fans = |yakus: &[(String, usize)]| yakus.iter().map(|y| y.1).sum::<usize>();
+
+    let mut rule = Rule::default();
+    let (m, yakus) = eval(&rule);
+    assert_eq!(m, 5);
+    assert_eq!(yakus.len(), 3);
+    assert_eq!(fans(&yakus), 5);
+    rule.yakuman_cap = 2;
+    let (m, yakus) = eval(&rule);
+    assert_eq!(m, 2);
+    assert_eq!(fans(&yakus), 2); // 役一覧も上限に合わせて切り捨て
+    assert_eq!(yakus, [("大四喜".to_string(), 2)]);
+    rule.yakuman_cap = 4;
+    let (m, yakus) = eval(&rule);
+    assert_eq!(m, 4);
+    assert_eq!(fans(&yakus), 4);
+    assert_eq!(yakus.len(), 2);
+    rule.yakuman_cap = 0;
+    rule.double_yakuman = false;
+    let (m, yakus) = eval(&rule);
+    assert_eq!(m, 3);
+    assert_eq!(fans(&yakus), 3);
+
+    // 複合なしの場合,役一覧は数えられた役満のみ
+    rule.composite_yakuman = false;
+    let (m, yakus) = eval(&rule);
+    assert_eq!(m, 1);
+    assert_eq!(yakus.len(), 1);
+    assert_eq!(fans(&yakus), 1);
+    rule.double_yakuman = true;
+    let (m, yakus) = eval(&rule);
+    assert_eq!(m, 2);
+    assert_eq!(yakus.len(), 1);
+    assert_eq!(yakus[0].1, 2);
+    assert_eq!(yakus[0].0, "大四喜"); // 同じ倍率の場合は責任払いの対象を優先
+}
@@ -2,12 +2,19 @@
 mod evaluate;
 mod parse;
 mod point;
+mod shanten;
+mod ukeire;
 mod win;
 mod yaku;
 
 pub use self::{
     evaluate::{evaluate_hand, evaluate_hand_ron, evaluate_hand_tsumo},
     parse::SetPairType,
+    point::calc_points,
+    shanten::{
+        calc_chiitoitsu_shanten, calc_kokushimusou_shanten, calc_normal_shanten, calc_shanten,
+    },
+    ukeire::{Ukeire, calc_effective_tiles, calc_ukeire, count_live_tiles},
     win::{calc_discards_to_win, calc_tiles_to_normal_win, calc_tiles_to_win, is_normal_win},
     yaku::{YakuDefine, YakuFlags},
 };
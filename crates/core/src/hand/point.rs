@@ -1,19 +1,26 @@
-use crate::model::{Point, Points};
+use crate::model::{Point, Points, Rule};
 
 fn ceil(n: Point) -> Point {
     (n + 99) / 100 * 100
 }
 
-fn calc_base_point(fu: usize, fan: usize, yakuman: usize) -> Point {
+fn calc_base_point(fu: usize, fan: usize, yakuman: usize, rule: &Rule) -> Point {
     (if yakuman == 0 {
         let base = fu * 2_usize.pow(fan as u32 + 2);
-        if base >= 2000 {
+        // 切り上げ満貫: 30符4飜, 60符3飜 (基本点1920) を満貫として扱う
+        if base >= 2000 || (rule.kiriage && base == 1920) {
             match fan {
                 ..6 => 2000,    // 満貫
                 6..8 => 3000,   // 跳満
                 8..11 => 4000,  // 倍満
                 11..13 => 6000, // 三倍満
-                13.. => 8000,   // 四倍満 (数え役満)
+                13.. => {
+                    if rule.kazoe_yakuman {
+                        8000 // 四倍満 (数え役満)
+                    } else {
+                        6000 // 三倍満
+                    }
+                }
             }
         } else {
             base
@@ -46,8 +53,14 @@ fn get_score_title(base_point: Point, yakuman: usize) -> String {
 }
 
 // 親の和了 (直撃, ツモ和了の子, ツモ和了の親)の支払いを返却
-pub fn calc_points(is_dealer: bool, fu: usize, fan: usize, yakuman: usize) -> (Points, String) {
-    let base = calc_base_point(fu, fan, yakuman);
+pub fn calc_points(
+    is_dealer: bool,
+    fu: usize,
+    fan: usize,
+    yakuman: usize,
+    rule: &Rule,
+) -> (Points, String) {
+    let base = calc_base_point(fu, fan, yakuman, rule);
     let title = get_score_title(base, yakuman);
     if is_dealer {
         ((ceil(base * 6), ceil(base * 2), 0), title)
@@ -59,19 +72,20 @@ pub fn calc_points(is_dealer: bool, fu: usize, fan: usize, yakuman: usize) -> (P
 // cargo test --release -p mahjong_core print_points_table -- --nocapture
 #[test]
 fn print_points_table() {
+    let rule = Rule::default();
     let fu_list = [20, 25, 30, 40, 50, 60, 70, 80, 90, 100, 110];
 
     println!("点数計算表 (子) ============================================");
     for fu in fu_list {
         print!("[{fu:3}符] ");
         for fan in 1..=4 {
-            let (scores, _) = calc_points(false, fu, fan, 0);
+            let (scores, _) = calc_points(false, fu, fan, 0, &rule);
             print!("{fan}飜:{:4}({:4}/{:4}) ", scores.0, scores.1, scores.2)
         }
         println!();
     }
     for fan in 5..=13 {
-        let (scores, title) = calc_points(false, 20, fan, 0);
+        let (scores, title) = calc_points(false, 20, fan, 0, &rule);
         println!(
             "{fan:2}飜:{:5}({:4}/{:5}) {title}",
             scores.0, scores.1, scores.2
@@ -83,14 +97,48 @@ fn print_points_table() {
     for fu in fu_list {
         print!("[{fu:3}符] ");
         for fan in 1..=4 {
-            let (scores, _) = calc_points(true, fu, fan, 0);
+            let (scores, _) = calc_points(true, fu, fan, 0, &rule);
             print!("{fan}飜:{:5}({:4}) ", scores.0, scores.1)
         }
         println!();
     }
     for fan in 5..=13 {
-        let (scores, title) = calc_points(true, 20, fan, 0);
+        let (scores, title) = calc_points(true, 20, fan, 0, &rule);
         println!("{fan:2}飜:{:5}({:5}) {title}", scores.0, scores.1);
     }
     println!();
 }
+
+#[test]
+fn test_kiriage() {
+    let mut rule = Rule::default();
+    assert_eq!(
+        calc_points(false, 30, 4, 0, &rule),
+        ((7700, 2000, 3900), "".into())
+    );
+    rule.kiriage = true;
+    assert_eq!(
+        calc_points(false, 30, 4, 0, &rule),
+        ((8000, 2000, 4000), "満貫".into())
+    );
+    assert_eq!(
+        calc_points(true, 60, 3, 0, &rule),
+        ((12000, 4000, 0), "満貫".into())
+    );
+    assert_eq!(
+        calc_points(false, 40, 3, 0, &rule),
+        ((5200, 1300, 2600), "".into())
+    );
+}
+
+#[test]
+fn test_kazoe_yakuman() {
+    let mut rule = Rule::default();
+    assert_eq!(calc_points(false, 30, 13, 0, &rule).1, "数え役満");
+    rule.kazoe_yakuman = false;
+    assert_eq!(
+        calc_points(false, 30, 13, 0, &rule),
+        ((24000, 6000, 12000), "三倍満".into())
+    );
+    assert_eq!(calc_points(false, 30, 0, 1, &rule).1, "役満");
+}
@@ -0,0 +1,267 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::model::*;
+
+// 向聴数計算
+// -1: 和了形, 0: 聴牌, 1: 一向聴, ...
+// 手牌の枚数から副露数を判定 (13枚または14枚 - 副露数 * 3)
+// このモジュールの関数は赤5を通常の5として扱う
+
+// すべての和了形 (通常形,七対子,国士無双)の最小値
+pub fn calc_shanten(hand: &TileTable) -> i32 {
+    let s0 = calc_normal_shanten(hand);
+    let s1 = calc_chiitoitsu_shanten(hand);
+    let s2 = calc_kokushimusou_shanten(hand);
+    s0.min(s1).min(s2)
+}
+
+// 通常形 (4面子1雀頭)
+pub fn calc_normal_shanten(hand: &TileTable) -> i32 {
+    let n_meld = (4 - count_tiles(hand) / 3).max(0) as usize;
+
+    // 状態: (面子数, 搭子数, 雀頭の有無)
+    let mut states = vec![(n_meld, 0, false)];
+    for ti in 0..TYPE {
+        let (blocks, blocks_pair) = decompose_row(&hand[ti], ti == TZ);
+        let mut next = vec![];
+        for &(m0, t0, p0) in &states {
+            for &(m, t) in &blocks {
+                next.push((m0 + m, t0 + t, p0));
+            }
+            if !p0 {
+                for &(m, t) in &blocks_pair {
+                    next.push((m0 + m, t0 + t, true));
+                }
+            }
+        }
+        next.sort();
+        next.dedup();
+        states = next;
+    }
+
+    states
+        .iter()
+        .map(|&(m, t, p)| {
+            let t = t.min(4 - m.min(4)); // 面子+搭子は4つまで
+            8 - 2 * m as i32 - t as i32 - p as i32
+        })
+        .min()
+        .unwrap()
+}
+
+// 七対子 (副露がある場合は不可)
+pub fn calc_chiitoitsu_shanten(hand: &TileTable) -> i32 {
+    if count_tiles(hand) < 13 {
+        return i32::MAX;
+    }
+    let mut n_pair = 0;
+    let mut n_kind = 0;
+    for ti in 0..TYPE {
+        for ni in 1..TNUM {
+            if hand[ti][ni] >= 2 {
+                n_pair += 1;
+            }
+            if hand[ti][ni] >= 1 {
+                n_kind += 1;
+            }
+        }
+    }
+    // 同じ牌4枚は2組の対子として扱わない
+    6 - n_pair + (7 - n_kind).max(0)
+}
+
+// 国士無双 (副露がある場合は不可)
+pub fn calc_kokushimusou_shanten(hand: &TileTable) -> i32 {
+    if count_tiles(hand) < 13 {
+        return i32::MAX;
+    }
+    let mut n_kind = 0;
+    let mut has_pair = false;
+    let mut check = |n: usize| {
+        if n >= 1 {
+            n_kind += 1;
+        }
+        if n >= 2 {
+            has_pair = true;
+        }
+    };
+    for ti in 0..TZ {
+        check(hand[ti][1]);
+        check(hand[ti][9]);
+    }
+    for ni in 1..8 {
+        check(hand[TZ][ni]);
+    }
+    13 - n_kind - has_pair as i32
+}
+
+// 牌種ごとの(面子数, 搭子数)の組み合わせ (雀頭なし, 雀頭あり)
+// いずれの値も小さい組み合わせは除外
+type Blocks = Vec<(usize, usize)>;
+
+// 先頭の牌の使い方(刻子,順子,雀頭,搭子,孤立牌)ごとに残りの形の結果を再帰的に求める
+// 同じ形の計算結果はキャッシュ (牌の枚数を5進数で表現した値をキーとする)
+fn decompose_row(tr: &TileRow, is_hornor: bool) -> (Blocks, Blocks) {
+    thread_local! {
+        static CACHE: RefCell<HashMap<(u32, bool), (Blocks, Blocks)>> = RefCell::new(HashMap::new());
+    }
+    let key = (
+        tr[1..TNUM].iter().fold(0, |k, &n| k * 5 + n as u32),
+        is_hornor,
+    );
+    if let Some(res) = CACHE.with(|c| c.borrow().get(&key).cloned()) {
+        return res;
+    }
+
+    let Some(ni) = (1..TNUM).find(|&ni| tr[ni] > 0) else {
+        return (vec![(0, 0)], vec![]);
+    };
+    let is_seq = |d: usize| !is_hornor && ni + d < TNUM && tr[ni + d] > 0;
+
+    // (取り除く牌の位置, 面子数, 搭子数, 雀頭)
+    let mut options: Vec<(Vec<usize>, usize, usize, bool)> = vec![];
+    if tr[ni] >= 3 {
+        options.push((vec![ni, ni, ni], 1, 0, false)); // 刻子
+    }
+    if is_seq(1) && is_seq(2) {
+        options.push((vec![ni, ni + 1, ni + 2], 1, 0, false)); // 順子
+    }
+    if tr[ni] >= 2 {
+        options.push((vec![ni, ni], 0, 0, true)); // 雀頭
+        options.push((vec![ni, ni], 0, 1, false)); // 対子
+    }
+    for d in [1, 2] {
+        if is_seq(d) {
+            options.push((vec![ni, ni + d], 0, 1, false)); // 両面,辺張 / 嵌張
+        }
+    }
+    options.push((vec![ni], 0, 0, false)); // 孤立牌
+
+    let mut res = (vec![], vec![]);
+    for (pos, m, t, is_pair) in options {
+        let mut tr2 = *tr;
+        for &i in &pos {
+            tr2[i] -= 1;
+        }
+        let (sub0, sub1) = decompose_row(&tr2, is_hornor);
+        let shift = |v: Blocks| v.into_iter().map(|(m2, t2)| (m + m2, t + t2));
+        if is_pair {
+            res.1.extend(shift(sub0));
+        } else {
+            res.0.extend(shift(sub0));
+            res.1.extend(shift(sub1));
+        }
+    }
+    let res = (pareto(res.0), pareto(res.1));
+    CACHE.with(|c| c.borrow_mut().insert(key, res.clone()));
+    res
+}
+
+fn pareto(mut v: Blocks) -> Blocks {
+    v.sort();
+    v.dedup();
+    let all = v.clone();
+    v.retain(|&(m, t)| {
+        !all.iter()
+            .any(|&(m2, t2)| (m2, t2) != (m, t) && m2 >= m && t2 >= t)
+    });
+    v
+}
+
+fn count_tiles(hand: &TileTable) -> i32 {
+    let mut n = 0;
+    for ti in 0..TYPE {
+        n += hand[ti][1..TNUM].iter().sum::<usize>();
+    }
+    n as i32
+}
+
+#[test]
+fn test_shanten() {
+    use crate::control::{common::tiles_to_tile_table, string::tiles_from_string};
+
+    let shanten = |s: &str| {
+        let hand = tiles_to_tile_table(&tiles_from_string(s).unwrap());
+        (
+            calc_normal_shanten(&hand),
+            calc_chiitoitsu_shanten(&hand),
+            calc_kokushimusou_shanten(&hand),
+        )
+    };
+    assert_eq!(shanten("m123456789p123s55").0, -1);
+    assert_eq!(shanten("m123456789p12s55").0, 0);
+    assert_eq!(shanten("m1357p2468s13579z1").0, 4); // 搭子過多
+    assert_eq!(shanten("m1122p3344s5566z7").1, 0);
+    assert_eq!(shanten("m1111p3344s5566z7").1, 2); // 4枚使いは対子1組
+    assert_eq!(shanten("m19p19s19z1234567").2, 0);
+    assert_eq!(shanten("m19p19s19z12345677").2, -1);
+    // 副露あり (2副露)
+    assert_eq!(shanten("m123s55z11").0, 0);
+    assert_eq!(shanten("m123s56z11").0, 0);
+    assert_eq!(shanten("m13s58z122").0, 2);
+    assert_eq!(shanten("m123s56z11").1, i32::MAX);
+}
+
+#[test]
+fn test_shanten_random() {
+    use rand::prelude::*;
+
+    use super::win::calc_tiles_to_win;
+    use crate::control::common::{dec_tile, inc_tile};
+
+    // 聴牌判定と向聴数計算の結果が一致するかを確認
+    let mut rng: rand::rngs::StdRng = rand::SeedableRng::seed_from_u64(0);
+    let mut wall = vec![];
+    for ti in 0..TYPE {
+        for ni in 1..TNUM {
+            if ti == TZ && ni > DR {
+                break;
+            }
+            for _ in 0..4 {
+                wall.push(Tile(ti, ni));
+            }
+        }
+    }
+    for i in 0..20000 {
+        wall.shuffle(&mut rng);
+        // 一色の手牌を多めに生成して聴牌形を増やす
+        let tiles: Vec<Tile> = if i % 2 == 0 {
+            wall.iter().take(13).copied().collect()
+        } else {
+            let ti = i % 3;
+            wall.iter()
+                .filter(|t| t.0 == ti)
+                .take(13)
+                .copied()
+                .collect()
+        };
+        let mut hand = TileTable::default();
+        for &t in &tiles {
+            inc_tile(&mut hand, t);
+        }
+        // 14枚の向聴数は打牌後の向聴数の最小値 (和了形を除く)
+        let t14 = wall.iter().find(|t| hand[t.0][t.1] < 4).unwrap();
+        inc_tile(&mut hand, *t14);
+        let mut min = i32::MAX;
+        for &t in tiles.iter().chain([t14]) {
+            dec_tile(&mut hand, t);
+            min = min.min(calc_shanten(&hand));
+            inc_tile(&mut hand, t);
+        }
+        assert_eq!(calc_shanten(&hand).max(0), min, "{:?} + {}", tiles, t14);
+        dec_tile(&mut hand, *t14);
+
+        let is_tenpai = calc_shanten(&hand) == 0;
+        let wts = calc_tiles_to_win(&hand);
+        if wts.is_empty() {
+            // 待ち牌をすべて自分で使い切っている形のみ和了牌なしの聴牌になる
+            let has_4 = tiles.iter().any(|&t| hand[t.0][t.1] == 4);
+            assert!(!is_tenpai || has_4, "{:?}", tiles);
+        } else {
+            assert!(is_tenpai, "{:?}", tiles);
+            inc_tile(&mut hand, wts[0]);
+            assert_eq!(calc_shanten(&hand), -1);
+            dec_tile(&mut hand, wts[0]);
+        }
+    }
+}
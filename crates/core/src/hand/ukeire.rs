@@ -0,0 +1,90 @@
+use super::shanten::calc_shanten;
+use crate::model::*;
+
+// 受け入れ (有効牌) 計算
+// このモジュールの関数は赤5を通常の5として扱う
+
+// 打牌候補ごとの受け入れ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ukeire {
+    pub discard: Tile,    // 打牌
+    pub shanten: i32,     // 打牌後の向聴数
+    pub tiles: Vec<Tile>, // 有効牌 (向聴数が進む牌)
+    pub n_live: usize,    // 有効牌の残り枚数 (見えている牌を除く)
+}
+
+// 13 - (副露数) * 3 枚の手牌の有効牌のリストを返却
+// 手牌ですでに4枚使っている牌は含まない
+pub fn calc_effective_tiles(hand: &TileTable) -> Vec<Tile> {
+    let shanten = calc_shanten(hand);
+    let mut hand = *hand;
+    let mut res = vec![];
+    for ti in 0..TYPE {
+        for ni in 1..TNUM {
+            if (ti == TZ && ni > DR) || hand[ti][ni] >= TILE {
+                continue;
+            }
+            hand[ti][ni] += 1;
+            if calc_shanten(&hand) < shanten {
+                res.push(Tile(ti, ni));
+            }
+            hand[ti][ni] -= 1;
+        }
+    }
+    res
+}
+
+// 有効牌のうち見えていない牌の枚数
+// visibleは自分の手牌を含む見えている牌の枚数 (Stageからはget_visible_tilesで取得)
+pub fn count_live_tiles(tiles: &[Tile], visible: &TileTable) -> usize {
+    tiles
+        .iter()
+        .map(|t| TILE - visible[t.0][t.1].min(TILE))
+        .sum()
+}
+
+// 14 - (副露数) * 3 枚の手牌について,打牌候補ごとの向聴数と有効牌を返却
+// 向聴数の小さい順,残り枚数の多い順に並び替え
+pub fn calc_ukeire(hand: &TileTable, visible: &TileTable) -> Vec<Ukeire> {
+    let mut hand = *hand;
+    let mut res = vec![];
+    for ti in 0..TYPE {
+        for ni in 1..TNUM {
+            if hand[ti][ni] == 0 {
+                continue;
+            }
+            hand[ti][ni] -= 1;
+            let tiles = calc_effective_tiles(&hand);
+            res.push(Ukeire {
+                discard: Tile(ti, ni),
+                shanten: calc_shanten(&hand),
+                n_live: count_live_tiles(&tiles, visible),
+                tiles,
+            });
+            hand[ti][ni] += 1;
+        }
+    }
+    res.sort_by_key(|u| (u.shanten, std::cmp::Reverse(u.n_live), u.discard));
+    res
+}
+
+#[test]
+fn test_ukeire() {
+    use crate::control::{common::tiles_to_tile_table, string::tiles_from_string};
+
+    let table = |s: &str| tiles_to_tile_table(&tiles_from_string(s).unwrap());
+    let hand = table("m123p456s789m56z112");
+    let ukeire = calc_ukeire(&hand, &hand);
+    // 打z2: 両面待ちの聴牌
+    assert_eq!(ukeire[0].discard, Tile(TZ, 2));
+    assert_eq!(ukeire[0].shanten, 0);
+    assert_eq!(ukeire[0].tiles, vec![Tile(TM, 4), Tile(TM, 7)]);
+    assert_eq!(ukeire[0].n_live, 8);
+    assert!(ukeire[1..].iter().all(|u| u.shanten == 1));
+
+    // 見えている牌は残り枚数から除外
+    let visible = table("m123p456s789m56z112m4447777");
+    let ukeire = calc_ukeire(&hand, &visible);
+    assert_eq!(ukeire[0].discard, Tile(TZ, 2));
+    assert_eq!(ukeire[0].n_live, 1);
+}
@@ -221,11 +221,7 @@ pub fn calc_tiles_to_chiitoitsu_win(hand: &TileTable) -> Vec<Tile> {
     for ti in 0..TYPE {
         for ni in 1..TNUM {
             match hand[ti][ni] {
-                1 => {
-                    if res.is_empty() {
-                        res.push(Tile(ti, ni));
-                    }
-                }
+                1 if res.is_empty() => res.push(Tile(ti, ni)),
                 2 => n_pair += 1,
                 3 => return vec![],
                 _ => {}
@@ -313,12 +309,20 @@ pub fn calc_discards_to_normal_tenpai(hand: &TileTable) -> Vec<(Tile, Vec<Tile>)
     for ti in 0..TYPE {
         for ni in 1..TNUM {
             if hand[ti][ni] > 0 {
+                // 5がすべて赤5の場合は赤5のフラグも合わせて減らす
+                let red5 = ni == 5 && hand[ti][0] == hand[ti][5];
                 hand[ti][ni] -= 1;
+                if red5 {
+                    hand[ti][0] -= 1;
+                }
                 let v = calc_tiles_to_normal_win(&hand);
                 if !v.is_empty() {
                     res.push((Tile(ti, ni), v));
                 }
                 hand[ti][ni] += 1;
+                if red5 {
+                    hand[ti][0] += 1;
+                }
             }
         }
     }
@@ -36,6 +36,7 @@ pub struct YakuContext {
     prevalent_wind: Tnum,    // 場風 (東: 1, 南: 2, 西: 3, 北: 4)
     seat_wind: Tnum,         // 自風 (同上)
     yaku_flags: YakuFlags,   // 組み合わせ以外による役 外部から設定を行う
+    kuitan: bool,            // 喰いタンのアリなし
     counts: Counts,          // 面子や牌種別のカウント
     iipeikou_count: usize,   // 一盃口, 二盃口用
     yakuhai_check: TileRow,  // 役牌面子のカウント(雀頭は含まない)
@@ -50,6 +51,7 @@ impl YakuContext {
         seat_wind: Tnum,
         is_drawn: bool,
         yaku_flags: YakuFlags,
+        kuitan: bool,
     ) -> Self {
         let pair_tile = get_pair(&parsed_hand);
         let winning_tile = winning_tile.to_normal(); // 赤5は通常5に変換
@@ -74,6 +76,7 @@ impl YakuContext {
             prevalent_wind,
             seat_wind,
             yaku_flags,
+            kuitan,
             counts,
             iipeikou_count,
             yakuhai_check,
@@ -123,6 +126,7 @@ impl YakuContext {
         }
     }
 
+    #[allow(clippy::collapsible_match)]
     pub fn calc_fu(&self) -> Vec<Fu> {
         // 例外の平和,七対子は予め処理
         if is_pinfu(self) {
@@ -327,6 +331,7 @@ fn count_iipeikou(ph: &ParsedHand) -> usize {
     n
 }
 
+#[allow(clippy::collapsible_match)]
 fn check_yakuhai(ph: &ParsedHand) -> TileRow {
     let mut tr = TileRow::default();
     for SetPair(tp, t) in ph {
@@ -357,6 +362,10 @@ impl YakuDefine {
         assert!(id != 10 && id != 11); // 自風, 場風は特定不能
         YAKU_LIST.iter().find(|&y| y.id == id)
     }
+
+    pub fn get_from_name(name: &str) -> Option<&'static YakuDefine> {
+        YAKU_LIST.iter().find(|&y| y.name == name)
+    }
 }
 
 impl fmt::Debug for YakuDefine {
@@ -553,6 +562,7 @@ fn is_chun(ctx: &YakuContext) -> bool {
 }
 
 // 平和
+#[allow(clippy::collapsible_match)]
 fn is_pinfu(ctx: &YakuContext) -> bool {
     if ctx.counts.shuntsu != 4 {
         return false;
@@ -592,6 +602,9 @@ fn is_tanyaochuu(ctx: &YakuContext) -> bool {
     if ctx.parsed_hand.is_empty() {
         return false; // 国士対策
     }
+    if ctx.is_open && !ctx.kuitan {
+        return false; // 喰いタンなし
+    }
 
     for SetPair(tp, t) in &ctx.parsed_hand {
         match tp {
@@ -622,6 +635,7 @@ fn is_ryanpeikou(ctx: &YakuContext) -> bool {
 }
 
 // 一気通貫
+#[allow(clippy::collapsible_match)]
 fn is_ikkitsuukan(ctx: &YakuContext) -> bool {
     if ctx.counts.shuntsu_total < 3 {
         return false;
@@ -646,6 +660,7 @@ fn is_ikkitsuukan(ctx: &YakuContext) -> bool {
 }
 
 // 三色同順
+#[allow(clippy::collapsible_match)]
 fn is_sanshokudoujun(ctx: &YakuContext) -> bool {
     if ctx.counts.shuntsu_total < 3 {
         return false;
@@ -667,6 +682,7 @@ fn is_sanshokudoujun(ctx: &YakuContext) -> bool {
 }
 
 // 三色同刻
+#[allow(clippy::collapsible_match)]
 fn is_sanshokudoukou(ctx: &YakuContext) -> bool {
     if ctx.counts.koutsu_total < 3 {
         return false;
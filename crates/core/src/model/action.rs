@@ -120,6 +120,27 @@ impl fmt::Display for Action {
     }
 }
 
+// 不正なアクションが選択された際にActorに通知されるエラー
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionError {
+    pub seat: Seat,
+    pub action: Action,     // 選択された不正なアクション
+    pub fallback: Action,   // 代わりに適用されたアクション
+    pub reason: String,     // 不正と判定された理由
+    pub violations: usize,  // 不正なアクションの累計回数
+    pub is_forfeited: bool, // 累計回数が上限に達したため以降のアクションは自動で選択される
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "seat{}: invalid action {} ({}), fallback to {} [violations: {}]",
+            self.seat, self.action, self.reason, self.fallback, self.violations
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WinningTile {
     pub tile: Tile,     // 和了牌
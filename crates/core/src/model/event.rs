@@ -14,6 +14,7 @@ pub enum Event {
     Win(EventWin),           // 局終了 (和了)
     Draw(EventDraw),         // 局終了 (流局)
     End(EventEnd),           // ゲーム終了
+    Timeout(EventTimeout),   // アクション選択の時間切れ
 }
 
 impl Event {
@@ -130,6 +131,11 @@ impl Event {
     pub fn end() -> Self {
         Self::End(EventEnd {})
     }
+
+    #[inline]
+    pub fn timeout(seat: Seat) -> Self {
+        Self::Timeout(EventTimeout { seat })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -207,6 +213,11 @@ pub struct EventDraw {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventEnd {}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventTimeout {
+    pub seat: Seat, // 時間切れになったプレイヤー (既定のアクションが代わりに選択される)
+}
+
 // [DrawType]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DrawType {
@@ -1,12 +1,16 @@
 use super::*;
 
+// Eventのタグも"type"なので内容は"data"に格納 (例: {"type":"Event","data":{"type":"Deal",...}})
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
+#[serde(tag = "type", content = "data")]
 pub enum ServerMessage {
     // clippyの警告が出るのでBox化 #large_enum_variant
     Event(Box<Event>),
     Action(PossibleActions),
     Info { seat: Seat },
+    Error(ActionError), // 選択されたアクションが不正だった場合の通知
+    // ロビーサーバでJoinが受理された場合に送信 (対局中に再接続する場合はJoinのsecretに指定)
+    Session { secret: String },
     Log, // TODO
 }
 
@@ -14,6 +18,14 @@ pub enum ServerMessage {
 #[serde(tag = "type")]
 pub enum ClientMessage {
     Action(SelectedAction),
+    // 接続直後に送信 (Endpointのtokenと一致しない場合は切断) nameはロビーサーバでのみ使用
+    Join {
+        token: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        secret: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,3 +40,27 @@ pub struct SelectedAction {
     pub id: u32, // ServerMessageの対応するActionのidをそのままコピー (誤動作防止用)
     pub action: Action,
 }
+
+#[test]
+fn test_message_json() {
+    // 往復変換で内側のEventのタグが保持されることを確認
+    let msg = ServerMessage::Event(Box::new(Event::Deal(EventDeal {
+        seat: 1,
+        tile: Tile(TP, 0),
+        is_replacement: false,
+    })));
+    let s = serde_json::to_string(&msg).unwrap();
+    let Ok(ServerMessage::Event(ev)) = serde_json::from_str(&s) else {
+        panic!("{}", s);
+    };
+    let Event::Deal(ev) = *ev else {
+        panic!("{}", s);
+    };
+    assert_eq!((ev.seat, ev.tile), (1, Tile(TP, 0)));
+
+    let s = r#"{"type":"Join","token":"abc"}"#;
+    let Ok(ClientMessage::Join { token, .. }) = serde_json::from_str(s) else {
+        panic!("{}", s);
+    };
+    assert_eq!(token, "abc");
+}
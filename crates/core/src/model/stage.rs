@@ -1,14 +1,54 @@
 use super::*;
 use crate::{control::common::tiles_from_tile_table, util::misc::vec_to_string};
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)] // 古いバージョンで出力されたログとの互換性のため,存在しない項目は既定値を使用
 pub struct Rule {
     pub round: usize,            // ゲーム設定 (1: 4人東, 2: 4人南)
-    pub is_sanma: bool,          // 三麻フラグ(未実装, 常にfalse)
+    pub is_sanma: bool,          // 三麻フラグ (座席3は空席)
     pub initial_score: Score,    // 初期スコア (4麻:25000)
     pub settlement_score: Score, // ゲームが終了して1位が確定するのに必要なスコア (4麻:30000)
     pub red5: usize,             // 赤ドラの枚数 (0~4) 一般的なルールでは1
     pub bust: bool,              // 飛びのアリなし
+    pub bust_score: Score,       // 飛びになる点数 この点数未満で飛び (0点で飛びにする場合は1)
+    pub time_base: f64,          // 1回のアクション選択の持ち時間(秒) 0の場合は無制限
+    pub time_extra: f64,         // 1試合の考慮時間(秒) 持ち時間を超過した分を消費
+    pub max_violations: usize, // 不正なアクションがこの回数に達したプレイヤーは以降自動で打牌 (0: 無制限)
+    pub kuitan: bool,          // 喰いタンのアリなし
+    pub kiriage: bool,         // 切り上げ満貫 (30符4飜, 60符3飜を満貫として扱う)
+    pub atamahane: bool,       // 頭ハネ (複数人のロンは放銃者から最も近いプレイヤーのみ和了)
+    pub sanchahou: bool,       // 三家和 (3人同時のロンは途中流局)
+    pub honba_per_winner: bool, // 複数人のロンで積み棒を和了者全員が受け取る (falseの場合は上家取り)
+    pub double_yakuman: bool, // 四暗刻単騎, 大四喜, 純正九蓮宝燈, 国士無双13面を二倍役満として扱う
+    pub composite_yakuman: bool, // 複数の役満の複合 (falseの場合は最も倍率の高い役満のみ)
+    pub yakuman_cap: usize,   // 役満の倍率の上限 (0: 無制限)
+    pub kazoe_yakuman: bool,  // 数え役満 (falseの場合は13飜以上も三倍満)
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            round: 0,
+            is_sanma: false,
+            initial_score: 0,
+            settlement_score: 0,
+            red5: 0,
+            bust: false,
+            bust_score: 0,
+            time_base: 0.0,
+            time_extra: 0.0,
+            max_violations: 0,
+            kuitan: true,
+            kiriage: false,
+            atamahane: false,
+            sanchahou: false,
+            honba_per_winner: false,
+            double_yakuman: true,
+            composite_yakuman: true,
+            yakuman_cap: 0,
+            kazoe_yakuman: true,
+        }
+    }
 }
 
 #[derive(Debug, Default, Serialize)]
@@ -86,7 +126,8 @@ pub struct Player {
     pub nukidoras: Vec<Nukidora>, // 北抜き
     pub riichi: Option<Index>,    // リーチ宣言牌のdiscardsにおけるindex
     pub discards: Vec<Discard>,   // 捨て牌一覧
-    pub pao: Option<Seat>,        // 責任払い, 役満を確定する副露を許したプレイヤーの座席
+    pub pao: Option<Seat>,        // 責任払い, 大三元・大四喜を確定する副露を許したプレイヤーの座席
+    pub pao_kan: Option<Seat>,    // 責任払い, 四槓子を確定する大明槓を許したプレイヤーの座席
     pub is_shown: bool,           // 手牌が見えるかどうか 見えない場合,手牌はすべてz8(=unknown)
     pub rank: usize,              // 現在の順位
 
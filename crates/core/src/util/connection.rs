@@ -1,12 +1,13 @@
 use std::{
     fmt,
     io::prelude::*,
-    net::{TcpListener, TcpStream},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
     sync::mpsc,
     thread,
+    time::{Duration, Instant},
 };
 
-use crate::{error, info, warn};
+use crate::{error, info, util::misc::Res, warn};
 
 #[derive(Debug)]
 pub enum Message {
@@ -20,6 +21,104 @@ pub enum Message {
 pub trait Connection: Send {
     fn send(&mut self, _msg: &str);
     fn recv(&mut self) -> Message;
+    fn close(&mut self);
+}
+
+// protocol: "tcp" | "ws"
+pub fn create_connection(protocol: &str, addr: &str) -> Res<Box<dyn Connection>> {
+    Ok(match protocol {
+        "tcp" => Box::new(TcpConnection::new(addr)),
+        "ws" => Box::new(WsConnection::new(addr)),
+        _ => return Err(format!("unknown protocol: {}", protocol).into()),
+    })
+}
+
+// クライアント側の接続 切断された場合はrecvの呼び出し時に再接続を試行
+pub fn create_client_connection(protocol: &str, addr: &str) -> Res<Box<dyn Connection>> {
+    Ok(match protocol {
+        "tcp" => Box::new(TcpConnection::connect(addr)),
+        "ws" => Box::new(WsConnection::connect(addr)),
+        _ => return Err(format!("unknown protocol: {}", protocol).into()),
+    })
+}
+
+// 複数のクライアントからの接続を受け付けて接続ごとにConnectionを生成 (ロビーサーバ用)
+// 生成されたConnectionは接続済みのため,Message::Openは発生しない
+pub fn listen_connections(protocol: &str, addr: &str) -> Res<mpsc::Receiver<Box<dyn Connection>>> {
+    if protocol != "tcp" && protocol != "ws" {
+        return Err(format!("unknown protocol: {}", protocol).into());
+    }
+    let is_ws = protocol == "ws";
+    let listener = TcpListener::bind(addr)?;
+    let (tx, rx) = mpsc::channel::<Box<dyn Connection>>();
+    thread::spawn(move || {
+        for request in listener.incoming() {
+            let stream = match request {
+                Ok(stream) => stream,
+                Err(err) => {
+                    error!("tcp error: {}", err);
+                    continue;
+                }
+            };
+            if !is_ws {
+                if tx
+                    .send(Box::new(TcpConnection::from_stream(stream)))
+                    .is_err()
+                {
+                    return;
+                }
+                continue;
+            }
+            // ハンドシェイクで他の接続の受け付けが止まらないように別スレッドで実行
+            let tx2 = tx.clone();
+            thread::spawn(move || {
+                if let Some(conn) = WsConnection::from_stream(stream) {
+                    tx2.send(Box::new(conn)).ok();
+                }
+            });
+        }
+    });
+    Ok(rx)
+}
+
+// 再接続の試行間隔
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+// WebSocketのハンドシェイクの待機時間の上限
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// クライアントとして接続する場合の接続先
+struct Remote {
+    addr: String,
+    last_try: Option<Instant>,
+}
+
+impl Remote {
+    fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.into(),
+            last_try: None,
+        }
+    }
+
+    fn try_connect(&mut self) -> Option<TcpStream> {
+        if self
+            .last_try
+            .is_some_and(|t| t.elapsed() < RECONNECT_INTERVAL)
+        {
+            return None;
+        }
+        self.last_try = Some(Instant::now());
+
+        let sock_addr = match self.addr.to_socket_addrs().map(|mut it| it.next()) {
+            Ok(Some(a)) => a,
+            _ => {
+                error!("invalid address: {}", self.addr);
+                return None;
+            }
+        };
+        TcpStream::connect_timeout(&sock_addr, RECONNECT_INTERVAL).ok()
+    }
 }
 
 impl fmt::Debug for dyn Connection {
@@ -33,14 +132,23 @@ impl fmt::Debug for dyn Connection {
 pub struct TcpConnection {
     stream: Option<TcpStream>,
     rx: mpsc::Receiver<TcpStream>,
+    buf: Vec<u8>, // 受信済みで未処理のデータ
+    remote: Option<Remote>,
+    local_addr: Option<SocketAddr>,
 }
 
 impl TcpConnection {
     pub fn new(addr: &str) -> Self {
         let (tx, rx) = mpsc::channel();
-        let conn = Self { stream: None, rx };
-
         let listener = TcpListener::bind(addr).unwrap();
+        let conn = Self {
+            stream: None,
+            rx,
+            buf: vec![],
+            remote: None,
+            local_addr: listener.local_addr().ok(),
+        };
+
         thread::spawn(move || {
             for request in listener.incoming() {
                 match request {
@@ -52,6 +160,38 @@ impl TcpConnection {
 
         conn
     }
+
+    pub fn connect(addr: &str) -> Self {
+        let (_, rx) = mpsc::channel();
+        Self {
+            stream: None,
+            rx,
+            buf: vec![],
+            remote: Some(Remote::new(addr)),
+            local_addr: None,
+        }
+    }
+
+    // 待ち受けているアドレス (ポート0を指定した場合に実際のポートを取得するため)
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    fn from_stream(stream: TcpStream) -> Self {
+        stream.set_nonblocking(true).unwrap();
+        info!(
+            "tcp connection opened from: {}",
+            stream.peer_addr().unwrap()
+        );
+        let (_, rx) = mpsc::channel();
+        Self {
+            stream: Some(stream),
+            rx,
+            buf: vec![],
+            remote: None,
+            local_addr: None,
+        }
+    }
 }
 
 impl Connection for TcpConnection {
@@ -78,59 +218,139 @@ impl Connection for TcpConnection {
         }
 
         if self.stream.is_none() {
+            if let Some(remote) = &mut self.remote
+                && let Some(stream) = remote.try_connect()
+            {
+                stream.set_nonblocking(true).unwrap();
+                info!("tcp connected to: {}", remote.addr);
+                self.stream = Some(stream);
+                return Message::Open;
+            }
             return Message::NoConnection;
         }
 
+        // 1回の読み込みで複数のメッセージを受信する場合があるので残りはbufに保持
         let stream = self.stream.as_mut().unwrap();
-        let mut reader = std::io::BufReader::new(stream);
-        let mut buf = String::new();
-        match reader.read_line(&mut buf) {
-            Ok(size) => {
-                if size != 0 {
-                    if buf.ends_with('\n') {
-                        buf.pop();
-                    }
-                    return Message::Text(buf);
-                }
+        loop {
+            if let Some(i) = self.buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buf.drain(..=i).collect();
+                return Message::Text(String::from_utf8_lossy(&line[..i]).into());
             }
-            Err(err) => {
-                if err.kind() == std::io::ErrorKind::WouldBlock {
-                    return Message::Nop;
-                } else {
+
+            let mut tmp = [0; 4096];
+            match stream.read(&mut tmp) {
+                Ok(0) => break,
+                Ok(size) => self.buf.extend_from_slice(&tmp[..size]),
+                Err(err) => {
+                    if err.kind() == std::io::ErrorKind::WouldBlock {
+                        return Message::Nop;
+                    }
                     error!("{}", err);
+                    break;
                 }
             }
         }
 
         self.stream = None;
+        self.buf.clear();
         info!("tcp connection closed");
         Message::Close
     }
+
+    fn close(&mut self) {
+        self.buf.clear();
+        if let Some(stream) = self.stream.take() {
+            stream.shutdown(std::net::Shutdown::Both).ok();
+            info!("tcp connection closed by server");
+        }
+    }
 }
 
 // websocket
+type WebSocket = tungstenite::protocol::WebSocket<TcpStream>;
+
 pub struct WsConnection {
-    stream: Option<tungstenite::protocol::WebSocket<TcpStream>>,
-    rx: mpsc::Receiver<TcpStream>,
+    stream: Option<WebSocket>,
+    rx: mpsc::Receiver<WebSocket>, // ハンドシェイク済みの接続
+    remote: Option<Remote>,
+    local_addr: Option<SocketAddr>,
 }
 
 impl WsConnection {
     pub fn new(addr: &str) -> Self {
         let (tx, rx) = mpsc::channel();
-        let conn = Self { stream: None, rx };
-
         let listener = TcpListener::bind(addr).unwrap();
+        let conn = Self {
+            stream: None,
+            rx,
+            remote: None,
+            local_addr: listener.local_addr().ok(),
+        };
+
         thread::spawn(move || {
             for request in listener.incoming() {
-                match request {
-                    Ok(stream) => tx.send(stream).unwrap(),
-                    Err(err) => error!("ws error: {}", err),
-                }
+                let stream = match request {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        error!("ws error: {}", err);
+                        continue;
+                    }
+                };
+                // ハンドシェイクを送らないクライアントでrecv(Endpointのロック中)が止まらないように別スレッドで実行
+                let tx2 = tx.clone();
+                thread::spawn(move || {
+                    if let Some(s) = accept_ws(stream) {
+                        tx2.send(s).ok();
+                    }
+                });
             }
         });
 
         conn
     }
+
+    pub fn connect(addr: &str) -> Self {
+        let (_, rx) = mpsc::channel();
+        Self {
+            stream: None,
+            rx,
+            remote: Some(Remote::new(addr)),
+            local_addr: None,
+        }
+    }
+
+    // 待ち受けているアドレス (ポート0を指定した場合に実際のポートを取得するため)
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    fn from_stream(stream: TcpStream) -> Option<Self> {
+        let s = accept_ws(stream)?;
+        let (_, rx) = mpsc::channel();
+        Some(Self {
+            stream: Some(s),
+            rx,
+            remote: None,
+            local_addr: None,
+        })
+    }
+}
+
+// ハンドシェイクはタイムアウト付きのブロッキングで行い,完了後にノンブロッキングに変更
+fn accept_ws(stream: TcpStream) -> Option<WebSocket> {
+    info!("ws connection opened from: {}", stream.peer_addr().unwrap());
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).ok();
+    match tungstenite::accept(stream) {
+        Ok(s) => {
+            s.get_ref().set_read_timeout(None).ok();
+            s.get_ref().set_nonblocking(true).unwrap();
+            Some(s)
+        }
+        Err(err) => {
+            error!("ws upgrade error: {}", err);
+            None
+        }
+    }
 }
 
 impl Connection for WsConnection {
@@ -141,16 +361,9 @@ impl Connection for WsConnection {
     }
 
     fn recv(&mut self) -> Message {
-        if let Ok(stream) = self.rx.try_recv() {
+        if let Ok(s) = self.rx.try_recv() {
             if self.stream.is_none() {
-                stream.set_nonblocking(true).unwrap();
-
-                info!("ws connection opened from: {}", stream.peer_addr().unwrap());
-                match tungstenite::accept(stream) {
-                    Ok(s) => self.stream = Some(s),
-                    Err(err) => error!("ws upgrade error: {}", err),
-                }
-
+                self.stream = Some(s);
                 return Message::Open;
             } else {
                 error!("ws duplicated connection");
@@ -158,6 +371,20 @@ impl Connection for WsConnection {
         }
 
         if self.stream.is_none() {
+            if let Some(remote) = &mut self.remote
+                && let Some(stream) = remote.try_connect()
+            {
+                let url = format!("ws://{}/", remote.addr);
+                match tungstenite::client(url, stream) {
+                    Ok((s, _)) => {
+                        s.get_ref().set_nonblocking(true).unwrap();
+                        info!("ws connected to: {}", remote.addr);
+                        self.stream = Some(s);
+                        return Message::Open;
+                    }
+                    Err(err) => error!("ws handshake error: {}", err),
+                }
+            }
             return Message::NoConnection;
         }
 
@@ -200,6 +427,14 @@ impl Connection for WsConnection {
         info!("ws connection closed");
         Message::Close
     }
+
+    fn close(&mut self) {
+        if let Some(mut stream) = self.stream.take() {
+            stream.close(None).ok();
+            stream.flush().ok();
+            info!("ws connection closed by server");
+        }
+    }
 }
 
 // #[test]
@@ -215,3 +450,48 @@ impl Connection for WsConnection {
 //         sleep(0.1);
 //     }
 // }
+
+// 受信するまで最大3秒待機 (再接続の試行間隔は1秒)
+#[cfg(test)]
+use crate::control::testing::wait_message;
+
+#[test]
+fn test_tcp_loopback() {
+    let mut server = TcpConnection::new("127.0.0.1:0");
+    let addr = server.local_addr().unwrap().to_string();
+    let mut client = TcpConnection::connect(&addr);
+    assert!(matches!(wait_message(&mut client), Message::Open));
+    assert!(matches!(wait_message(&mut server), Message::Open));
+
+    // 連続して送信したメッセージが1つずつ受信できる
+    for i in 0..3 {
+        server.send(&i.to_string());
+    }
+    for i in 0..3 {
+        assert!(matches!(wait_message(&mut client), Message::Text(t) if t == i.to_string()));
+    }
+
+    // サーバ側から切断した後に再接続
+    server.close();
+    assert!(matches!(wait_message(&mut client), Message::Close));
+    assert!(matches!(wait_message(&mut client), Message::Open));
+    assert!(matches!(wait_message(&mut server), Message::Open));
+    client.send("x");
+    assert!(matches!(wait_message(&mut server), Message::Text(t) if t == "x"));
+}
+
+#[test]
+fn test_ws_stalled_handshake() {
+    // ハンドシェイクを送らないクライアントがいても他のクライアントと接続できる
+    let mut server = WsConnection::new("127.0.0.1:0");
+    let addr = server.local_addr().unwrap().to_string();
+    let _stalled = TcpStream::connect(&addr).unwrap();
+    let mut client = WsConnection::connect(&addr);
+    assert!(matches!(wait_message(&mut client), Message::Open));
+    assert!(matches!(wait_message(&mut server), Message::Open));
+
+    server.send("a");
+    assert!(matches!(wait_message(&mut client), Message::Text(t) if t == "a"));
+    client.send("b");
+    assert!(matches!(wait_message(&mut server), Message::Text(t) if t == "b"));
+}
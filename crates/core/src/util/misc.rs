@@ -130,3 +130,60 @@ pub fn rank_by_rank_vec<T: Ord + Clone>(v: &[T]) -> Vec<usize> {
     }
     res
 }
+
+// 0..nのすべての順列 (辞書順)
+pub fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut res = vec![];
+    for p in permutations(n - 1) {
+        for i in 0..n {
+            let mut p2: Vec<usize> = p.iter().map(|&x| if x >= i { x + 1 } else { x }).collect();
+            p2.insert(0, i);
+            res.push(p2);
+        }
+    }
+    res.sort();
+    res
+}
+
+// 標本の平均と平均の標準誤差 (標本が1つ以下の場合,標準誤差はNaN)
+pub fn mean_and_se(v: &[f64]) -> (f64, f64) {
+    let n = v.len() as f64;
+    let mean = v.iter().sum::<f64>() / n;
+    let var = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, (var / n).sqrt())
+}
+
+#[test]
+fn test_permutations() {
+    assert_eq!(permutations(0), vec![Vec::<usize>::new()]);
+    assert_eq!(
+        permutations(3),
+        vec![
+            vec![0, 1, 2],
+            vec![0, 2, 1],
+            vec![1, 0, 2],
+            vec![1, 2, 0],
+            vec![2, 0, 1],
+            vec![2, 1, 0],
+        ]
+    );
+    let perms = permutations(4);
+    assert_eq!(perms.len(), 24);
+    assert!(perms.windows(2).all(|w| w[0] < w[1])); // 辞書順で重複なし
+}
+
+#[test]
+fn test_mean_and_se() {
+    let (mean, se) = mean_and_se(&[1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(mean, 2.5);
+    assert!((se - (5.0f64 / 3.0 / 4.0).sqrt()).abs() < 1e-12);
+
+    // 標本が同じ値のみの場合は0, 1つの場合はNaN
+    assert_eq!(mean_and_se(&[2.0, 2.0]), (2.0, 0.0));
+    let (mean, se) = mean_and_se(&[5.0]);
+    assert_eq!(mean, 5.0);
+    assert!(se.is_nan());
+}
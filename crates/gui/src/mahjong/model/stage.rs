@@ -171,6 +171,7 @@ impl GuiStage {
             MjEvent::Win(ev) => self.event_win(ev),
             MjEvent::Draw(ev) => self.event_draw(ev),
             MjEvent::End(ev) => self.event_end(ev),
+            MjEvent::Timeout(_) => {}
         }
 
         apply_event(&mut self.stage, event);
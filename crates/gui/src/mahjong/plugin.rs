@@ -211,6 +211,10 @@ impl GuiMahjong {
                 ServerMessage::Info { seat } => {
                     self.player_seat = Some(seat); // このメッセージはEventNewより先に受信
                 }
+                ServerMessage::Error(err) => {
+                    error!("server error: {err}");
+                }
+                ServerMessage::Session { .. } => {} // GUIクライアントモードの接続側で処理
                 ServerMessage::Log => todo!(),
             }
         }
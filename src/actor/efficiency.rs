@@ -0,0 +1,337 @@
+use mahjong_core::{
+    control::{common::*, safety::calc_riichi_danger},
+    hand::*,
+};
+
+use super::*;
+
+pub struct EfficiencyBotBuilder;
+
+impl ActorBuilder for EfficiencyBotBuilder {
+    fn get_default_config(&self) -> Config {
+        Config {
+            name: "EfficiencyBot".into(),
+            args: vec![Arg::int("aggressiveness", 1), Arg::bool("call", true)],
+        }
+    }
+
+    fn create(&self, config: Config) -> Box<dyn Actor> {
+        Box::new(EfficiencyBot::from_config(config))
+    }
+}
+
+#[derive(Clone)]
+pub struct EfficiencyBot {
+    config: Config,
+    stage: StageRef,
+    seat: Seat,
+    aggressiveness: i32, // 他家のリーチに対して向聴数がこの値未満なら押す (0: 常にベタオリ)
+    call: bool,          // 鳴きの有無
+}
+
+// 向聴数と受け入れ枚数による牌効率Bot
+// 乱数を使用しないので同じシードの対局は同じ結果になる
+impl EfficiencyBot {
+    pub fn from_config(config: Config) -> Self {
+        let args = &config.args;
+        let aggressiveness = args[0].value.as_int();
+        let call = args[1].value.as_bool();
+        Self {
+            config,
+            stage: StageRef::default(),
+            seat: NO_SEAT,
+            aggressiveness,
+            call,
+        }
+    }
+
+    fn select_turn_action(&self, stg: &Stage, acts: &[Action]) -> Action {
+        let pl = &stg.players[self.seat];
+        let find = |tp: ActionType| acts.iter().find(|a| a.ty == tp);
+
+        if let Some(a) = find(ActionType::Tsumo) {
+            return a.clone();
+        }
+        if pl.is_riichi {
+            return Action::nop(); // ツモ切り
+        }
+        if let Some(a) = find(ActionType::Nukidora) {
+            return a.clone();
+        }
+        if let Some(a) = find(ActionType::Kyushukyuhai)
+            && calc_kokushimusou_shanten(&pl.hand) > 2
+        {
+            return a.clone();
+        }
+
+        // 鳴いた後は喰い替えになる牌を除外
+        // 受け入れの打牌候補は赤5を区別しないため,アクションの牌も赤5を通常の5として比較
+        let contains = |a: &Action, t: Tile| a.tiles.iter().any(|t2| t2.to_normal() == t);
+        let ukeire: Vec<Ukeire> = calc_ukeire(&pl.hand, &get_visible_tiles(stg))
+            .into_iter()
+            .filter(|u| find(ActionType::Discard).is_none_or(|a| !contains(a, u.discard)))
+            .collect();
+        let Some(best) = ukeire.first() else {
+            return Action::nop();
+        };
+
+        // 他家のリーチに対して向聴数が大きい場合は放銃率の最も低い牌でベタオリ
+        if is_threatened(stg, self.seat) && best.shanten >= self.aggressiveness {
+            let u = ukeire
+                .iter()
+                .min_by(|u0, u1| {
+                    let d0 = calc_riichi_danger(stg, self.seat, u0.discard);
+                    let d1 = calc_riichi_danger(stg, self.seat, u1.discard);
+                    d0.total_cmp(&d1)
+                })
+                .unwrap();
+            return Action::discard(self.to_hand_tile(pl, u.discard));
+        }
+
+        // 聴牌かつ和了牌が残っている場合はリーチ
+        if let Some(a) = find(ActionType::Riichi) {
+            let u = ukeire
+                .iter()
+                .find(|u| u.shanten == 0 && u.n_live > 0 && contains(a, u.discard));
+            if let Some(u) = u {
+                return Action::riichi(self.to_hand_tile(pl, u.discard));
+            }
+        }
+
+        Action::discard(self.to_hand_tile(pl, best.discard))
+    }
+
+    fn select_call_action(&self, stg: &Stage, acts: &[Action]) -> Action {
+        if acts.contains(&Action::ron()) {
+            return Action::ron();
+        }
+        if !self.call || is_threatened(stg, self.seat) {
+            return Action::nop();
+        }
+        let Some((_, _, t)) = stg.last_tile else {
+            return Action::nop();
+        };
+        let t = t.to_normal();
+
+        let pl = &stg.players[self.seat];
+        let shanten = calc_shanten(&pl.hand);
+        let is_yakuhai_tile = is_yakuhai(stg, self.seat, t);
+        let has_yaku = pl
+            .melds
+            .iter()
+            .any(|m| is_yakuhai(stg, self.seat, m.tiles[0]))
+            || (0..TNUM).any(|ni| pl.hand[TZ][ni] >= 3 && is_yakuhai(stg, self.seat, Tile(TZ, ni)));
+
+        let mut best = (shanten, Action::nop());
+        for act in acts {
+            match act.ty {
+                ActionType::Pon if is_yakuhai_tile => {
+                    // 役牌は向聴数が悪化しなければポン
+                    let s = calc_shanten_after_call(&pl.hand, &act.tiles);
+                    if s <= best.0 {
+                        best = (s, act.clone());
+                    }
+                }
+                ActionType::Chi | ActionType::Pon => {
+                    // 役が確定しているか喰いタンが可能な場合のみ向聴数が進む鳴きを行う
+                    let is_kuitan = stg.rule.kuitan && is_tanyao_shape(pl, &act.tiles, t);
+                    if !has_yaku && !is_kuitan {
+                        continue;
+                    }
+                    let s = calc_shanten_after_call(&pl.hand, &act.tiles);
+                    if s < best.0 {
+                        best = (s, act.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        best.1
+    }
+
+    // 赤5しか持っていない場合は赤5を打牌
+    fn to_hand_tile(&self, pl: &Player, t: Tile) -> Tile {
+        tiles_with_red5(&pl.hand, t)[0]
+    }
+}
+
+impl Actor for EfficiencyBot {
+    fn init(&mut self, stage: StageRef, seat: Seat) {
+        self.stage = stage;
+        self.seat = seat;
+    }
+
+    fn select(&mut self, acts: &[Action], _tenpais: &[Tenpai]) -> ActionFuture {
+        let stg = self.stage.lock().unwrap();
+        let act = if stg.turn == self.seat {
+            self.select_turn_action(&stg, acts)
+        } else {
+            self.select_call_action(&stg, acts)
+        };
+        ready(act)
+    }
+
+    fn get_config(&self) -> &Config {
+        &self.config
+    }
+}
+
+impl Listener for EfficiencyBot {}
+
+// 鳴いた後(打牌前)の手牌の向聴数
+fn calc_shanten_after_call(hand: &TileTable, consumed: &[Tile]) -> i32 {
+    let mut h = *hand;
+    for &t in consumed {
+        dec_tile(&mut h, t);
+    }
+    calc_shanten(&h).max(0)
+}
+
+fn is_yakuhai(stg: &Stage, seat: Seat, t: Tile) -> bool {
+    t.is_doragon()
+        || t == Tile(TZ, get_prevalent_wind(stg))
+        || t == Tile(TZ, get_seat_wind(stg, seat))
+}
+
+// 副露と鳴きに使う牌がすべて中張牌で,手牌の么九牌が1枚以下
+fn is_tanyao_shape(pl: &Player, consumed: &[Tile], t: Tile) -> bool {
+    let meld_tiles = pl.melds.iter().flat_map(|m| m.tiles.iter());
+    if !meld_tiles
+        .chain(consumed)
+        .chain([&t])
+        .all(|t| t.is_simple())
+    {
+        return false;
+    }
+    let n_end: usize = tiles_from_tile_table(&pl.hand)
+        .iter()
+        .filter(|t| t.is_end())
+        .count();
+    n_end <= 1
+}
+
+fn is_threatened(stg: &Stage, seat: Seat) -> bool {
+    (0..get_n_seat(stg)).any(|s| s != seat && stg.players[s].is_riichi)
+}
+
+#[cfg(test)]
+fn create_test_controller(
+    hands: [&str; SEAT],
+) -> mahjong_core::control::stage_controller::StageController {
+    use mahjong_core::control::{
+        stage_controller::StageController, string::tiles_from_string, testing::east_rule,
+    };
+
+    let nop = create_actor("Nop");
+    let actors = std::array::from_fn(|_| nop.clone_box());
+    let mut ctrl = StageController::new(actors, vec![], [false; SEAT]);
+    let rule = Rule {
+        red5: 1,
+        ..east_rule()
+    };
+    let hands = hands.map(|h| tiles_from_string(h).unwrap());
+    ctrl.handle_event(&Event::begin());
+    ctrl.handle_event(&Event::new(
+        rule,
+        0,
+        0,
+        0,
+        0,
+        vec![Tile(TS, 1)],
+        Default::default(),
+        [25000; SEAT],
+        hands,
+        70,
+        2,
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+    ));
+    ctrl
+}
+
+#[cfg(test)]
+fn create_test_bot(seat: Seat) -> EfficiencyBot {
+    let mut bot = EfficiencyBot::from_config(EfficiencyBotBuilder.get_default_config());
+    bot.seat = seat;
+    bot
+}
+
+#[test]
+fn test_efficiency_bot() {
+    use mahjong_core::control::{possible_actions::*, stage_controller::StageController};
+
+    let turn_action = |ctrl: &StageController| {
+        let stg = ctrl.get_stage();
+        let s = stg.turn;
+        let tenpais = calc_possible_tenpai_discards(
+            &stg.players[s],
+            get_prevalent_wind(&stg),
+            get_seat_wind(&stg, s),
+            &stg.rule,
+        );
+        let acts = calc_possible_turn_actions(&stg, &None, &tenpais);
+        create_test_bot(s).select_turn_action(&stg, &acts)
+    };
+    let others = ["s1112223334445", "s5556667778889", "p1112223334449"];
+
+    // 孤立した字牌を打牌
+    let mut ctrl = create_test_controller(["m123p456s789m58z11", others[0], others[1], others[2]]);
+    ctrl.handle_event(&Event::deal(0, Tile(TZ, 7), false));
+    assert_eq!(turn_action(&ctrl), Action::discard(Tile(TZ, 7)));
+
+    // 聴牌の場合はリーチ (打牌が赤5のみの場合も同様)
+    let mut ctrl = create_test_controller(["m12356s789z11122", others[0], others[1], others[2]]);
+    ctrl.handle_event(&Event::deal(0, Tile(TP, 0), false));
+    assert_eq!(turn_action(&ctrl), Action::riichi(Tile(TP, 0)));
+
+    // 役牌(中)は向聴数が悪化しなければポン
+    let mut ctrl =
+        create_test_controller(["m123p456s78m59z77", "m1112223334449", others[1], others[2]]);
+    ctrl.handle_event(&Event::deal(0, Tile(TZ, 5), false));
+    ctrl.handle_event(&Event::discard(0, Tile(TZ, 5), true, false));
+    ctrl.handle_event(&Event::deal(1, Tile(TZ, 7), false));
+    ctrl.handle_event(&Event::discard(1, Tile(TZ, 7), true, false));
+    let stg = ctrl.get_stage();
+    let acts = &calc_possible_call_actions(&stg, true)[0];
+    let act = create_test_bot(0).select_call_action(&stg, acts);
+    assert_eq!(act.ty, ActionType::Pon);
+    assert_eq!(act.tiles, [Tile(TZ, 7), Tile(TZ, 7)]);
+    drop(stg);
+
+    // 他家のリーチに対して向聴数が大きい場合は現物でベタオリ
+    let mut ctrl = create_test_controller(["m147p258s369z135m5", others[0], others[1], others[2]]);
+    ctrl.handle_event(&Event::deal(0, Tile(TZ, 2), false));
+    ctrl.handle_event(&Event::discard(0, Tile(TZ, 2), true, false));
+    ctrl.handle_event(&Event::deal(1, Tile(TM, 5), false));
+    ctrl.handle_event(&Event::discard(1, Tile(TM, 5), true, true));
+    ctrl.handle_event(&Event::deal(2, Tile(TZ, 4), false));
+    ctrl.handle_event(&Event::discard(2, Tile(TZ, 4), true, false));
+    ctrl.handle_event(&Event::deal(3, Tile(TZ, 6), false));
+    ctrl.handle_event(&Event::discard(3, Tile(TZ, 6), true, false));
+    ctrl.handle_event(&Event::deal(0, Tile(TZ, 7), false));
+    assert_eq!(turn_action(&ctrl), Action::discard(Tile(TM, 5)));
+}
+
+#[test]
+fn test_efficiency_bot_determinism() {
+    use mahjong_core::control::{
+        engine::MahjongEngine,
+        testing::{Recorder, east_rule},
+    };
+
+    // 乱数を使用しないため,同じシードの対局は同じイベント列になる
+    let run = || {
+        let bot = create_actor("EfficiencyBot");
+        let actors = std::array::from_fn(|_| bot.clone_box());
+        let recorder = Recorder::default();
+        let listeners: Vec<Box<dyn Listener>> = vec![Box::new(recorder.clone())];
+        let mut game = MahjongEngine::new(7, east_rule(), 0.0, actors, listeners, [false; SEAT]);
+        game.run();
+        mahjong_core::serde_json::to_string(&recorder.events()).unwrap()
+    };
+    let events = run();
+    assert!(events.contains(r#""type":"Win""#) || events.contains(r#""type":"Draw""#));
+    assert_eq!(events, run());
+}
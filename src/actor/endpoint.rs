@@ -1,16 +1,20 @@
 use std::{
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Sender},
+    },
     task::{Context, Poll, Waker},
     thread,
 };
 
 use mahjong_core::{
     error,
-    serde_json::{self, Value, json},
+    serde_json::{self, json},
     util::{
-        connection::{Connection, Message, TcpConnection},
+        connection::{Connection, Message, create_connection},
         misc::sleep,
     },
+    warn,
 };
 
 use super::*;
@@ -22,8 +26,9 @@ impl ActorBuilder for EndpointBuilder {
         Config {
             name: "Endpoint".into(),
             args: vec![
-                Arg::bool("debug", false),
                 Arg::string("addr", "127.0.0.1:52010"),
+                Arg::string("protocol", "tcp"),
+                Arg::string("token", ""),
             ],
         }
     }
@@ -35,54 +40,129 @@ impl ActorBuilder for EndpointBuilder {
 
 #[derive(Debug, Default)]
 struct SharedData {
-    msgs: Vec<(Value, bool)>, // [(message, is_action)]
+    msgs: Vec<(ServerMessage, bool)>, // [(message, is_action)]
     cursor: usize,
+    kyoku_start: usize,     // 現在の局の最初のメッセージ(Info)のindex
+    selecting: Option<u32>, // 応答を待っているPossibleActionsのid
     action: Option<Action>,
     waker: Option<Waker>,
     is_expired: bool,
+    is_disconnected: bool, // ロビーサーバのクライアントが切断中
+    is_dropped: bool,
 }
 
+// TCP(1行に1つのJSON)またはWebSocketでクライアントと通信するActor
+// クライアントは接続直後にJoinを送信し,tokenが一致した場合のみServerMessageの送信を開始
 pub struct Endpoint {
     config: Config,
     shared: Arc<Mutex<SharedData>>,
+    conn_tx: Sender<Box<dyn Connection>>, // 再接続したクライアントの接続
     seat: Seat,
-    debug: bool,
+    act_id: u32,
 }
 
 impl Endpoint {
     pub fn from_config(config: Config) -> Self {
         let args = &config.args;
-        let debug = args[0].value.as_bool();
-        let addr = args[1].value.as_string();
-        let mut conn = Box::new(TcpConnection::new(&addr));
+        let addr = args[0].value.as_string();
+        let protocol = args[1].value.as_string();
+        let token = args[2].value.as_string();
+        let conn = match create_connection(&protocol, &addr) {
+            Ok(conn) => conn,
+            Err(err) => {
+                error!("{}", err);
+                std::process::exit(0);
+            }
+        };
+        Self::new(config, conn, Some(token))
+    }
+
+    // ロビーサーバでJoin済みのクライアントの接続から生成
+    // 切断中のアクションはすべてNopとし,reconnectorから送られた接続で再開
+    pub fn from_connection(conn: Box<dyn Connection>) -> Self {
+        let config = EndpointBuilder.get_default_config();
+        Self::new(config, conn, None)
+    }
+
+    pub fn reconnector(&self) -> Sender<Box<dyn Connection>> {
+        self.conn_tx.clone()
+    }
+
+    // tokenがNoneの場合はJoin済みの接続
+    pub(crate) fn new(
+        config: Config,
+        mut conn: Box<dyn Connection>,
+        token: Option<String>,
+    ) -> Self {
         let arc0 = Arc::new(Mutex::new(SharedData::default()));
         let arc1 = arc0.clone();
+        let (conn_tx, conn_rx) = mpsc::channel::<Box<dyn Connection>>();
 
         thread::spawn(move || {
+            let mut is_joined = token.is_none();
             loop {
                 sleep(0.01); // 負荷軽減&Lock解除時間
                 let mut d = arc1.lock().unwrap();
+                if d.is_dropped {
+                    conn.close();
+                    return;
+                }
+                if let Ok(c) = conn_rx.try_recv() {
+                    conn.close();
+                    conn = c;
+                    is_joined = true;
+                    d.cursor = d.kyoku_start;
+                    d.is_disconnected = false;
+                }
+
                 match conn.recv() {
-                    Message::Open => d.cursor = 0,
-                    Message::Text(act) => match serde_json::from_str::<Action>(&act) {
-                        Ok(act) => {
-                            d.action = Some(act);
-                            d.waker.take().unwrap().wake();
+                    Message::Open => is_joined = false,
+                    Message::Text(text) => match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(ClientMessage::Join { token: t, .. })
+                            if !is_joined && Some(&t) == token.as_ref() =>
+                        {
+                            // 再接続の場合もクライアントが局を再構築できるように現在の局の最初から送信
+                            is_joined = true;
+                            d.cursor = d.kyoku_start;
+                        }
+                        Ok(_) if !is_joined => {
+                            warn!("join rejected: {}", text);
+                            conn.close();
+                        }
+                        Ok(ClientMessage::Action(act)) => {
+                            if d.selecting == Some(act.id) {
+                                d.selecting = None;
+                                d.action = Some(act.action);
+                                if let Some(waker) = d.waker.take() {
+                                    waker.wake();
+                                }
+                            }
                         }
-                        Err(err) => error!("{}: {}", err, act),
+                        Ok(ClientMessage::Join { .. }) => warn!("already joined: {}", text),
+                        Err(err) => error!("{}: {}", err, text),
                     },
-                    Message::Nop => {
+                    Message::Nop if is_joined => {
                         while d.cursor < d.msgs.len() {
                             let (msg, is_action) = &d.msgs[d.cursor];
                             if *is_action && d.cursor != d.msgs.len() - 1 {
                                 // メッセージがアクションでかつ最後のメッセージでない場合は失効済みなので送信しない
                             } else {
-                                conn.send(&msg.to_string());
+                                conn.send(&json!(msg).to_string());
                             }
                             d.cursor += 1;
                         }
                     }
-                    Message::Close => {}
+                    Message::Nop => {}
+                    Message::Close => {
+                        is_joined = false;
+                        if token.is_none() {
+                            // 再接続はロビーサーバ経由のみ
+                            d.is_disconnected = true;
+                            if let Some(waker) = d.waker.take() {
+                                waker.wake();
+                            }
+                        }
+                    }
                     Message::NoConnection => {}
                 }
             }
@@ -91,12 +171,19 @@ impl Endpoint {
         Self {
             config,
             shared: arc0,
+            conn_tx,
             seat: NO_SEAT,
-            debug,
+            act_id: 0,
         }
     }
 }
 
+impl Drop for Endpoint {
+    fn drop(&mut self) {
+        self.shared.lock().unwrap().is_dropped = true;
+    }
+}
+
 impl Clone for Endpoint {
     fn clone(&self) -> Self {
         panic!("Actor 'Endpoint' can't be cloned");
@@ -105,14 +192,25 @@ impl Clone for Endpoint {
 
 impl Actor for Endpoint {
     fn init(&mut self, _stage: StageRef, seat: Seat) {
-        *self.shared.lock().unwrap() = SharedData::default();
+        let mut d = self.shared.lock().unwrap();
+        *d = SharedData {
+            is_disconnected: d.is_disconnected,
+            ..Default::default()
+        };
+        drop(d);
         self.seat = seat;
     }
 
     fn select(&mut self, acts: &[Action], tenpais: &[Tenpai]) -> ActionFuture {
+        self.act_id += 1;
         let mut shared = self.shared.lock().unwrap();
-        let act_msg = json!({"type": "Action", "actions": json!(acts), "tenpais": json!(tenpais)});
+        let act_msg = ServerMessage::Action(PossibleActions {
+            id: self.act_id,
+            actions: acts.to_vec(),
+            tenpais: tenpais.to_vec(),
+        });
         shared.msgs.push((act_msg, true));
+        shared.selecting = Some(self.act_id);
         shared.action = None;
         shared.is_expired = false;
 
@@ -124,7 +222,17 @@ impl Actor for Endpoint {
     fn expire(&mut self) {
         let mut shared = self.shared.lock().unwrap();
         shared.is_expired = true;
-        shared.waker.take().unwrap().wake();
+        shared.selecting = None;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn reject(&mut self, error: &ActionError) {
+        let mut shared = self.shared.lock().unwrap();
+        shared
+            .msgs
+            .push((ServerMessage::Error(error.clone()), false));
     }
 
     fn get_config(&self) -> &Config {
@@ -135,36 +243,37 @@ impl Actor for Endpoint {
 impl Listener for Endpoint {
     fn notify_event(&mut self, _stg: &Stage, event: &Event) {
         let mut d = self.shared.lock().unwrap();
-        let val = match event {
+        let ev = match event {
             Event::New(ev) => {
-                // プレイヤーから見えるべきではない情報を削除 (他家の手牌, 牌山)
+                // 他家の手牌はStageController側で秘匿済み (-omniscientで指定した座席の場合は秘匿しない)
+                // 牌山は-omniscientの場合も送信しない
                 let mut ev2 = ev.clone();
-                for s in 0..SEAT {
-                    if !self.debug && s != self.seat {
-                        ev2.hands[s].fill(Z8);
-                    }
-                }
                 ev2.wall = vec![];
                 ev2.dora_wall = vec![];
                 ev2.ura_dora_wall = vec![];
                 ev2.replacement_wall = vec![];
 
-                let mut val = json!(ev2);
-                val["seat"] = json!(self.seat);
-                val
-            }
-            Event::Deal(ev) => {
-                let t = if !self.debug && self.seat != ev.seat {
-                    Z8
-                } else {
-                    ev.tile
-                };
-                let ev2 = Event::Deal(EventDeal { tile: t, ..*ev });
-                json!(ev2)
+                d.kyoku_start = d.msgs.len();
+                d.msgs
+                    .push((ServerMessage::Info { seat: self.seat }, false));
+                Event::New(ev2)
             }
-            _ => json!(event),
+            _ => event.clone(),
         };
-        d.msgs.push((val, false));
+        d.msgs.push((ServerMessage::Event(Box::new(ev)), false));
+        drop(d);
+
+        // ゲーム終了後はすぐにプロセスが終了する可能性があるので送信完了まで待機 (最大1秒)
+        if let Event::End(_) = event {
+            for _ in 0..100 {
+                let d = self.shared.lock().unwrap();
+                if d.cursor >= d.msgs.len() {
+                    break;
+                }
+                drop(d);
+                sleep(0.01);
+            }
+        }
     }
 }
 
@@ -176,7 +285,7 @@ impl Future for SelectFuture {
     type Output = Action;
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let mut shared = self.shared.lock().unwrap();
-        if shared.is_expired {
+        if shared.is_expired || shared.is_disconnected {
             return Poll::Ready(Action::nop());
         }
         if shared.action.is_none() {
@@ -186,3 +295,72 @@ impl Future for SelectFuture {
         Poll::Ready(shared.action.take().unwrap())
     }
 }
+
+#[test]
+fn test_endpoint() {
+    use mahjong_core::{
+        control::testing::{wait_action, wait_message},
+        serde_json::Value,
+        util::connection::TcpConnection,
+    };
+
+    // 127.0.0.1:0で待ち受けるEndpointとそれに接続したクライアント
+    let connect = || {
+        let server = TcpConnection::new("127.0.0.1:0");
+        let addr = server.local_addr().unwrap().to_string();
+        let config = EndpointBuilder.get_default_config();
+        let ep = Endpoint::new(config, Box::new(server), Some("secret".into()));
+        let mut client = TcpConnection::connect(&addr);
+        assert!(matches!(wait_message(&mut client), Message::Open));
+        (ep, client)
+    };
+    let recv_json = |client: &mut TcpConnection| match wait_message(client) {
+        Message::Text(text) => serde_json::from_str::<Value>(&text).unwrap(),
+        msg => panic!("text is expected, but {:?}", msg),
+    };
+    let action = r#"{"type":"Action","id":1,"action":{"ty":"Discard","tiles":["m1"]}}"#;
+
+    // tokenが一致しない場合は切断
+    let (_ep, mut client) = connect();
+    client.send(r#"{"type":"Join","token":"wrong","name":""}"#);
+    assert!(matches!(wait_message(&mut client), Message::Close));
+
+    // Join前のActionは拒否して切断
+    let (_ep, mut client) = connect();
+    client.send(action);
+    assert!(matches!(wait_message(&mut client), Message::Close));
+
+    // Join後はServerMessageを{"type":..,"data":..}の形式で送信し,同じidのActionを受け付ける
+    let (mut ep, mut client) = connect();
+    client.send(r#"{"type":"Join","token":"secret","name":""}"#);
+    ep.init(StageRef::default(), 0);
+    ep.notify_event(&Stage::default(), &Event::begin());
+    let acts = [Action::nop(), Action::new(ActionType::Discard, vec![])];
+    let fut = ep.select(&acts, &[]);
+    let msg = recv_json(&mut client);
+    assert_eq!(msg["type"], "Event");
+    assert_eq!(msg["data"]["type"], "Begin");
+    let msg = recv_json(&mut client);
+    assert_eq!(msg["type"], "Action");
+    assert_eq!(msg["data"]["id"], 1);
+    assert_eq!(
+        msg["data"]["actions"][1],
+        json!({"ty": "Discard", "tiles": []})
+    );
+    assert_eq!(msg["data"]["tenpais"], json!([]));
+    client.send(&action.replace(r#""id":1"#, r#""id":2"#)); // idが異なるActionは無視
+    client.send(action);
+    assert_eq!(wait_action(fut), Action::discard(Tile(TM, 1)));
+}
+
+#[test]
+fn test_endpoint_key() {
+    use mahjong_core::control::rating::actor_key;
+
+    // レーティングのキーにtokenを含めない
+    let ep = create_actor("Endpoint(127.0.0.1:0,tcp,secret)");
+    assert_eq!(
+        actor_key(ep.as_ref()),
+        "Endpoint(addr=127.0.0.1:0,protocol=tcp)"
+    );
+}
@@ -17,7 +17,7 @@ impl ActorBuilder for GuiBuilder {
     fn get_default_config(&self) -> Config {
         Config {
             name: "Gui".into(),
-            args: vec![Arg::bool("conceal", true)],
+            args: vec![],
         }
     }
 
@@ -36,7 +36,6 @@ struct Shared {
 #[derive(Debug)]
 pub struct Gui {
     config: Config,
-    conceal: bool,
     messages: MessageHolder,
     tx: Sender<ServerMessage>,
     shared: Arc<Mutex<Shared>>,
@@ -47,7 +46,6 @@ impl Gui {
     pub fn from_config(config: Config) -> Self {
         let (tx, client_rx) = channel();
         let (client_tx, rx) = channel();
-        let conceal = config.args[0].value.as_bool();
 
         let shared = Arc::new(Mutex::new(Shared {
             possible_actions: None,
@@ -71,14 +69,14 @@ impl Gui {
                             }
                         }
                     }
+                    ClientMessage::Join { .. } => {}
                 }
             }
         });
 
         Self {
             config,
-            conceal,
-            messages: MessageHolder::new(NO_SEAT, conceal),
+            messages: MessageHolder::new(NO_SEAT),
             tx,
             shared,
             client_txrx: Some((client_tx, client_rx)),
@@ -110,7 +108,7 @@ impl Clone for Gui {
 
 impl Actor for Gui {
     fn init(&mut self, _stage: StageRef, seat: Seat) {
-        self.messages = MessageHolder::new(seat, self.conceal);
+        self.messages = MessageHolder::new(seat);
     }
 
     fn select(&mut self, acts: &[Action], tenpais: &[Tenpai]) -> ActionFuture {
@@ -167,46 +165,26 @@ impl Future for SelectFuture {
 #[derive(Debug)]
 struct MessageHolder {
     seat: Seat,
-    conceal: bool,
     messages: Vec<ServerMessage>,
     cursor: usize,
     act_id: u32,
 }
 
 impl MessageHolder {
-    fn new(seat: Seat, conceal: bool) -> Self {
+    fn new(seat: Seat) -> Self {
         Self {
             seat,
-            conceal,
             messages: vec![],
             cursor: 0,
             act_id: 0,
         }
     }
 
-    fn push_event(&mut self, mut event: Event) {
-        match &mut event {
-            Event::New(ev) => {
-                self.messages = vec![ServerMessage::Info { seat: self.seat }];
-                if self.conceal {
-                    for s in 0..SEAT {
-                        if s != self.seat {
-                            ev.hands[s].fill(Z8);
-                        }
-                    }
-                    ev.wall = vec![];
-                    ev.dora_wall = vec![];
-                    ev.ura_dora_wall = vec![];
-                    ev.replacement_wall = vec![];
-                }
-            }
-            Event::Deal(ev) => {
-                if self.conceal && self.seat != ev.seat {
-                    ev.tile = Z8;
-                }
-            }
-            _ => {}
-        };
+    // 他家の手牌や牌山はStageController側で秘匿済み (-omniscientで指定した座席の場合は秘匿しない)
+    fn push_event(&mut self, event: Event) {
+        if let Event::New(_) = event {
+            self.messages = vec![ServerMessage::Info { seat: self.seat }];
+        }
         self.messages.push(ServerMessage::Event(Box::new(event)));
     }
 
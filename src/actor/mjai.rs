@@ -6,7 +6,6 @@ use std::{
 };
 
 use mahjong_core::{
-    control::common::get_scores,
     convert::mjai::*,
     error, info,
     serde_json::{self, Value, json},
@@ -23,7 +22,7 @@ struct SharedData {
     cursor: usize,
     selected_action: Option<MjaiAction>,
     is_riichi: bool,
-    round: usize, // (= EventNew.rule.round)
+    rule: Rule, // (= EventNew.rule)
 }
 
 pub struct MjaiEndpointBuilder;
@@ -49,7 +48,7 @@ pub struct MjaiEndpoint {
     stage: StageRef,
     seat: Seat,
     data: Arc<Mutex<SharedData>>,
-    try_riichi: Option<Seat>,
+    serializer: MjaiSerializer,
     is_new_game: bool,
     timeout: i32, // selected_action の最大待機時間(秒)
     timeout_count: i32,
@@ -66,7 +65,7 @@ impl MjaiEndpoint {
             stage: StageRef::default(),
             seat: NO_SEAT,
             data: data.clone(),
-            try_riichi: None,
+            serializer: MjaiSerializer::new(NO_SEAT),
             is_new_game: false,
             timeout,
             timeout_count: 0,
@@ -113,18 +112,11 @@ impl MjaiEndpoint {
         d.record.push(serde_json::to_value(event).unwrap());
     }
 
-    fn confirm_riichi_accepted(&mut self, stg: &Stage) {
-        if let Some(s) = self.try_riichi {
-            self.try_riichi = None;
-            self.add_record(MjaiEvent::reach_accepted(s, &get_scores(stg)));
-        }
-    }
-
-    fn notify_begin(&mut self, _stg: &Stage, _event: &EventBegin) {
+    fn notify_begin(&mut self) {
         self.is_new_game = true;
     }
 
-    fn notify_new(&mut self, stg: &Stage, event: &EventNew) {
+    fn notify_new(&mut self, event: &EventNew) {
         assert!(self.seat != NO_SEAT);
 
         // 前の局のデータがすべて送信されていない場合は待機
@@ -142,104 +134,9 @@ impl MjaiEndpoint {
             self.is_new_game = false;
         }
         data.seat = self.seat;
-        data.round = event.rule.round;
+        data.rule = event.rule.clone();
         *self.data.lock().unwrap() = data;
-        self.try_riichi = None;
-
-        self.add_record(MjaiEvent::start_kyoku(
-            self.seat,
-            event.round,
-            event.dealer,
-            event.honba,
-            event.riichi_sticks,
-            &event.doras,
-            &event.hands,
-            &get_scores(stg),
-        ));
-    }
-
-    fn notify_deal(&mut self, stg: &Stage, event: &EventDeal) {
-        self.confirm_riichi_accepted(stg);
-        self.add_record(MjaiEvent::tsumo(self.seat, event.seat, event.tile));
-    }
-
-    fn notify_discard(&mut self, _stg: &Stage, event: &EventDiscard) {
-        if event.is_riichi {
-            self.add_record(MjaiEvent::reach(event.seat));
-        }
-
-        self.add_record(MjaiEvent::dahai(event.seat, event.tile, event.is_drawn));
-
-        if event.is_riichi {
-            self.try_riichi = Some(event.seat);
-        }
-    }
-
-    fn notify_meld(&mut self, stg: &Stage, event: &EventMeld) {
-        self.confirm_riichi_accepted(stg);
-
-        self.add_record(match event.meld_type {
-            MeldType::Chi => {
-                let lt = stg.last_tile.unwrap();
-                MjaiEvent::chi(event.seat, &event.consumed, lt.2, lt.0)
-            }
-            MeldType::Pon => {
-                let lt = stg.last_tile.unwrap();
-                MjaiEvent::pon(event.seat, &event.consumed, lt.2, lt.0)
-            }
-            MeldType::Minkan => {
-                let lt = stg.last_tile.unwrap();
-                MjaiEvent::daiminkan(event.seat, &event.consumed, lt.2, lt.0)
-            }
-            MeldType::Ankan => MjaiEvent::ankan(event.seat, &event.consumed),
-            MeldType::Kakan => {
-                let c = event.consumed[0];
-                let t = c.to_normal();
-                let t0 = if t.is_suit() && t.1 == 5 && c.1 != 0 {
-                    Tile(t.0, 0)
-                } else {
-                    t
-                };
-                MjaiEvent::kakan(event.seat, &event.consumed, &[t, t, t0])
-            }
-        });
-    }
-
-    fn notify_nukidora(&mut self, _stg: &Stage, _event: &EventNukidora) {
-        panic!();
-    }
-
-    fn notify_dora(&mut self, _stg: &Stage, event: &EventDora) {
-        self.add_record(MjaiEvent::dora(event.tile));
-    }
-
-    fn notify_win(&mut self, stg: &Stage, event: &EventWin) {
-        for ctx in &event.contexts {
-            self.add_record(MjaiEvent::hora(
-                ctx.seat,
-                stg.turn,
-                ctx.winning_tile,
-                &event.ura_doras,
-                &ctx.score_context,
-                &ctx.delta_scores,
-                &get_scores(stg),
-            ));
-        }
-        self.add_record(MjaiEvent::end_kyoku());
-    }
-
-    fn notify_draw(&mut self, stg: &Stage, event: &EventDraw) {
-        self.add_record(MjaiEvent::ryukyoku(
-            event.draw_type,
-            &[false; SEAT],
-            &[0; SEAT],
-            &get_scores(stg),
-        ));
-        self.add_record(MjaiEvent::end_kyoku());
-    }
-
-    fn notify_end(&mut self, stg: &Stage, _event: &EventEnd) {
-        self.add_record(MjaiEvent::end_game(&get_scores(stg)));
+        self.serializer = MjaiSerializer::new(self.seat);
     }
 }
 
@@ -299,7 +196,8 @@ impl Actor for MjaiEndpoint {
             if let MjaiAction::Dahai { pai, .. } = mjai_act {
                 return ready(Action::riichi(tile_from_mjai(&pai)));
             } else {
-                panic!();
+                error!("reach must be followed by dahai");
+                return ready(Action::nop());
             }
         }
 
@@ -334,16 +232,12 @@ impl Actor for MjaiEndpoint {
 impl Listener for MjaiEndpoint {
     fn notify_event(&mut self, stg: &Stage, event: &Event) {
         match event {
-            Event::Begin(ev) => self.notify_begin(stg, ev),
-            Event::New(ev) => self.notify_new(stg, ev),
-            Event::Deal(ev) => self.notify_deal(stg, ev),
-            Event::Discard(ev) => self.notify_discard(stg, ev),
-            Event::Meld(ev) => self.notify_meld(stg, ev),
-            Event::Nukidora(ev) => self.notify_nukidora(stg, ev),
-            Event::Dora(ev) => self.notify_dora(stg, ev),
-            Event::Win(ev) => self.notify_win(stg, ev),
-            Event::Draw(ev) => self.notify_draw(stg, ev),
-            Event::End(ev) => self.notify_end(stg, ev),
+            Event::Begin(_) => self.notify_begin(),
+            Event::New(ev) => self.notify_new(ev),
+            _ => {}
+        }
+        for e in self.serializer.push_event(stg, event) {
+            self.add_record(e);
         }
     }
 }
@@ -393,7 +287,7 @@ fn stream_handler(
                 // start_game 新しい試合が始まった場合,またはクライアントの再接続時に送信
                 need_start_game = false;
                 d.send_start_game = false;
-                send(&json!(MjaiEvent::start_game(d.seat, d.round)))?;
+                send(&json!(MjaiEvent::start_game(d.seat, &d.rule)))?;
                 recv()?; // recv none
             }
         }
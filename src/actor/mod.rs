@@ -1,9 +1,11 @@
 // Actorトレイトを継承して打牌の判断を行う構造体のモジュール(AI,プレイヤー,botなど)
-mod endpoint;
+mod efficiency;
+pub mod endpoint;
 mod manual;
 mod mjai;
 mod nop;
 mod null;
+mod process;
 mod random;
 mod tiitoitsu;
 
@@ -38,6 +40,8 @@ pub fn create_actor(exp: &str) -> Box<dyn Actor> {
         Box::new(manual::ManualBuilder),
         Box::new(mjai::MjaiEndpointBuilder),
         Box::new(tiitoitsu::TiitoitsuBotBuilder),
+        Box::new(efficiency::EfficiencyBotBuilder),
+        Box::new(process::ProcessBuilder),
         #[cfg(feature = "gui")]
         Box::new(gui::GuiBuilder),
     ];
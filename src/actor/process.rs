@@ -0,0 +1,579 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use mahjong_core::{
+    convert::mjai::*,
+    error,
+    serde_json::{self, Value, json},
+};
+
+use super::*;
+
+pub struct ProcessBuilder;
+
+impl ActorBuilder for ProcessBuilder {
+    fn get_default_config(&self) -> Config {
+        Config {
+            name: "Process".into(),
+            args: vec![Arg::string("cmd", ""), Arg::string("protocol", "native")],
+        }
+    }
+
+    fn create(&self, config: Config) -> Box<dyn Actor> {
+        Box::new(Process::from_config(config))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Native, // ServerMessage/ClientMessage
+    Mjai,
+}
+
+#[derive(Debug, Default)]
+struct SharedData {
+    stdin: Option<ChildStdin>,
+    n_sent: usize,            // 送信したメッセージの数 (mjai: 応答の対応付け用)
+    n_recv: usize,            // 受信したメッセージの数
+    selecting: Option<usize>, // 応答を待っているアクション (native: id, mjai: 応答のindex)
+    is_turn: bool,            // mjai: ツモ番のアクションかどうか
+    is_riichi: bool,          // mjai: reachに続くdahaiの待機中
+    skip_riichi: bool,        // mjai: 送信済みのreachとdahaiを再送しない
+    action: Option<Action>,
+    waker: Option<Waker>,
+    is_expired: bool,
+    is_closed: bool,
+}
+
+impl SharedData {
+    fn send(&mut self, value: &Value) {
+        let Some(stdin) = &mut self.stdin else {
+            return;
+        };
+        if let Err(err) = writeln!(stdin, "{}", value).and_then(|_| stdin.flush()) {
+            error!("failed to write to process: {}", err);
+            self.stdin = None;
+        }
+        self.n_sent += 1;
+    }
+
+    fn set_action(&mut self, act: Action) {
+        self.selecting = None;
+        self.action = Some(act);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+// 子プロセスを起動して標準入出力(1行に1つのJSON)で通信するActor
+// 複製されたインスタンスはそれぞれ別の子プロセスを起動する (子プロセスの起動はinitの初回呼び出し時)
+pub struct Process {
+    config: Config,
+    cmd: String,
+    protocol: Protocol,
+    stage: StageRef,
+    seat: Seat,
+    child: Option<Child>,
+    shared: Arc<Mutex<SharedData>>,
+    act_id: usize,              // native: PossibleActionsのid
+    serializer: MjaiSerializer, // mjai
+    pending: Option<MjaiEvent>, // mjai: possible_actionsを付加する可能性があるため未送信のイベント
+    is_new_game: bool,          // mjai: start_gameの送信が必要
+}
+
+impl Process {
+    pub fn from_config(config: Config) -> Self {
+        let args = &config.args;
+        let cmd = args[0].value.as_string();
+        let protocol = match args[1].value.as_string().as_str() {
+            "native" => Protocol::Native,
+            "mjai" => Protocol::Mjai,
+            p => {
+                error!("unknown protocol: {}", p);
+                std::process::exit(0);
+            }
+        };
+        if cmd.trim().is_empty() {
+            error!("cmd is not specified");
+            std::process::exit(0);
+        }
+
+        Self {
+            config,
+            cmd,
+            protocol,
+            stage: StageRef::default(),
+            seat: NO_SEAT,
+            child: None,
+            shared: Arc::new(Mutex::new(SharedData::default())),
+            act_id: 0,
+            serializer: MjaiSerializer::new(NO_SEAT),
+            pending: None,
+            is_new_game: false,
+        }
+    }
+
+    fn spawn(&mut self) {
+        let mut it = self.cmd.split_whitespace();
+        let program = it.next().unwrap();
+        let mut child = match Command::new(program)
+            .args(it)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                error!("failed to spawn process '{}': {}", self.cmd, err);
+                std::process::exit(1);
+            }
+        };
+
+        let stdout = child.stdout.take().unwrap();
+        *self.shared.lock().unwrap() = SharedData {
+            stdin: child.stdin.take(),
+            ..Default::default()
+        };
+        let shared2 = self.shared.clone();
+        let protocol = self.protocol;
+        let seat = self.seat;
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let mut d = shared2.lock().unwrap();
+                let res = match protocol {
+                    Protocol::Native => recv_native(&mut d, &line),
+                    Protocol::Mjai => recv_mjai(&mut d, &line, seat),
+                };
+                d.n_recv += 1;
+                if let Err(err) = res {
+                    error!("{}: {}", err, line);
+                }
+            }
+            // 子プロセスが終了した場合,以降のアクションはすべてNop
+            let mut d = shared2.lock().unwrap();
+            if d.stdin.is_some() {
+                error!("process closed unexpectedly: seat {}", seat);
+            }
+            d.is_closed = true;
+            d.selecting = None;
+            if let Some(waker) = d.waker.take() {
+                waker.wake();
+            }
+        });
+
+        self.child = Some(child);
+        if self.protocol == Protocol::Mjai {
+            self.send(&json!(MjaiEvent::hello()));
+        }
+    }
+
+    fn send(&mut self, value: &Value) {
+        self.shared.lock().unwrap().send(value);
+    }
+
+    fn send_native(&mut self, msg: &ServerMessage) {
+        self.send(&json!(msg));
+    }
+
+    fn push_mjai_event(&mut self, event: MjaiEvent) {
+        if let Some(ev) = self.pending.take() {
+            self.send(&json!(ev));
+        }
+        self.pending = Some(event);
+    }
+
+    fn flush_mjai_event(&mut self) {
+        if let Some(ev) = self.pending.take() {
+            self.send(&json!(ev));
+        }
+    }
+
+    fn notify_mjai_event(&mut self, stg: &Stage, event: &Event) {
+        match event {
+            Event::Begin(_) => self.is_new_game = true,
+            Event::New(ev) => {
+                self.serializer = MjaiSerializer::new(self.seat);
+                self.shared.lock().unwrap().skip_riichi = false;
+                if self.is_new_game {
+                    self.is_new_game = false;
+                    self.push_mjai_event(MjaiEvent::start_game(self.seat, &ev.rule));
+                }
+            }
+            _ => {}
+        }
+
+        for ev in self.serializer.push_event(stg, event) {
+            // reachとdahaiはアクションの選択時に送信済み
+            if let MjaiEvent::Reach { actor } | MjaiEvent::Dahai { actor, .. } = ev
+                && actor == self.seat
+            {
+                let mut d = self.shared.lock().unwrap();
+                if d.skip_riichi {
+                    d.skip_riichi = matches!(ev, MjaiEvent::Reach { .. });
+                    continue;
+                }
+            }
+            self.push_mjai_event(ev);
+        }
+
+        if let Event::End(_) = event {
+            self.flush_mjai_event();
+        }
+    }
+
+    fn select_mjai(&mut self, acts: &[Action]) {
+        let stg = self.stage.lock().unwrap();
+        let mjai_acts: Vec<MjaiAction> = acts
+            .iter()
+            .filter_map(|a| MjaiAction::from_action(&stg, self.seat, a))
+            .collect();
+        let is_turn = stg.turn == self.seat;
+        drop(stg);
+
+        let mut val = match self.pending.take() {
+            Some(ev) => json!(ev),
+            None => json!({"type": "none"}),
+        };
+        val["possible_actions"] = json!(mjai_acts);
+
+        let mut d = self.shared.lock().unwrap();
+        d.selecting = Some(d.n_sent);
+        d.is_turn = is_turn;
+        d.send(&val);
+    }
+}
+
+impl Clone for Process {
+    fn clone(&self) -> Self {
+        Self::from_config(self.config.clone())
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        self.shared.lock().unwrap().stdin = None; // stdinを閉じて終了を通知
+        if let Some(child) = &mut self.child {
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
+
+impl Actor for Process {
+    fn init(&mut self, stage: StageRef, seat: Seat) {
+        self.stage = stage;
+        self.seat = seat;
+        if self.child.is_none() {
+            self.spawn();
+        }
+    }
+
+    fn select(&mut self, acts: &[Action], tenpais: &[Tenpai]) -> ActionFuture {
+        {
+            let mut d = self.shared.lock().unwrap();
+            d.action = None;
+            d.is_expired = false;
+            d.is_riichi = false;
+        }
+
+        match self.protocol {
+            Protocol::Native => {
+                self.act_id += 1;
+                self.shared.lock().unwrap().selecting = Some(self.act_id);
+                self.send_native(&ServerMessage::Action(PossibleActions {
+                    id: self.act_id as u32,
+                    actions: acts.to_vec(),
+                    tenpais: tenpais.to_vec(),
+                }));
+            }
+            Protocol::Mjai => self.select_mjai(acts),
+        }
+
+        Box::pin(SelectFuture {
+            shared: self.shared.clone(),
+        })
+    }
+
+    fn expire(&mut self) {
+        let mut d = self.shared.lock().unwrap();
+        d.is_expired = true;
+        d.selecting = None;
+        if let Some(waker) = d.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn reject(&mut self, error: &ActionError) {
+        if self.protocol == Protocol::Native {
+            self.send_native(&ServerMessage::Error(error.clone()));
+        }
+    }
+
+    fn get_config(&self) -> &Config {
+        &self.config
+    }
+}
+
+impl Listener for Process {
+    fn notify_event(&mut self, stg: &Stage, event: &Event) {
+        match self.protocol {
+            Protocol::Native => {
+                if let Event::New(_) = event {
+                    self.send_native(&ServerMessage::Info { seat: self.seat });
+                }
+                self.send_native(&ServerMessage::Event(Box::new(event.clone())));
+            }
+            Protocol::Mjai => self.notify_mjai_event(stg, event),
+        }
+    }
+}
+
+fn recv_native(d: &mut SharedData, line: &str) -> Res {
+    let ClientMessage::Action(act) = serde_json::from_str(line)? else {
+        return Ok(());
+    };
+    if d.selecting == Some(act.id as usize) {
+        d.set_action(act.action);
+    }
+    Ok(())
+}
+
+// mjaiのクライアントは送信したすべてのイベントに対して1行の応答を返す
+// possible_actionsを付加したイベントに対する応答のみを処理
+fn recv_mjai(d: &mut SharedData, line: &str, seat: Seat) -> Res {
+    if d.selecting != Some(d.n_recv) {
+        return Ok(());
+    }
+
+    let act: MjaiAction = serde_json::from_str(line)?;
+    if d.is_riichi {
+        // reachに続くdahai
+        let MjaiAction::Dahai { pai, tsumogiri, .. } = &act else {
+            d.set_action(Action::nop());
+            return Err("reach must be followed by dahai".into());
+        };
+        let act2 = if *tsumogiri {
+            Action::riichi_drawn()
+        } else {
+            Action::riichi(tile_from_mjai(pai))
+        };
+        d.send(&json!(act)); // dahaiを返送
+        d.skip_riichi = true;
+        d.set_action(act2);
+        return Ok(());
+    }
+
+    match act {
+        MjaiAction::Reach { .. } => {
+            // reachを返送してdahaiを待機
+            d.is_riichi = true;
+            d.selecting = Some(d.n_sent);
+            d.send(&json!(MjaiEvent::reach(seat)));
+        }
+        MjaiAction::Join { .. } => d.set_action(Action::nop()),
+        _ => {
+            let is_turn = d.is_turn;
+            d.set_action(act.to_action(is_turn));
+        }
+    }
+    Ok(())
+}
+
+struct SelectFuture {
+    shared: Arc<Mutex<SharedData>>,
+}
+
+impl Future for SelectFuture {
+    type Output = Action;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut d = self.shared.lock().unwrap();
+        if let Some(act) = d.action.take() {
+            return Poll::Ready(act);
+        }
+        if d.is_expired || d.is_closed {
+            return Poll::Ready(Action::nop());
+        }
+        d.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+fn create_test_process(name: &str, script: &str, protocol: &str) -> (Process, std::path::PathBuf) {
+    // 1行受信するごとに応答するシェルスクリプトを子プロセスとして使用 (受信した行はlogに記録)
+    let dir = std::env::temp_dir().join(format!("process_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let log = dir.join("log");
+    let script = format!(
+        "while read -r line; do\necho \"$line\" >> {}\n{}\ndone\n",
+        log.display(),
+        script
+    );
+    std::fs::write(dir.join("bot.sh"), script).unwrap();
+    let config = Config {
+        name: "Process".into(),
+        args: vec![
+            Arg::string("cmd", &format!("sh {}", dir.join("bot.sh").display())),
+            Arg::string("protocol", protocol),
+        ],
+    };
+    (Process::from_config(config), dir)
+}
+
+#[test]
+fn test_process_native() {
+    use mahjong_core::control::testing::wait_action;
+
+    // PossibleActionsの先頭のアクションをidと一緒に返すbot
+    let script = r#"case "$line" in
+*'"type":"Action"}')
+  id=${line#*'"id":'}; id=${id%%,*}
+  act=$(echo "$line" | sed 's/.*"actions":\[\({[^}]*}\).*/\1/')
+  echo "{\"type\":\"Action\",\"id\":$id,\"action\":$act}";;
+esac"#;
+    let (mut process, dir) = create_test_process("native", script, "native");
+    let shared = process.shared.clone();
+    process.init(StageRef::default(), 0);
+    process.notify_event(&Stage::default(), &Event::begin());
+
+    let discard = Action::discard(Tile(TM, 1));
+    let act = wait_action(process.select(&[discard.clone(), Action::nop()], &[]));
+    assert_eq!(act, discard);
+    let act = wait_action(process.select(&[Action::nop()], &[]));
+    assert_eq!(act, Action::nop());
+
+    let d = shared.lock().unwrap();
+    assert_eq!((d.n_sent, d.n_recv), (3, 2)); // Event, Action, Action
+    drop(d);
+    drop(process);
+    let log = std::fs::read_to_string(dir.join("log")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(log.lines().count(), 3);
+    assert!(log.lines().nth(2).unwrap().contains(r#""id":2"#));
+}
+
+#[test]
+fn test_process_mjai() {
+    use mahjong_core::control::{stage_controller::StageController, testing::wait_action};
+
+    // ツモに対してreachを返し,返送されたreachに対してツモ切りのdahaiを返すbot (それ以外はnone)
+    let script = r#"case "$line" in
+*possible_actions*) echo '{"type":"reach","actor":0}';;
+*'"type":"reach"'*) echo '{"type":"dahai","actor":0,"pai":"9s","tsumogiri":true}';;
+*) echo '{"type":"none"}';;
+esac"#;
+    let (process, dir) = create_test_process("mjai", script, "mjai");
+    let shared = process.shared.clone();
+    let nop = crate::actor::create_actor("Nop");
+    let actors: [Box<dyn Actor>; SEAT] = [
+        Box::new(process),
+        nop.clone_box(),
+        nop.clone_box(),
+        nop.clone_box(),
+    ];
+    let mut ctrl = StageController::new(actors, vec![], [false; SEAT]);
+
+    // 5p単騎の聴牌
+    let mut hands: [Vec<Tile>; SEAT] = Default::default();
+    for n in 1..=9 {
+        hands[0].push(Tile(TM, n));
+    }
+    hands[0].extend([Tile(TP, 1), Tile(TP, 2), Tile(TP, 3), Tile(TP, 5)]);
+    for s in 1..3 {
+        hands[s] = (1..=8).map(|n| Tile(TS, n)).collect();
+    }
+    hands[1].extend([
+        Tile(TZ, 1),
+        Tile(TZ, 1),
+        Tile(TZ, 1),
+        Tile(TZ, 2),
+        Tile(TZ, 2),
+    ]);
+    hands[2].extend([
+        Tile(TZ, 3),
+        Tile(TZ, 3),
+        Tile(TZ, 3),
+        Tile(TZ, 4),
+        Tile(TZ, 4),
+    ]);
+    hands[3] = [4, 6, 7, 8, 9].map(|n| Tile(TP, n)).to_vec();
+    hands[3].extend([5, 5, 5, 6, 6, 6, 7, 7].map(|n| Tile(TZ, n)));
+    let rule = Rule {
+        initial_score: 25000,
+        settlement_score: 30000,
+        ..Default::default()
+    };
+    ctrl.handle_event(&Event::begin());
+    ctrl.handle_event(&Event::new(
+        rule,
+        0,
+        0,
+        0,
+        0,
+        vec![Tile(TZ, 2)],
+        Default::default(),
+        [25000; SEAT],
+        hands,
+        70,
+        2,
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+    ));
+    let t = Tile(TS, 9);
+    ctrl.handle_event(&Event::deal(0, t, false));
+    let act = wait_action(ctrl.query_action(0, &[Action::nop(), Action::riichi(t)], &[]));
+    assert_eq!(act, Action::riichi_drawn());
+    assert!(shared.lock().unwrap().skip_riichi);
+
+    // 送信済みのreachとdahaiは再送しない
+    ctrl.handle_event(&Event::discard(0, t, true, true));
+    ctrl.handle_event(&Event::deal(1, Tile(TZ, 3), false));
+    ctrl.handle_event(&Event::end());
+    for _ in 0..500 {
+        let d = shared.lock().unwrap();
+        if d.n_recv == d.n_sent {
+            break;
+        }
+        drop(d);
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let d = shared.lock().unwrap();
+    assert!(!d.skip_riichi);
+    assert_eq!(d.n_recv, d.n_sent);
+    let n_sent = d.n_sent;
+    drop(d);
+    drop(ctrl);
+
+    let log = std::fs::read_to_string(dir.join("log")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), n_sent);
+    let types: Vec<&str> = lines
+        .iter()
+        .map(|l| {
+            l.split(r#""type":""#)
+                .nth(1)
+                .unwrap()
+                .split('"')
+                .next()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(
+        &types[..5],
+        &["hello", "start_game", "start_kyoku", "tsumo", "reach"]
+    );
+    assert_eq!(types[5], "dahai");
+    assert!(lines[5].contains(r#""tsumogiri":true"#));
+    assert_eq!(types.iter().filter(|&&t| t == "reach").count(), 1);
+    assert_eq!(types.iter().filter(|&&t| t == "dahai").count(), 1);
+    assert!(types.contains(&"reach_accepted"));
+}
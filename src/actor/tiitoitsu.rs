@@ -99,10 +99,8 @@ pub fn count_left_tile(stg: &Stage, seat: Seat, tile: Tile) -> usize {
             U => {
                 n += 1;
             }
-            H(s) => {
-                if s != seat {
-                    n += 1;
-                }
+            H(s) if s != seat => {
+                n += 1;
             }
             _ => {}
         }
@@ -224,12 +224,14 @@ impl Calculator {
             &self.melds,
             &self.doras,
             &self.ura_doras,
+            0,
             self.winning_tile,
             self.is_drawn,
             self.is_dealer,
             self.prevalent_wind,
             self.seat_wind,
             &self.yaku_flags,
+            &Rule::default(),
         ) else {
             return if is_normal_win(&self.hand) {
                 println!("{indent}和了牌: {} (役無し)", self.winning_tile);
@@ -348,7 +350,15 @@ impl Calculator {
         let tiles = calc_tiles_to_win(&self.hand);
 
         if tiles.is_empty() {
-            println!("聴牌形ではありません");
+            // 向聴数と有効牌を表示
+            let visible = self.visible_tiles();
+            let effective_tiles = calc_effective_tiles(&self.hand);
+            println!(
+                "{}向聴 有効牌: {} ({}枚)",
+                calc_shanten(&self.hand),
+                tiles_to_string(&effective_tiles),
+                count_live_tiles(&effective_tiles, &visible),
+            );
             return None;
         }
 
@@ -374,8 +384,7 @@ impl Calculator {
         let discards = calc_discards_to_win(&self.hand);
 
         if discards.is_empty() {
-            println!("一向聴以上の手牌です");
-            return None;
+            return self.calculate_ukeire();
         }
 
         for (discard, _) in discards {
@@ -388,6 +397,35 @@ impl Calculator {
         None
     }
 
+    fn calculate_ukeire(&self) -> Option<Verify> {
+        // 一向聴以上の手牌 -> 打牌候補ごとの向聴数と有効牌
+        for u in calc_ukeire(&self.hand, &self.visible_tiles()) {
+            println!(
+                "打牌: {} => {}向聴 有効牌: {} ({}枚)",
+                u.discard,
+                u.shanten,
+                tiles_to_string(&u.tiles),
+                u.n_live,
+            );
+        }
+
+        None
+    }
+
+    // 見えている牌 (手牌,副露,ドラ表示牌)
+    fn visible_tiles(&self) -> TileTable {
+        let mut visible = self.hand;
+        for m in &self.melds {
+            for &t in &m.tiles {
+                inc_tile(&mut visible, t);
+            }
+        }
+        for &d in &self.doras {
+            inc_tile(&mut visible, d);
+        }
+        visible
+    }
+
     fn parse_stage_info(&mut self, input: &str) -> Res {
         let exps: Vec<&str> = input.split(',').collect();
         let len = exps.len();
@@ -477,6 +515,11 @@ impl Calculator {
     }
 }
 
+fn tiles_to_string(tiles: &[Tile]) -> String {
+    let tiles: Vec<String> = tiles.iter().map(|t| t.to_string()).collect();
+    tiles.join(", ")
+}
+
 fn print_usage() {
     error!(
         r"invalid input
@@ -495,7 +538,7 @@ Options
 fn test_calculator() {
     let file = File::open("tests/win_hands.txt").unwrap();
     let lines = io::BufReader::new(file).lines();
-    for exp in lines.flatten() {
+    for exp in lines.map_while(Result::ok) {
         let exp2 = exp.replace(' ', "");
         if exp2.is_empty() || exp2.starts_with('#') {
             // 空行とコメント行はスキップ
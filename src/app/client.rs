@@ -1,25 +1,222 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use mahjong_core::{
+    error, info,
+    model::*,
+    serde_json,
+    util::{
+        connection::{Message, create_client_connection},
+        misc::*,
+    },
+};
+
+// [App]
 #[derive(Debug)]
 pub struct ClientApp {
-    args: Vec<String>,
+    addr: String,
+    protocol: String,
+    token: String,
+    name: String,
 }
 
 impl ClientApp {
     pub fn new(args: Vec<String>) -> Self {
         #[cfg(not(feature = "gui"))]
         {
-            mahjong_core::error!("`gui` feature is required at compile time");
+            error!("`gui` feature is required at compile time");
             std::process::exit(1);
         }
-        Self { args }
+
+        let mut app = Self {
+            addr: "127.0.0.1:52010".into(),
+            protocol: "tcp".into(),
+            token: "".into(),
+            name: "".into(),
+        };
+
+        let mut it = args.iter();
+        while let Some(s) = it.next() {
+            match s.as_str() {
+                "-ws" => app.protocol = "ws".into(),
+                "-token" => app.token = next_value(&mut it, s),
+                "-name" => app.name = next_value(&mut it, s),
+                opt if !opt.starts_with('-') => app.addr = opt.into(),
+                opt => {
+                    error!("unknown option: {}", opt);
+                    std::process::exit(0);
+                }
+            }
+        }
+
+        app
     }
 
     pub fn run(&mut self) {
-        assert!(self.args.is_empty());
+        let (tx, rx) = connect(&self.protocol, &self.addr, &self.token, &self.name);
         #[cfg(feature = "gui")]
-        {
-            let (_, rx) = std::sync::mpsc::channel(); // TODO
-            let (tx, _) = std::sync::mpsc::channel(); // TODO
-            mahjong_gui::run(tx, rx);
+        mahjong_gui::run(tx, rx);
+    }
+}
+
+// サーバ側のEndpointに接続してServerMessageとClientMessageをGuiのチャネルに中継
+// 切断された場合は再接続してJoinを再送 (Endpointが現在の局の最初からメッセージを再送)
+// ロビーサーバの場合はSessionで受信したsecretをJoinに付けて対局中の座席に復帰
+fn connect(
+    protocol: &str,
+    addr: &str,
+    token: &str,
+    name: &str,
+) -> (Sender<ClientMessage>, Receiver<ServerMessage>) {
+    let mut conn = match create_client_connection(protocol, addr) {
+        Ok(conn) => conn,
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let (token, name, addr) = (token.to_string(), name.to_string(), addr.to_string());
+    let join = move |secret: &str| {
+        serde_json::to_string(&ClientMessage::Join {
+            token: token.clone(),
+            name: name.clone(),
+            secret: secret.into(),
+        })
+        .unwrap()
+    };
+
+    let (client_tx, client_rx) = mpsc::channel::<ClientMessage>(); // upstream
+    let (server_tx, server_rx) = mpsc::channel(); // downstream
+    thread::spawn(move || {
+        let mut is_connected = false;
+        let mut secret = String::new(); // ロビーサーバから受信した再接続用のsecret
+        loop {
+            // 未接続の間に選択されたアクションは破棄 (再接続後にActionが再送される)
+            for msg in client_rx.try_iter() {
+                if is_connected {
+                    conn.send(&serde_json::to_string(&msg).unwrap());
+                }
+            }
+
+            loop {
+                match conn.recv() {
+                    Message::Open => {
+                        is_connected = true;
+                        conn.send(&join(&secret));
+                    }
+                    Message::Text(text) => match serde_json::from_str::<ServerMessage>(&text) {
+                        Ok(ServerMessage::Session { secret: s }) => secret = s,
+                        Ok(msg) => {
+                            if server_tx.send(msg).is_err() {
+                                return; // Guiが終了
+                            }
+                        }
+                        Err(err) => error!("{}: {}", err, text),
+                    },
+                    Message::Close => {
+                        is_connected = false;
+                        info!("reconnecting to {}", addr);
+                    }
+                    Message::Nop | Message::NoConnection => break,
+                }
+            }
+            sleep(0.01);
         }
+    });
+
+    (client_tx, server_rx)
+}
+
+#[test]
+fn test_connect_resync() {
+    use std::{
+        io::copy,
+        net::{Shutdown, TcpListener, TcpStream},
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use mahjong_core::{
+        control::{
+            actor::Actor,
+            listener::Listener,
+            stage_controller::StageRef,
+            testing::{config, wait_action},
+        },
+        util::connection::TcpConnection,
+    };
+
+    use crate::actor::endpoint::Endpoint;
+
+    let server = TcpConnection::new("127.0.0.1:0");
+    let ep_addr = server.local_addr().unwrap();
+    let mut ep = Endpoint::new(config("Endpoint"), Box::new(server), Some("secret".into()));
+
+    // クライアントとEndpointの間を中継するプロキシ (中継中のソケットを切断して通信断を再現)
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_addr = listener.local_addr().unwrap().to_string();
+    let sockets = Arc::new(Mutex::new(Vec::<TcpStream>::new()));
+    let sockets2 = sockets.clone();
+    thread::spawn(move || {
+        for down in listener.incoming().flatten() {
+            let up = TcpStream::connect(ep_addr).unwrap();
+            for (mut r, mut w) in [
+                (down.try_clone().unwrap(), up.try_clone().unwrap()),
+                (up.try_clone().unwrap(), down.try_clone().unwrap()),
+            ] {
+                thread::spawn(move || copy(&mut r, &mut w));
+            }
+            sockets2.lock().unwrap().extend([down, up]);
+        }
+    });
+
+    let (tx, rx) = connect("tcp", &proxy_addr, "secret", "");
+    let recv = || rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    let scores = [25000; SEAT];
+    let new = Event::new(
+        Rule::default(),
+        0,
+        0,
+        0,
+        0,
+        vec![],
+        Default::default(),
+        scores,
+        Default::default(),
+        0,
+        0,
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+    );
+    let stg = Stage::default();
+    ep.init(StageRef::default(), 0);
+    ep.notify_event(&stg, &new);
+    ep.notify_event(&stg, &Event::deal(0, Tile(TZ, 8), false));
+    let fut = ep.select(&[Action::nop()], &[]);
+
+    assert!(matches!(recv(), ServerMessage::Info { seat: 0 }));
+    assert!(matches!(recv(), ServerMessage::Event(ev) if matches!(*ev, Event::New(_))));
+    assert!(matches!(recv(), ServerMessage::Event(ev) if matches!(*ev, Event::Deal(_))));
+    assert!(matches!(recv(), ServerMessage::Action(a) if a.id == 1));
+
+    // 局の途中で切断 => 再接続後に現在の局のInfoとNewから再送され,応答待ちのActionも再送される
+    for s in sockets.lock().unwrap().drain(..) {
+        s.shutdown(Shutdown::Both).ok();
     }
+    assert!(matches!(recv(), ServerMessage::Info { seat: 0 }));
+    assert!(matches!(recv(), ServerMessage::Event(ev) if matches!(*ev, Event::New(_))));
+    assert!(matches!(recv(), ServerMessage::Event(ev) if matches!(*ev, Event::Deal(_))));
+    assert!(matches!(recv(), ServerMessage::Action(a) if a.id == 1));
+
+    let act = SelectedAction {
+        id: 1,
+        action: Action::nop(),
+    };
+    tx.send(ClientMessage::Action(act)).unwrap();
+    assert_eq!(wait_action(fut), Action::nop());
 }
@@ -1,11 +1,23 @@
-use std::sync::mpsc;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex, mpsc},
+};
 
 use mahjong_core::{
-    control::{actor::Actor, engine::MahjongEngine, listener::Listener},
+    control::{
+        actor::Actor,
+        common::calc_n_seat,
+        engine::MahjongEngine,
+        listener::Listener,
+        rating::{INITIAL_RATE, RatingStore, actor_key},
+        sprt::{Sprt, Verdict},
+        stats::Stats,
+    },
     error, info,
     model::*,
     rand::prelude::*,
-    util::misc::*,
+    serde_json::{self, json},
+    util::{connection::create_connection, misc::*},
 };
 
 use crate::{actor::*, listener::*};
@@ -18,12 +30,20 @@ pub struct EngineApp {
     pause: f64,
     n_game: u32,
     n_thread: u32,
+    n_dup: usize, // デュプリケート: 同じシードを座席を入れ替えて対局する回数 (0: 無効)
     view: bool,
     write: bool,
     write_tenhou: bool,
+    write_mjlog: bool,
     debug: bool,
     quiet: bool,
-    names: [String; SEAT], // actor names
+    event_sender: Option<(&'static str, String)>, // (protocol, addr)
+    rating: String,                               // レーティングの保存先
+    stats: Option<String>,                        // 統計の出力先 (.csv または .json)
+    sprt: Option<(&'static str, f64)>,            // 逐次検定 (指標, 検出する最小の差)
+    sprt_conf: f64,                               // 逐次検定の信頼度
+    names: [String; SEAT],                        // actor names
+    omniscient: [bool; SEAT], // 秘匿情報を含むStageとEventを渡す座席 (デバッグ用)
 }
 
 impl EngineApp {
@@ -37,39 +57,98 @@ impl EngineApp {
                 settlement_score: 30000,
                 red5: 1,
                 bust: true,
+                bust_score: 0,
+                time_base: 0.0,
+                time_extra: 0.0,
+                max_violations: 0,
+                kuitan: true,
+                kiriage: false,
+                atamahane: false,
+                sanchahou: false,
+                honba_per_winner: false,
+                double_yakuman: true,
+                composite_yakuman: true,
+                yakuman_cap: 0,
+                kazoe_yakuman: true,
             },
             pause: 0.0,
             n_game: 0,
             n_thread: 16,
+            n_dup: 0,
             view: false,
             write: false,
             write_tenhou: false,
+            write_mjlog: false,
             debug: false,
             quiet: false,
+            event_sender: None,
+            rating: "local/rating.json".into(),
+            stats: None,
+            sprt: None,
+            sprt_conf: 0.95,
             names: ["Nop".into(), "Nop".into(), "Nop".into(), "Nop".into()],
+            omniscient: [false; SEAT],
         };
 
+        // 持ち点と返し点は指定がない場合,4麻と3麻で異なる初期値を使用
+        let mut initial_score = None;
+        let mut settlement_score = None;
+
         let mut it = args.iter();
         while let Some(s) = it.next() {
             match s.as_str() {
                 "-s" => app.seed = next_value(&mut it, s),
                 "-r-round" => app.rule.round = next_value(&mut it, s),
-                "-r-init" => app.rule.initial_score = next_value(&mut it, s),
-                "-r-settle" => app.rule.settlement_score = next_value(&mut it, s),
+                "-r-sanma" => app.rule.is_sanma = next_value(&mut it, s),
+                "-r-init" => initial_score = Some(next_value(&mut it, s)),
+                "-r-settle" => settlement_score = Some(next_value(&mut it, s)),
                 "-r-red5" => app.rule.red5 = next_value(&mut it, s),
                 "-r-bust" => app.rule.bust = next_value(&mut it, s),
+                "-r-bust-score" => app.rule.bust_score = next_value(&mut it, s),
+                "-r-time-base" => app.rule.time_base = next_value(&mut it, s),
+                "-r-time-extra" => app.rule.time_extra = next_value(&mut it, s),
+                "-r-max-violations" => app.rule.max_violations = next_value(&mut it, s),
+                "-r-kuitan" => app.rule.kuitan = next_value(&mut it, s),
+                "-r-kiriage" => app.rule.kiriage = next_value(&mut it, s),
+                "-r-atamahane" => app.rule.atamahane = next_value(&mut it, s),
+                "-r-sanchahou" => app.rule.sanchahou = next_value(&mut it, s),
+                "-r-honba-per-winner" => app.rule.honba_per_winner = next_value(&mut it, s),
+                "-r-double-yakuman" => app.rule.double_yakuman = next_value(&mut it, s),
+                "-r-composite-yakuman" => app.rule.composite_yakuman = next_value(&mut it, s),
+                "-r-yakuman-cap" => app.rule.yakuman_cap = next_value(&mut it, s),
+                "-r-kazoe" => app.rule.kazoe_yakuman = next_value(&mut it, s),
                 "-p" => app.pause = next_value(&mut it, s),
                 "-g" => app.n_game = next_value(&mut it, s),
                 "-t" => app.n_thread = next_value(&mut it, s),
+                "-dup" => app.n_dup = next_value(&mut it, s),
+                "-rating" => app.rating = next_value(&mut it, s),
+                "-stats" => app.stats = Some(next_value(&mut it, s)),
+                "-sprt-rank" => app.sprt = Some(("rank", next_value(&mut it, s))),
+                "-sprt-score" => app.sprt = Some(("score", next_value(&mut it, s))),
+                "-sprt-conf" => app.sprt_conf = next_value(&mut it, s),
                 "-v" => app.view = true,
                 "-w" => app.write = true,
                 "-w-tenhou" => app.write_tenhou = true,
+                "-w-mjlog" => app.write_mjlog = true,
                 "-d" => app.debug = true,
                 "-q" => app.quiet = true,
+                "-e-tcp" => app.event_sender = Some(("tcp", next_value(&mut it, s))),
+                "-e-ws" => app.event_sender = Some(("ws", next_value(&mut it, s))),
                 "-0" => app.names[0] = next_value(&mut it, s),
                 "-1" => app.names[1] = next_value(&mut it, s),
                 "-2" => app.names[2] = next_value(&mut it, s),
                 "-3" => app.names[3] = next_value(&mut it, s),
+                "-omniscient" => {
+                    let seats: String = next_value(&mut it, s);
+                    for seat in seats.split(',') {
+                        match seat.parse::<Seat>() {
+                            Ok(seat) if seat < SEAT => app.omniscient[seat] = true,
+                            _ => {
+                                error_exit::<_, ()>(format!("-omniscient: invalid seat '{}'", seat))
+                            }
+                        }
+                    }
+                }
                 opt => {
                     error!("unknown option: {}", opt);
                     std::process::exit(0);
@@ -77,6 +156,32 @@ impl EngineApp {
             }
         }
 
+        let (init, settle) = if app.rule.is_sanma {
+            (35000, 40000)
+        } else {
+            (25000, 30000)
+        };
+        app.rule.initial_score = initial_score.unwrap_or(init);
+        app.rule.settlement_score = settlement_score.unwrap_or(settle);
+
+        // 逐次検定では-gが試合数の上限になる
+        if app.sprt.is_some() {
+            if app.n_game == 0 {
+                error_exit::<_, ()>("-sprt-rank and -sprt-score require -g");
+            }
+            if !(0.5 < app.sprt_conf && app.sprt_conf < 1.0) {
+                error_exit::<_, ()>(format!(
+                    "-sprt-conf: {} is out of (0.5, 1.0)",
+                    app.sprt_conf
+                ));
+            }
+        }
+
+        // 3麻の場合,座席3は空席
+        if app.rule.is_sanma {
+            app.names[3] = "Null".into();
+        }
+
         if app.seed == 0 {
             app.seed = unixtime_now() as u64;
             info!(
@@ -85,8 +190,6 @@ impl EngineApp {
             );
         }
 
-        assert!(app.rule.bust); // TODO: 飛びなしルール実装
-
         app
     }
 
@@ -127,9 +230,21 @@ impl EngineApp {
         if self.write_tenhou {
             listeners.push(Box::new(crate::listener::TenhouEventWriter::new()));
         }
+        if self.write_mjlog {
+            listeners.push(Box::new(crate::listener::MjlogEventWriter::new()));
+        }
         if self.debug {
             listeners.push(Box::new(Debug::new()));
         }
+        if let Some((protocol, addr)) = &self.event_sender {
+            match create_connection(protocol, addr) {
+                Ok(conn) => listeners.push(Box::new(EventSender::new(conn))),
+                Err(err) => {
+                    error!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
 
         #[cfg(feature = "gui")]
         {
@@ -163,6 +278,7 @@ impl EngineApp {
                         self.pause,
                         actors,
                         listeners,
+                        self.omniscient,
                     );
                     game.run();
                 });
@@ -177,29 +293,73 @@ impl EngineApp {
             std::process::exit(1);
         }
 
-        let mut game =
-            MahjongEngine::new(self.seed, self.rule.clone(), self.pause, actors, listeners);
+        let mut game = MahjongEngine::new(
+            self.seed,
+            self.rule.clone(),
+            self.pause,
+            actors,
+            listeners,
+            self.omniscient,
+        );
         game.run();
     }
 
     fn run_multiple_game(self, actors: [Box<dyn Actor>; 4]) {
-        use std::{thread, time};
+        use std::{panic, thread, time};
 
         let mut n_game = 0;
         let mut n_thread = 0;
         let mut n_game_end = 0;
+        let mut n_game_error = 0;
         let mut rng: StdRng = SeedableRng::seed_from_u64(self.seed);
         let (tx, rx) = mpsc::channel();
         let mut sum_delta_scores = [0; SEAT];
         let mut sum_ranks = [0; SEAT];
+        let mut sum_violations = [0; SEAT];
+        let n_seat = calc_n_seat(self.rule.is_sanma);
+        let mut ratings = RatingStore::load(&self.rating)
+            .unwrap_or_else(|err| error_exit(format!("{}: {}", self.rating, err)));
+        let keys: Vec<String> = actors.iter().map(|a| actor_key(a.as_ref())).collect();
+        let mut stats: [Stats; SEAT] = Default::default(); // actorごと
+
+        // デュプリケートの場合は各シードを全座席表でn_dup回ずつ対局
+        let dup_tables = if self.n_dup == 0 {
+            vec![]
+        } else {
+            get_dup_seat_tables(n_seat, self.n_dup).unwrap_or_else(|| {
+                error_exit(format!(
+                    "-dup: {} or {} is expected, but {}",
+                    n_seat,
+                    permutations(n_seat).len(),
+                    self.n_dup
+                ))
+            })
+        };
+        let n_dup = dup_tables.len().max(1);
+        let mut n_total = self.n_game * n_dup as u32;
+        let mut dup_seed = 0;
+        let mut dup_sets: HashMap<usize, DupSet> = HashMap::new(); // 集計中のシード
+        let mut dup_results: Vec<[f64; SEAT]> = vec![]; // シードごとの各actorの持ち点の増減の平均
+        let mut sprt = self.sprt.map(|(_, delta)| Sprt::new(delta, self.sprt_conf));
         loop {
-            if n_game < self.n_game && n_thread < self.n_thread {
+            if n_game < n_total && n_thread < self.n_thread {
+                let k = n_game as usize;
                 n_game += 1;
                 n_thread += 1;
 
-                let seed = rng.next_u64();
+                let seed;
                 let mut shuffle_table = [0, 1, 2, 3];
-                shuffle_table.shuffle(&mut rng);
+                if dup_tables.is_empty() {
+                    seed = rng.next_u64();
+                    shuffle_table[..n_seat].shuffle(&mut rng); // 3麻の場合,空席はシャッフルしない
+                } else {
+                    if k.is_multiple_of(n_dup) {
+                        dup_seed = rng.next_u64();
+                    }
+                    seed = dup_seed;
+                    shuffle_table = dup_tables[k % n_dup];
+                }
+                let set = k / n_dup;
                 let null = create_actor("Null");
                 let mut shuffled_actors: [Box<dyn Actor>; SEAT] = [
                     null.clone_box(),
@@ -216,25 +376,95 @@ impl EngineApp {
                 let tx2 = tx.clone();
                 thread::spawn(move || {
                     let start = time::Instant::now();
-                    let mut game = MahjongEngine::new(seed, rule, pause, shuffled_actors, vec![]);
-                    game.run();
-                    tx2.send((shuffle_table, game, start.elapsed())).unwrap();
+                    // 1試合の異常終了でベンチマーク全体が停止しないようにpanicを捕捉
+                    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                        let stats = Arc::new(Mutex::new(Default::default()));
+                        let listeners: Vec<Box<dyn Listener>> =
+                            vec![Box::new(StatsCollector::new(stats.clone()))];
+                        let mut game = MahjongEngine::new(
+                            seed,
+                            rule,
+                            pause,
+                            shuffled_actors,
+                            listeners,
+                            [false; SEAT],
+                        );
+                        game.run();
+                        let game_stats: [Stats; SEAT] = stats.lock().unwrap().clone();
+                        (game, game_stats)
+                    }));
+                    tx2.send((set, shuffle_table, seed, res.ok(), start.elapsed()))
+                        .unwrap();
                 });
             }
 
             loop {
-                if let Ok((shuffle, game, elapsed)) = rx.try_recv() {
+                if let Ok((set, shuffle, seed, game, elapsed)) = rx.try_recv() {
                     let ms = elapsed.as_nanos() / 1000000;
-                    print!("{:5},{:4}ms,{:20}", n_game_end, ms, game.get_seed());
-                    for s in 0..SEAT {
-                        let pl = &game.get_stage().players[s];
-                        let (score, rank) = (pl.score, pl.rank + 1);
-                        let i = shuffle[s];
-                        sum_delta_scores[i] += score - self.rule.initial_score;
-                        sum_ranks[i] += rank;
-                        print!(", ac{}:{:5}({})", i, score, rank);
+                    print!("{:5},{:4}ms,{:20}", n_game_end, ms, seed);
+                    let dup_set = dup_sets.entry(set).or_default();
+                    if let Some((game, game_stats)) = game {
+                        let violations = game.get_violations();
+                        let mut results = vec![];
+                        for s in 0..n_seat {
+                            let pl = &game.get_stage().players[s];
+                            let (score, rank) = (pl.score, pl.rank + 1);
+                            let i = shuffle[s];
+                            let delta_score = score - self.rule.initial_score;
+                            sum_delta_scores[i] += delta_score;
+                            sum_ranks[i] += rank;
+                            sum_violations[i] += violations[s];
+                            stats[i].merge(&game_stats[s]);
+                            results.push((keys[i].clone(), pl.rank, delta_score));
+                            dup_set.sum_delta_scores[i] += delta_score;
+                            dup_set.sum_ranks[i] += rank;
+                            print!(", ac{}:{:5}({})", i, score, rank);
+                        }
+                        println!();
+                        ratings.update(&results, self.rule.is_sanma);
+                        if let Err(err) = ratings.save() {
+                            error!("{}: {}", self.rating, err);
+                        }
+                    } else {
+                        println!(", aborted");
+                        n_game_error += 1;
+                        dup_set.is_valid = false;
+                    }
+
+                    // 異常終了した試合を含むシードは集計から除外
+                    dup_set.n_game += 1;
+                    if dup_set.n_game == n_dup {
+                        if dup_set.is_valid {
+                            let mut avg_ranks = [0.0; SEAT];
+                            let mut avg_deltas = [0.0; SEAT];
+                            for i in 0..n_seat {
+                                avg_ranks[i] = dup_set.sum_ranks[i] as f64 / n_dup as f64;
+                                avg_deltas[i] = dup_set.sum_delta_scores[i] as f64 / n_dup as f64;
+                            }
+                            if !dup_tables.is_empty() {
+                                dup_results.push(avg_deltas);
+                            }
+
+                            // 逐次検定の標本は終了したシードごとの平均 (デュプリケートでない場合は1試合)
+                            // 判定が出た場合は開始済みのシードの試合のみ実行し,新しいシードの試合は開始しない
+                            if let (Some(sprt), Some((metric, _))) = (&mut sprt, self.sprt)
+                                && sprt.verdict() == Verdict::Undecided
+                            {
+                                let others = |v: &[f64; SEAT]| {
+                                    v[1..n_seat].iter().sum::<f64>() / (n_seat - 1) as f64
+                                };
+                                sprt.push(if metric == "rank" {
+                                    others(&avg_ranks) - avg_ranks[0]
+                                } else {
+                                    avg_deltas[0] - others(&avg_deltas)
+                                });
+                                if sprt.verdict() != Verdict::Undecided {
+                                    n_total = n_game.next_multiple_of(n_dup as u32);
+                                }
+                            }
+                        }
+                        dup_sets.remove(&set);
                     }
-                    println!();
 
                     n_thread -= 1;
                     n_game_end += 1;
@@ -245,17 +475,279 @@ impl EngineApp {
                 sleep(0.01);
             }
 
-            if n_thread == 0 && n_game == self.n_game {
-                for i in 0..SEAT {
+            if n_thread == 0 && n_game == n_total {
+                // 異常終了した試合は集計から除外
+                let n_valid = (n_game - n_game_error).max(1);
+                for i in 0..n_seat {
                     println!(
-                        "ac{} avg_rank: {:.2}, avg_delta_score: {:6}",
+                        "ac{} avg_rank: {:.2}, avg_delta_score: {:6}, violations: {}, rate: {:.1}",
                         i,
-                        sum_ranks[i] as f32 / n_game as f32,
-                        sum_delta_scores[i] / n_game as i32,
+                        sum_ranks[i] as f32 / n_valid as f32,
+                        sum_delta_scores[i] / n_valid as i32,
+                        sum_violations[i],
+                        ratings.get(&keys[i]).map_or(INITIAL_RATE, |r| r.rate),
                     );
                 }
+                if n_game_error != 0 {
+                    println!("aborted games: {}", n_game_error);
+                }
+                print_stats_report(&stats, n_seat);
+                if let Some(path) = &self.stats
+                    && let Err(err) = write_stats(path, &stats, &keys, n_seat)
+                {
+                    error!("{}: {}", path, err);
+                }
+                if !dup_tables.is_empty() {
+                    print_dup_report(&dup_results, n_seat, n_dup);
+                }
+                if let (Some(sprt), Some((metric, _))) = (&sprt, self.sprt) {
+                    print_sprt_report(sprt, metric, self.sprt_conf, n_dup);
+                }
                 break;
             }
         }
     }
 }
+
+#[derive(Debug)]
+struct DupSet {
+    n_game: usize,
+    is_valid: bool,
+    sum_delta_scores: [Score; SEAT], // actorごと
+    sum_ranks: [usize; SEAT],
+}
+
+impl Default for DupSet {
+    fn default() -> Self {
+        Self {
+            n_game: 0,
+            is_valid: true,
+            sum_delta_scores: [0; SEAT],
+            sum_ranks: [0; SEAT],
+        }
+    }
+}
+
+// デュプリケートで使用する座席表 (table[seat] = actorのindex)
+// n_dup == n_seat: 巡回 (各actorが各座席に1回ずつ), n_dup == n_seat!: 全順列
+fn get_dup_seat_tables(n_seat: usize, n_dup: usize) -> Option<Vec<[usize; SEAT]>> {
+    let to_table = |p: &[usize]| {
+        let mut t = [0, 1, 2, 3];
+        t[..n_seat].copy_from_slice(p);
+        t
+    };
+    let perms = permutations(n_seat);
+    if n_dup == n_seat {
+        let rotations = (0..n_seat).map(|r| {
+            let p: Vec<usize> = (0..n_seat).map(|s| (s + r) % n_seat).collect();
+            to_table(&p)
+        });
+        Some(rotations.collect())
+    } else if n_dup == perms.len() {
+        Some(perms.iter().map(|p| to_table(p)).collect())
+    } else {
+        None
+    }
+}
+
+// シードごとに平均した持ち点の増減を対応のある標本として比較
+// シードが1つの場合は標準誤差,標準誤差が0の場合はt値を"-"で表示
+fn print_dup_report(results: &[[f64; SEAT]], n_seat: usize, n_dup: usize) {
+    let fmt_se = |se: f64| {
+        if se.is_finite() {
+            format!("{:6.1}", se)
+        } else {
+            format!("{:>6}", "-")
+        }
+    };
+    let fmt_t = |mean: f64, se: f64| {
+        if se.is_finite() && se > 0.0 {
+            format!("{:+.2}", mean / se)
+        } else {
+            "-".into()
+        }
+    };
+
+    println!();
+    println!("duplicate: {} seeds x {} games", results.len(), n_dup);
+    for i in 0..n_seat {
+        let v: Vec<f64> = results.iter().map(|r| r[i]).collect();
+        let (mean, se) = mean_and_se(&v);
+        println!("ac{} avg_delta_score: {:+8.1} ± {}", i, mean, fmt_se(se));
+    }
+    for i in 0..n_seat {
+        for j in i + 1..n_seat {
+            let diffs: Vec<f64> = results.iter().map(|r| r[i] - r[j]).collect();
+            let (mean, se) = mean_and_se(&diffs);
+            println!(
+                "ac{} - ac{}: {:+8.1} ± {} (t = {})",
+                i,
+                j,
+                mean,
+                fmt_se(se),
+                fmt_t(mean, se)
+            );
+        }
+    }
+}
+
+// 指標ごとに各actorの値と標準誤差を表示 (役はactorごとの和了数に対する割合)
+fn print_stats_report(stats: &[Stats; SEAT], n_seat: usize) {
+    let metrics: Vec<HashMap<String, (f64, f64)>> = stats[..n_seat]
+        .iter()
+        .map(|st| {
+            st.metrics(n_seat)
+                .into_iter()
+                .map(|(k, v, se)| (k, (v, se)))
+                .collect()
+        })
+        .collect();
+    let mut names: Vec<String> = Stats::default()
+        .metrics(n_seat)
+        .into_iter()
+        .map(|(k, _, _)| k)
+        .collect();
+    let yakus: BTreeSet<&String> = stats[..n_seat]
+        .iter()
+        .flat_map(|st| st.yaku_counts.keys())
+        .collect();
+    names.extend(yakus.into_iter().map(|y| format!("yaku:{}", y)));
+
+    println!();
+    print!("{:24}", "stats (± SE)");
+    for i in 0..n_seat {
+        print!("{:>20}", format!("ac{}", i));
+    }
+    println!();
+    for name in &names {
+        print!("{:24}", name);
+        for m in &metrics {
+            let cell = match m.get(name) {
+                Some((v, se)) if name.ends_with("_score") => format!("{:.0} ± {:.0}", v, se),
+                Some((v, se)) => format!("{:.3} ± {:.3}", v, se),
+                None => "-".into(),
+            };
+            print!("{:>20}", cell);
+        }
+        println!();
+    }
+}
+
+// 拡張子が.csvの場合は (actor, key, metric, value, se) の行, それ以外は集計値を含むJSONで出力
+fn write_stats(path: &str, stats: &[Stats; SEAT], keys: &[String], n_seat: usize) -> Res {
+    let data = if path.ends_with(".csv") {
+        let mut lines = vec!["actor,key,metric,value,se".to_string()];
+        for i in 0..n_seat {
+            for (name, v, se) in stats[i].metrics(n_seat) {
+                let key = keys[i].replace('"', "\"\"");
+                lines.push(format!("ac{},\"{}\",{},{},{}", i, key, name, v, se));
+            }
+        }
+        lines.join("\n") + "\n"
+    } else {
+        let actors: Vec<_> = (0..n_seat)
+            .map(|i| {
+                let metrics: serde_json::Map<_, _> = stats[i]
+                    .metrics(n_seat)
+                    .into_iter()
+                    .map(|(name, v, se)| (name, json!({"value": v, "se": se})))
+                    .collect();
+                json!({
+                    "actor": format!("ac{}", i),
+                    "key": keys[i],
+                    "stats": stats[i],
+                    "metrics": metrics,
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&actors)?
+    };
+    write_to_file(path, &data)
+}
+
+// ac0(候補)と他のactor(基準)の平均の差についての逐次検定の結果
+// デュプリケートの場合は標本の単位はシード値
+fn print_sprt_report(sprt: &Sprt, metric: &str, confidence: f64, n_dup: usize) {
+    let verdict = match sprt.verdict() {
+        Verdict::H1 => format!("ac0 is better than the others by >= {}", sprt.delta),
+        Verdict::H0 => format!("ac0 is not better than the others by {}", sprt.delta),
+        Verdict::Undecided => "undecided".into(),
+    };
+    println!();
+    println!(
+        "sprt ({}, delta: {}, confidence: {}): {}",
+        metric, sprt.delta, confidence, verdict
+    );
+    println!(
+        "    {}: {}, diff: {:+.3} ± {:.3}, llr: {:.3} [{:.3}, {:.3}]",
+        if n_dup > 1 { "seeds" } else { "games" },
+        sprt.len(),
+        sprt.mean(),
+        (sprt.variance() / sprt.len() as f64).sqrt(),
+        sprt.llr(),
+        sprt.lower,
+        sprt.upper,
+    );
+}
+
+// cargo test --release test_sanma_game
+#[test]
+fn test_sanma_game() {
+    let rule = Rule {
+        round: 2,
+        is_sanma: true,
+        initial_score: 35000,
+        settlement_score: 40000,
+        red5: 1,
+        bust: true,
+        bust_score: 0,
+        time_base: 0.0,
+        time_extra: 0.0,
+        ..Default::default()
+    };
+    for seed in 1..=20 {
+        let actors = [
+            create_actor("TiitoitsuBot"),
+            create_actor("RandomDiscard"),
+            create_actor("TiitoitsuBot"),
+            create_actor("Null"),
+        ];
+        let mut game = MahjongEngine::new(seed, rule.clone(), 0.0, actors, vec![], [false; SEAT]);
+        game.run();
+
+        // 3人の合計点は(流局時に残った供託を除いて)初期値の合計と一致し,空席は常に0点で最下位
+        let stg = game.get_stage();
+        let total: i32 = stg.players[..3].iter().map(|pl| pl.score).sum();
+        let sticks = stg.riichi_sticks as i32 * 1000;
+        assert!(total == 35000 * 3 || total + sticks == 35000 * 3);
+        assert_eq!(stg.players[3].score, 0);
+        assert_eq!(stg.players[3].rank, 3);
+    }
+}
+
+#[test]
+fn test_dup_seat_tables() {
+    // 各actorが各座席にちょうど同じ回数ずつ座る
+    let check = |n_seat: usize, n_dup: usize| {
+        let tables = get_dup_seat_tables(n_seat, n_dup).unwrap();
+        assert_eq!(tables.len(), n_dup);
+        for t in &tables {
+            let mut actors = t[..n_seat].to_vec();
+            actors.sort();
+            assert_eq!(actors, (0..n_seat).collect::<Vec<_>>());
+            assert_eq!(t[n_seat..], (n_seat..SEAT).collect::<Vec<_>>()[..]); // 空席は固定
+        }
+        for s in 0..n_seat {
+            for i in 0..n_seat {
+                let n = tables.iter().filter(|t| t[s] == i).count();
+                assert_eq!(n, n_dup / n_seat, "seat {} actor {}", s, i);
+            }
+        }
+    };
+    check(4, 4); // 巡回
+    check(4, 24); // 全順列
+    check(3, 3); // 3麻の巡回
+    check(3, 6); // 3麻の全順列
+    assert!(get_dup_seat_tables(4, 2).is_none());
+    assert!(get_dup_seat_tables(3, 4).is_none());
+}
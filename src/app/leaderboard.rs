@@ -0,0 +1,64 @@
+use mahjong_core::{control::rating::RatingStore, error, model::*, util::misc::*};
+
+// [App]
+#[derive(Debug)]
+pub struct LeaderboardApp {
+    rating: String, // レーティングの保存先
+    min_games: usize,
+}
+
+impl LeaderboardApp {
+    pub fn new(args: Vec<String>) -> Self {
+        let mut app = Self {
+            rating: "local/rating.json".into(),
+            min_games: 0,
+        };
+
+        let mut it = args.iter();
+        while let Some(s) = it.next() {
+            match s.as_str() {
+                "-rating" => app.rating = next_value(&mut it, s),
+                "-min-games" => app.min_games = next_value(&mut it, s),
+                opt => {
+                    error!("unknown option: {}", opt);
+                    std::process::exit(0);
+                }
+            }
+        }
+
+        app
+    }
+
+    pub fn run(&mut self) {
+        let ratings = RatingStore::load(&self.rating)
+            .unwrap_or_else(|err| error_exit(format!("{}: {}", self.rating, err)));
+        let now = unixtime_now();
+
+        println!(
+            "{:>4} {:>7} {:>6} {:>8} {:>23} {:>9} {:>8}  name",
+            "#", "rate", "games", "avg_rank", "rank_counts", "avg_delta", "last(d)"
+        );
+        let mut i = 0;
+        for (name, r) in ratings.leaderboard() {
+            if r.n_game < self.min_games {
+                continue;
+            }
+            i += 1;
+            let counts: Vec<String> = (0..SEAT)
+                .map(|k| format!("{:5}", r.rank_counts[k]))
+                .collect();
+            let days = (now - r.last_played as f64) / 86400.0;
+            println!(
+                "{:4} {:7.1} {:6} {:8.2} {} {:9} {:8.1}  {}",
+                i,
+                r.rate,
+                r.n_game,
+                r.avg_rank(),
+                counts.join(" "),
+                r.avg_delta_score(),
+                days,
+                name,
+            );
+        }
+    }
+}
@@ -2,8 +2,11 @@
 mod calculator;
 mod client;
 mod engine;
+mod leaderboard;
 mod replay;
+mod server;
 
 pub use self::{
-    calculator::CalculatorApp, client::ClientApp, engine::EngineApp, replay::ReplayApp,
+    calculator::CalculatorApp, client::ClientApp, engine::EngineApp, leaderboard::LeaderboardApp,
+    replay::ReplayApp, server::ServerApp,
 };
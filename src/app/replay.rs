@@ -2,20 +2,27 @@ use std::path::{Path, PathBuf};
 
 use mahjong_core::{
     control::{actor::Actor, listener::Listener, stage_controller::StageController},
+    convert::{
+        mjai::mjai_to_events,
+        tenhou::{TenhouLog, tenhou_to_events},
+    },
     error,
     model::*,
     serde_json,
     util::misc::*,
 };
 
-use crate::listener::{Debug, EventPrinter};
+use crate::listener::{Debug, EventPrinter, EventWriter, TenhouEventWriter};
 
 #[derive(Debug)]
 pub struct ReplayApp {
     file_path: String,
     skip: String,
     debug: bool,
-    // names: [String; SEAT], // actor names
+    write: bool,
+    write_tenhou: bool,
+    tenhou_dir: String, // 天鳳形式の牌譜の出力先
+                        // names: [String; SEAT], // actor names
 }
 
 impl ReplayApp {
@@ -26,6 +33,9 @@ impl ReplayApp {
             file_path: String::new(),
             skip: String::new(),
             debug: false,
+            write: false,
+            write_tenhou: false,
+            tenhou_dir: "local/paifu_tenhou".into(),
             // names: [
             //     String::new(),
             //     String::new(),
@@ -40,6 +50,8 @@ impl ReplayApp {
                 "-f" => app.file_path = next_value(&mut it, s),
                 "-s" => app.skip = next_value(&mut it, s),
                 "-d" => app.debug = true,
+                "-w" => app.write = true,
+                "-w-tenhou" => app.write_tenhou = true,
                 opt => {
                     error!("unknown option: {}", opt);
                     exit(0);
@@ -62,15 +74,21 @@ impl ReplayApp {
         if self.debug {
             listeners.push(Box::new(Debug::new()));
         }
+        if self.write {
+            listeners.push(Box::new(EventWriter::new()));
+        }
+        if self.write_tenhou {
+            listeners.push(Box::new(TenhouEventWriter::with_dir(&self.tenhou_dir)));
+        }
 
-        // パスがディレクトリならそのディレクトリ内のすべてのjsonファイルを読み込む
+        // パスがディレクトリならそのディレクトリ内のすべてのjson(mjaiの場合はjsonl, mjson)ファイルを読み込む
         let path = Path::new(&self.file_path);
         let paths: Vec<std::path::PathBuf> = if path.is_dir() {
             get_paths(path)
                 .unwrap_or_else(error_exit)
                 .into_iter()
                 .filter(|p| match p.extension() {
-                    Some(ext) => ext == "json",
+                    Some(ext) => ext == "json" || ext == "jsonl" || ext == "mjson",
                     None => false,
                 })
                 .collect()
@@ -98,19 +116,53 @@ impl ReplayApp {
         for p in paths {
             println!("source file: {:?}\n", p);
             let contents = std::fs::read_to_string(p).unwrap_or_else(error_exit);
-            let record: Vec<Event> = serde_json::from_str(&contents).unwrap();
+            let records = parse_records(&contents).unwrap_or_else(error_exit);
 
-            if let Event::New(ev) = &record[0]
-                && (ev.round, ev.dealer, ev.honba) < rkh
-            {
-                continue;
-            }
+            // 牌譜ファイルごとに1試合として扱う (TenhouEventWriterなどが試合単位で出力するため)
+            let mut is_begun = false;
+            for record in records {
+                if let Some(Event::New(ev)) = record.first()
+                    && (ev.round, ev.dealer, ev.honba) < rkh
+                {
+                    continue;
+                }
 
-            game.run(record);
+                if !is_begun {
+                    game.run(vec![Event::begin()]);
+                    is_begun = true;
+                }
+                game.run(record);
+            }
+            if is_begun {
+                game.run(vec![Event::end()]);
+            }
         }
     }
 }
 
+// 牌譜ファイルを局ごとのイベント列に変換
+// 天鳳形式(-w-tenhouで出力した牌譜または天鳳の牌譜)とmjai形式(JSON Lines)の場合は1ファイルに複数の局が含まれる場合がある
+fn parse_records(contents: &str) -> Res<Vec<Vec<Event>>> {
+    // mjai形式は1行目が"type"を持つオブジェクト
+    let first_line = contents
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("");
+    if let Ok(v) = serde_json::from_str::<serde_json::Value>(first_line)
+        && v.get("type").is_some()
+    {
+        return mjai_to_events(contents);
+    }
+
+    let value: serde_json::Value = serde_json::from_str(contents)?;
+    if value.get("log").is_some() {
+        let log: TenhouLog = serde_json::from_value(value)?;
+        tenhou_to_events(&log)
+    } else {
+        Ok(vec![serde_json::from_value(value)?])
+    }
+}
+
 #[derive(Debug)]
 struct Replay {
     ctrl: StageController,
@@ -127,7 +179,7 @@ impl Replay {
         ];
 
         Self {
-            ctrl: StageController::new(nops, listeners),
+            ctrl: StageController::new(nops, listeners, [false; SEAT]),
         }
     }
 
@@ -139,3 +191,188 @@ impl Replay {
         }
     }
 }
+
+#[test]
+fn test_log_round_trip() {
+    use std::sync::{Arc, Mutex};
+
+    use mahjong_core::{
+        control::{
+            engine::MahjongEngine,
+            testing::{FnListener, Greedy},
+        },
+        convert::{
+            mjai::{MjaiEvent, MjaiSerializer},
+            tenhou::TenhouSerializer,
+        },
+    };
+
+    use crate::actor::create_actor;
+
+    // 局ごとに元のイベント列と天鳳形式,mjai形式の牌譜を記録
+    type Rounds = Arc<Mutex<Vec<(Vec<Event>, [String; 2])>>>;
+    let new_recorder = |rounds: &Rounds| {
+        let rounds = rounds.clone();
+        let mut serializer = TenhouSerializer::new();
+        let mut mjai_serializer = MjaiSerializer::new(NO_SEAT);
+        let mut events = vec![];
+        let mut mjai_lines = vec![];
+        FnListener(move |stg: &Stage, event: &Event| {
+            if let Event::New(_) = event {
+                serializer = TenhouSerializer::new(); // 局単位で比較
+            }
+            serializer.push_event(stg, event);
+            if let Event::New(ev) = event {
+                let start_game = MjaiEvent::start_game(NO_SEAT, &ev.rule);
+                mjai_lines = vec![serde_json::to_string(&start_game).unwrap()];
+            }
+            for e in mjai_serializer.push_event(stg, event) {
+                mjai_lines.push(serde_json::to_string(&e).unwrap());
+            }
+            match event {
+                Event::Begin(_) | Event::End(_) | Event::Timeout(_) => return,
+                Event::New(_) => events.clear(),
+                _ => {}
+            }
+            events.push(event.clone());
+            if let Event::Win(_) | Event::Draw(_) = event {
+                let events = std::mem::take(&mut events);
+                let logs = [serializer.serialize(), mjai_lines.join("\n")];
+                rounds.lock().unwrap().push((events, logs));
+            }
+        })
+    };
+
+    // 元のイベント列と牌譜を読み込んだイベント列を比較
+    fn assert_round_trip(events: &[Event], log: &str, is_mjai: bool) {
+        let json = |e: &Event| serde_json::to_value(e).unwrap();
+        let json_yakus = |s: &ScoreContext| serde_json::to_value(&s.yakus).unwrap();
+        let records = parse_records(log).unwrap();
+        assert_eq!(records.len(), 1);
+        let imported = &records[0];
+        assert_eq!(events.len(), imported.len(), "{}", log);
+        for (e0, e1) in events.iter().zip(imported) {
+            match (e0, e1) {
+                (Event::New(ev0), Event::New(ev1)) => {
+                    assert_eq!(ev0.hands, ev1.hands);
+                    assert_eq!(ev0.scores, ev1.scores);
+                    assert_eq!(ev0.doras, ev1.doras);
+                    assert_eq!(ev0.wall_count, ev1.wall_count);
+                }
+                (Event::Win(ev0), Event::Win(ev1)) => {
+                    assert_eq!(ev0.delta_scores, ev1.delta_scores);
+                    for (c0, c1) in ev0.contexts.iter().zip(&ev1.contexts) {
+                        assert_eq!(c0.seat, c1.seat);
+                        assert_eq!(c0.hand, c1.hand);
+                        assert_eq!(c0.winning_tile, c1.winning_tile);
+                        assert_eq!(c0.pao, c1.pao, "{}", log);
+                        assert_eq!(c0.delta_scores, c1.delta_scores);
+                        let (s0, s1) = (&c0.score_context, &c1.score_context);
+                        assert_eq!(s0.points, s1.points);
+                        assert_eq!(s0.score, s1.score);
+                        assert_eq!(s0.yakuman, s1.yakuman);
+                        assert_eq!(s0.title, s1.title);
+                        // 天鳳形式は満貫以上の符などが記録されない
+                        if is_mjai {
+                            assert_eq!(s0.fu, s1.fu);
+                            assert_eq!(s0.fan, s1.fan);
+                            assert_eq!(json_yakus(s0), json_yakus(s1), "{}", log);
+                        }
+                    }
+                }
+                (Event::Draw(ev0), Event::Draw(ev1)) => {
+                    assert_eq!(ev0.draw_type, ev1.draw_type, "{}", log);
+                    assert_eq!(ev0.delta_scores, ev1.delta_scores);
+                    assert_eq!(ev0.nagashimangan_scores, ev1.nagashimangan_scores);
+                    assert_eq!(ev0.hands, ev1.hands, "{}", log);
+                }
+                _ => assert_eq!(json(e0), json(e1), "{}", log),
+            }
+        }
+    }
+
+    let check_rounds = |rounds: &Rounds| {
+        for (events, logs) in rounds.lock().unwrap().iter() {
+            for (i_log, log) in logs.iter().enumerate() {
+                assert_round_trip(events, log, i_log == 1);
+            }
+        }
+    };
+
+    for is_sanma in [false, true] {
+        let rule = Rule {
+            round: 2,
+            is_sanma,
+            initial_score: if is_sanma { 35000 } else { 25000 },
+            settlement_score: if is_sanma { 40000 } else { 30000 },
+            red5: 1,
+            bust: true,
+            ..Default::default()
+        };
+        for seed in 1..=5 {
+            let actors: [Box<dyn Actor>; SEAT] = [
+                Box::new(Greedy::new(true)),
+                create_actor("TiitoitsuBot"),
+                Box::new(Greedy::new(true)),
+                create_actor(if is_sanma { "Null" } else { "TiitoitsuBot" }),
+            ];
+            let rounds = Arc::new(Mutex::new(vec![]));
+            let recorder = new_recorder(&rounds);
+            let mut game = MahjongEngine::new(
+                seed,
+                rule.clone(),
+                0.0,
+                actors,
+                vec![Box::new(recorder)],
+                [false; SEAT],
+            );
+            game.run();
+            check_rounds(&rounds);
+        }
+    }
+
+    // 天鳳の牌譜 (責任払いの大三元のロン) を読み込んで再生し,出力した牌譜と比較
+    let contents = std::fs::read_to_string("tests/tenhou/pao_daisangen.json").unwrap();
+    let records = parse_records(&contents).unwrap();
+    let Some(Event::Win(ev)) = records[0].last() else {
+        panic!("win is expected");
+    };
+    assert_eq!(ev.contexts[0].pao, Some(2));
+    assert_eq!(ev.delta_scores, [0, 32000, -16000, -16000]);
+    for record in records {
+        let rounds = Arc::new(Mutex::new(vec![]));
+        let mut replay = Replay::new(vec![Box::new(new_recorder(&rounds))]);
+        replay.run(record);
+        assert_eq!(rounds.lock().unwrap().len(), 1);
+        check_rounds(&rounds);
+    }
+}
+
+#[test]
+fn test_replay_write_tenhou() {
+    // 同じ天鳳の牌譜を2つのファイルとして再生すると試合ごとに別のファイルに出力される
+    let tmp = std::env::temp_dir().join(format!("replay_write_tenhou_{}", std::process::id()));
+    let (src, out) = (tmp.join("src"), tmp.join("out"));
+    std::fs::create_dir_all(&src).unwrap();
+    for name in ["a.json", "b.json"] {
+        std::fs::copy("tests/tenhou/pao_daisangen.json", src.join(name)).unwrap();
+    }
+    let args = ["-f", src.to_str().unwrap(), "-w-tenhou"];
+    let mut app = ReplayApp::new(args.iter().map(|s| s.to_string()).collect());
+    app.tenhou_dir = out.to_str().unwrap().into();
+    app.run();
+    let outputs = get_paths(&out).unwrap();
+
+    let source: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string("tests/tenhou/pao_daisangen.json").unwrap())
+            .unwrap();
+    assert_eq!(outputs.len(), 2);
+    for p in outputs {
+        let log: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&p).unwrap()).unwrap();
+        assert_eq!(log["log"].as_array().unwrap().len(), 1);
+        assert_eq!(log["log"][0][16], source["log"][0][16]); // 和了結果
+        assert_eq!(log["sc"], source["sc"]);
+    }
+    std::fs::remove_dir_all(&tmp).unwrap();
+}
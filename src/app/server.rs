@@ -0,0 +1,466 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    panic,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Instant,
+};
+
+use mahjong_core::{
+    control::{
+        actor::Actor,
+        engine::MahjongEngine,
+        rating::{INITIAL_RATE, RatingStore, actor_key},
+    },
+    error, info,
+    model::*,
+    rand::{self, prelude::*},
+    serde_json,
+    util::{
+        connection::{Connection, Message, listen_connections},
+        misc::*,
+    },
+    warn,
+};
+
+use crate::actor::{create_actor, endpoint::Endpoint};
+
+// Joinを送信せずにこの秒数が経過した接続は切断
+const JOIN_TIMEOUT: f64 = 10.0;
+
+// クライアントの成績とレーティングのキーの接頭辞 (botのキーと区別)
+const CLIENT_KEY_PREFIX: &str = "client:";
+
+// [App]
+#[derive(Debug)]
+pub struct ServerApp {
+    seed: u64,
+    rule: Rule,
+    addr: String,
+    protocol: String,
+    token: String,
+    bot: String,    // 空席を埋めるActor
+    wait: f64,      // 待機中のクライアントがこの秒数を超えた場合は空席をbotで埋めて開始
+    n_table: usize, // 同時に実行する卓の上限
+    rating: String, // レーティングの保存先
+}
+
+impl ServerApp {
+    pub fn new(args: Vec<String>) -> Self {
+        let mut app = Self {
+            seed: 0,
+            rule: Rule {
+                round: 1,
+                initial_score: 25000,
+                settlement_score: 30000,
+                red5: 1,
+                bust: true,
+                time_base: 10.0,
+                ..Default::default()
+            },
+            addr: "127.0.0.1:52010".into(),
+            protocol: "tcp".into(),
+            token: "".into(),
+            bot: "EfficiencyBot".into(),
+            wait: 10.0,
+            n_table: 4,
+            rating: "local/rating.json".into(),
+        };
+
+        let mut it = args.iter();
+        while let Some(s) = it.next() {
+            match s.as_str() {
+                "-s" => app.seed = next_value(&mut it, s),
+                "-r-round" => app.rule.round = next_value(&mut it, s),
+                "-r-time-base" => app.rule.time_base = next_value(&mut it, s),
+                "-r-time-extra" => app.rule.time_extra = next_value(&mut it, s),
+                "-a" => app.addr = next_value(&mut it, s),
+                "-ws" => app.protocol = "ws".into(),
+                "-token" => app.token = next_value(&mut it, s),
+                "-b" => app.bot = next_value(&mut it, s),
+                "-wait" => app.wait = next_value(&mut it, s),
+                "-t" => app.n_table = next_value(&mut it, s),
+                "-rating" => app.rating = next_value(&mut it, s),
+                opt => {
+                    error!("unknown option: {}", opt);
+                    std::process::exit(0);
+                }
+            }
+        }
+
+        if app.seed == 0 {
+            app.seed = unixtime_now() as u64;
+            info!(
+                "Random seed is not specified. Unix timestamp '{}' is used as seed.",
+                app.seed
+            );
+        }
+
+        app
+    }
+
+    pub fn run(self) {
+        let conn_rx = match listen_connections(&self.protocol, &self.addr) {
+            Ok(rx) => rx,
+            Err(err) => {
+                error!("{}: {}", self.addr, err);
+                std::process::exit(1);
+            }
+        };
+        let mut lobby = Lobby::new(&self, conn_rx);
+        println!("seed: {}", self.seed);
+        println!("bot: {}", lobby.bot_name);
+        println!("listening on {} ({})", self.addr, self.protocol);
+        println!();
+
+        loop {
+            lobby.poll();
+            sleep(0.01);
+        }
+    }
+}
+
+// 待機列,対局中の卓,成績を管理するロビー (ServerApp::runから一定間隔でpollを呼び出す)
+struct Lobby {
+    rule: Rule,
+    token: String,
+    bot: Box<dyn Actor>,
+    bot_name: String,
+    wait: f64,
+    max_table: usize,
+    rating: String,
+    rng: StdRng,
+    conn_rx: Receiver<Box<dyn Connection>>,
+    joining: Vec<(Box<dyn Connection>, Instant)>,
+    queue: Vec<Client>,
+    playing: HashMap<String, Playing>,
+    records: BTreeMap<String, Record>,
+    ratings: RatingStore,
+    tx: Sender<TableResult>,
+    rx: Receiver<TableResult>,
+    n_table: usize,
+    n_running: usize,
+}
+
+// (卓のid, 座席ごとのキー, シード値, 終了したゲーム(異常終了の場合はNone))
+type TableResult = (usize, [String; SEAT], u64, Option<MahjongEngine>);
+
+impl Lobby {
+    fn new(app: &ServerApp, conn_rx: Receiver<Box<dyn Connection>>) -> Self {
+        let bot = create_actor(&app.bot);
+        let bot_name = actor_key(bot.as_ref()); // botのキーはActorの式
+        let ratings = RatingStore::load(&app.rating)
+            .unwrap_or_else(|err| error_exit(format!("{}: {}", app.rating, err)));
+        let (tx, rx) = mpsc::channel();
+        Self {
+            rule: app.rule.clone(),
+            token: app.token.clone(),
+            bot,
+            bot_name,
+            wait: app.wait,
+            max_table: app.n_table,
+            rating: app.rating.clone(),
+            rng: SeedableRng::seed_from_u64(app.seed),
+            conn_rx,
+            joining: vec![],
+            queue: vec![],
+            playing: HashMap::new(),
+            records: BTreeMap::new(),
+            ratings,
+            tx,
+            rx,
+            n_table: 0,
+            n_running: 0,
+        }
+    }
+
+    fn poll(&mut self) {
+        self.accept_joins();
+
+        // 待機中に切断したクライアントを除外
+        self.queue.retain_mut(|c| {
+            if let Message::Close = c.conn.recv() {
+                info!("{} left", c.name);
+                return false;
+            }
+            true
+        });
+
+        // マッチング (4人揃った場合,または最も長く待機しているクライアントの待ち時間が上限を超えた場合)
+        while self.n_running < self.max_table
+            && !self.queue.is_empty()
+            && (self.queue.len() >= SEAT
+                || self.queue[0].since.elapsed().as_secs_f64() >= self.wait)
+        {
+            self.start_table();
+        }
+
+        let results: Vec<_> = self.rx.try_iter().collect();
+        for res in results {
+            self.record_table(res);
+        }
+    }
+
+    fn accept_joins(&mut self) {
+        self.joining
+            .extend(self.conn_rx.try_iter().map(|conn| (conn, Instant::now())));
+        for (mut conn, since) in std::mem::take(&mut self.joining) {
+            match conn.recv() {
+                Message::Text(text) => match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Join {
+                        token,
+                        name,
+                        secret,
+                    }) if token == self.token && !name.is_empty() => {
+                        // 対局中のnameはSessionで通知したsecretが一致する場合のみ再接続
+                        if let Some(p) = self.playing.get(&name) {
+                            if secret == p.secret {
+                                info!("{} reconnected", name);
+                                p.reconnector.send(conn).ok();
+                            } else {
+                                warn!("name already playing: {}", name);
+                                conn.close();
+                            }
+                        } else if self.queue.iter().any(|c| c.name == name) {
+                            warn!("name already in use: {}", name);
+                            conn.close();
+                        } else {
+                            info!("{} joined", name);
+                            let secret = format!("{:032x}", rand::rng().random::<u128>());
+                            let msg = ServerMessage::Session {
+                                secret: secret.clone(),
+                            };
+                            conn.send(&serde_json::to_string(&msg).unwrap());
+                            self.queue.push(Client {
+                                name,
+                                secret,
+                                conn,
+                                since: Instant::now(),
+                            });
+                        }
+                    }
+                    _ => {
+                        warn!("join rejected: {}", text);
+                        conn.close();
+                    }
+                },
+                Message::Nop if since.elapsed().as_secs_f64() < JOIN_TIMEOUT => {
+                    self.joining.push((conn, since));
+                }
+                _ => conn.close(),
+            }
+        }
+    }
+
+    fn start_table(&mut self) {
+        let mut seats: Vec<(String, Box<dyn Actor>)> = vec![];
+        let n_client = self.queue.len().min(SEAT);
+        for c in self.queue.drain(..n_client) {
+            let ep = Endpoint::from_connection(c.conn);
+            let p = Playing {
+                secret: c.secret,
+                reconnector: ep.reconnector(),
+            };
+            self.playing.insert(c.name.clone(), p);
+            seats.push((CLIENT_KEY_PREFIX.to_string() + &c.name, Box::new(ep)));
+        }
+        while seats.len() < SEAT {
+            seats.push((self.bot_name.clone(), self.bot.clone_box()));
+        }
+        seats.shuffle(&mut self.rng);
+        let (names, actors): (Vec<_>, Vec<_>) = seats.into_iter().unzip();
+        let names: [String; SEAT] = names.try_into().unwrap();
+        let actors: [Box<dyn Actor>; SEAT] = actors.try_into().unwrap();
+
+        self.n_table += 1;
+        self.n_running += 1;
+        println!("table {:4} start: {}", self.n_table, names.join(", "));
+
+        let id = self.n_table;
+        let seed = self.rng.next_u64();
+        let rule = self.rule.clone();
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            // 1卓の異常終了でサーバ全体が停止しないようにpanicを捕捉
+            let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                let mut game = MahjongEngine::new(seed, rule, 0.0, actors, vec![], [false; SEAT]);
+                game.run();
+                game
+            }));
+            tx.send((id, names, seed, res.ok())).unwrap();
+        });
+    }
+
+    // 結果の集計 (終了した卓のEndpointはgameと一緒に破棄されて接続が切断される)
+    fn record_table(&mut self, (id, names, seed, game): TableResult) {
+        self.n_running -= 1;
+        for name in names
+            .iter()
+            .filter_map(|n| n.strip_prefix(CLIENT_KEY_PREFIX))
+        {
+            self.playing.remove(name);
+        }
+
+        let Some(game) = game else {
+            println!("table {:4} aborted", id);
+            return;
+        };
+        print!("table {:4} end: {:20}", id, seed);
+        let stg = game.get_stage();
+        let mut results = vec![];
+        for s in 0..SEAT {
+            let pl = &stg.players[s];
+            let delta_score = pl.score - self.rule.initial_score;
+            let r = self.records.entry(names[s].clone()).or_default();
+            r.n_game += 1;
+            r.sum_rank += pl.rank + 1;
+            r.sum_delta_score += delta_score;
+            results.push((names[s].clone(), pl.rank, delta_score));
+            print!(", {}:{:5}({})", names[s], pl.score, pl.rank + 1);
+        }
+        println!();
+        self.ratings.update(&results, false);
+        if let Err(err) = self.ratings.save() {
+            error!("{}: {}", self.rating, err);
+        }
+        print_records(&self.records, &self.ratings);
+    }
+}
+
+struct Client {
+    name: String,
+    secret: String, // 再接続用 (Sessionでクライアントに通知)
+    conn: Box<dyn Connection>,
+    since: Instant, // 待機開始時刻
+}
+
+// 対局中のクライアント
+struct Playing {
+    secret: String,
+    reconnector: Sender<Box<dyn Connection>>,
+}
+
+#[derive(Debug, Default)]
+struct Record {
+    n_game: usize,
+    sum_rank: usize,
+    sum_delta_score: i32,
+}
+
+// クライアント名ごとのこのサーバでの成績 (平均順位順) とレーティング
+fn print_records(records: &BTreeMap<String, Record>, ratings: &RatingStore) {
+    let avg_rank = |r: &Record| r.sum_rank as f32 / r.n_game as f32;
+    let mut rs: Vec<_> = records.iter().collect();
+    rs.sort_by(|(_, r0), (_, r1)| avg_rank(r0).total_cmp(&avg_rank(r1)));
+    for (name, r) in rs {
+        println!(
+            "    {:24} games: {:4}, avg_rank: {:.2}, avg_delta_score: {:6}, rate: {:.1}",
+            name,
+            r.n_game,
+            avg_rank(r),
+            r.sum_delta_score / r.n_game as i32,
+            ratings.get(name).map_or(INITIAL_RATE, |r| r.rate),
+        );
+    }
+}
+
+#[test]
+fn test_lobby() {
+    use mahjong_core::{control::testing::wait_message, util::connection::TcpConnection};
+
+    let path = std::env::temp_dir().join(format!("mahjong_lobby_{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    let args = format!("-s 1 -token t -wait 0.2 -r-time-base 0 -rating {}", path);
+    let app = ServerApp::new(args.split(' ').map(String::from).collect());
+    let (conn_tx, conn_rx) = mpsc::channel();
+    let mut lobby = Lobby::new(&app, conn_rx);
+
+    // ロビーが受け付けたConnectionとJoinを送信したクライアントの接続
+    let join = |name: &str, secret: &str| {
+        let mut server = TcpConnection::new("127.0.0.1:0");
+        let addr = server.local_addr().unwrap().to_string();
+        let mut client = TcpConnection::connect(&addr);
+        assert!(matches!(wait_message(&mut client), Message::Open));
+        assert!(matches!(wait_message(&mut server), Message::Open));
+        conn_tx
+            .send(Box::new(server) as Box<dyn Connection>)
+            .unwrap();
+        let msg = ClientMessage::Join {
+            token: "t".into(),
+            name: name.into(),
+            secret: secret.into(),
+        };
+        client.send(&serde_json::to_string(&msg).unwrap());
+        client
+    };
+    // ロビーを進めながらクライアントのメッセージを待機
+    let recv = |lobby: &mut Lobby, client: &mut TcpConnection| {
+        for _ in 0..300 {
+            lobby.poll();
+            match client.recv() {
+                Message::Text(text) => return Some(serde_json::from_str(&text).unwrap()),
+                Message::Close => return None,
+                _ => sleep(0.01),
+            }
+        }
+        panic!("no message from lobby");
+    };
+    // 対局が終わるまですべてのActionにNopを返すクライアント
+    let play = |mut client: TcpConnection| {
+        thread::spawn(move || {
+            while let Message::Text(text) = wait_message(&mut client) {
+                if let Ok(ServerMessage::Action(acts)) = serde_json::from_str(&text) {
+                    let act = SelectedAction {
+                        id: acts.id,
+                        action: Action::nop(),
+                    };
+                    client.send(&serde_json::to_string(&ClientMessage::Action(act)).unwrap());
+                }
+            }
+        })
+    };
+
+    // alice, bob + bot 2人で卓を作成 (持ち時間なしのため,aliceが応答するまで対局は進まない)
+    let mut alice = join("alice", "");
+    let Some(ServerMessage::Session { secret }) = recv(&mut lobby, &mut alice) else {
+        panic!("session is expected");
+    };
+    let mut bob = join("bob", "");
+    assert!(matches!(
+        recv(&mut lobby, &mut bob),
+        Some(ServerMessage::Session { .. })
+    ));
+    let bob = play(bob);
+    while lobby.n_running == 0 {
+        lobby.poll();
+        sleep(0.01);
+    }
+
+    // 対局中のnameでsecretが一致しない場合は拒否
+    let mut impostor = join("alice", "wrong");
+    assert!(recv(&mut lobby, &mut impostor).is_none());
+
+    // secretが一致する場合は座席に復帰し,元の接続は切断
+    let alice2 = play(join("alice", &secret));
+    while recv(&mut lobby, &mut alice).is_some() {}
+    for _ in 0..3000 {
+        if lobby.n_running == 0 {
+            break;
+        }
+        lobby.poll();
+        sleep(0.01);
+    }
+    assert_eq!(lobby.n_running, 0);
+    alice2.join().unwrap();
+    bob.join().unwrap();
+
+    // キーごとの成績 (クライアントはnameに接頭辞,botはActorの式)
+    let n_game = |key: &str| lobby.records.get(key).map_or(0, |r| r.n_game);
+    assert_eq!(n_game("client:alice"), 1);
+    assert_eq!(n_game("client:bob"), 1);
+    assert_eq!(n_game(&lobby.bot_name), 2);
+    assert_eq!(lobby.records.len(), 3);
+    assert_eq!(lobby.ratings.get("client:alice").unwrap().n_game, 1);
+    assert_eq!(lobby.ratings.get(&lobby.bot_name).unwrap().n_game, 1);
+    std::fs::remove_file(path).ok();
+}
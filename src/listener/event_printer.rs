@@ -82,6 +82,9 @@ impl Listener for EventPrinter {
             End(_) => {
                 println!("End");
             }
+            Timeout(ev) => {
+                println!("Timeout seat: {}", ev.seat);
+            }
         }
         println!();
     }
@@ -6,7 +6,7 @@ use std::{
 use mahjong_core::{
     control::listener::Listener,
     model::*,
-    serde_json::{Value, json},
+    serde_json::json,
     util::{
         connection::{Connection, Message},
         misc::sleep,
@@ -16,11 +16,12 @@ use mahjong_core::{
 #[derive(Debug, Default)]
 struct SharedData {
     send_request: bool,
-    msgs: Vec<Value>,
+    msgs: Vec<ServerMessage>,
     cursor: usize,
 }
 
 // [EventSender]
+// 接続したクライアントにServerMessageのEventを送信 (観戦用, 秘匿情報を含む)
 #[derive(Debug)]
 pub struct EventSender {
     data: Arc<Mutex<SharedData>>,
@@ -41,7 +42,7 @@ impl EventSender {
                         Message::Text(_) => {}
                         Message::Nop => {
                             while d.cursor < d.msgs.len() {
-                                conn.send(&d.msgs[d.cursor].to_string());
+                                conn.send(&json!(d.msgs[d.cursor]).to_string());
                                 d.cursor += 1;
                             }
                             break;
@@ -66,7 +67,7 @@ impl Listener for EventSender {
                 d.msgs.clear();
                 d.cursor = 0;
             }
-            d.msgs.push(json!(event));
+            d.msgs.push(ServerMessage::Event(Box::new(event.clone())));
             d.send_request = true;
         }
 
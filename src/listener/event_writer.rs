@@ -1,6 +1,6 @@
 use mahjong_core::{
     control::listener::Listener,
-    convert::tenhou::TenhouSerializer,
+    convert::{mjlog::MjlogSerializer, tenhou::TenhouSerializer},
     model::*,
     serde_json::{self, Value, json},
     util::misc::*,
@@ -66,18 +66,25 @@ impl Listener for EventWriter {
 }
 
 // [TenhouEventWriter]
+// 1試合分の牌譜を1つのファイルに出力 (途中で終了した場合に備えて局の終了毎に上書き)
 #[derive(Debug)]
 pub struct TenhouEventWriter {
+    dir: String, // 出力先のディレクトリ
     start_time: u64,
-    round_index: i32,
+    prev_start_time: u64,
     serializer: TenhouSerializer,
 }
 
 impl TenhouEventWriter {
     pub fn new() -> Self {
+        Self::with_dir("local/paifu_tenhou")
+    }
+
+    pub fn with_dir(dir: &str) -> Self {
         Self {
+            dir: dir.into(),
             start_time: unixtime_now() as u64,
-            round_index: 0,
+            prev_start_time: 0,
             serializer: TenhouSerializer::new(),
         }
     }
@@ -90,31 +97,74 @@ impl Default for TenhouEventWriter {
 }
 
 impl Listener for TenhouEventWriter {
+    fn notify_event(&mut self, stg: &Stage, event: &Event) {
+        let mut write = false;
+        match event {
+            Event::Begin(_) => {
+                // 牌譜の再生時など1秒以内に複数の試合が開始した場合にファイル名が重複しないようにずらす
+                self.start_time = (unixtime_now() as u64).max(self.prev_start_time + 1);
+                self.prev_start_time = self.start_time;
+            }
+            Event::Win(_) | Event::Draw(_) | Event::End(_) => {
+                write = true;
+            }
+            _ => {}
+        }
+
+        self.serializer.push_event(stg, event);
+        if write {
+            write_to_file(
+                &format!("{}/{}.json", self.dir, self.start_time),
+                &self.serializer.serialize(),
+            )
+            .ok();
+        }
+    }
+}
+
+// [MjlogEventWriter]
+// 天鳳のmjlog(XML)形式で1試合分の牌譜を出力
+#[derive(Debug)]
+pub struct MjlogEventWriter {
+    start_time: u64,
+    serializer: MjlogSerializer,
+}
+
+impl MjlogEventWriter {
+    pub fn new() -> Self {
+        Self {
+            start_time: unixtime_now() as u64,
+            serializer: MjlogSerializer::new(),
+        }
+    }
+}
+
+impl Default for MjlogEventWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Listener for MjlogEventWriter {
     fn notify_event(&mut self, stg: &Stage, event: &Event) {
         let mut write = false;
         match event {
             Event::Begin(_) => {
                 self.start_time = unixtime_now() as u64;
-                self.round_index = 0;
             }
-            Event::Win(_) | Event::Draw(_) => {
+            Event::Win(_) | Event::Draw(_) | Event::End(_) => {
                 write = true;
             }
-            Event::End(_) => {}
             _ => {}
         }
 
         self.serializer.push_event(stg, event);
         if write {
             write_to_file(
-                &format!(
-                    "local/paifu_tenhou/{}/{:02}.json",
-                    self.start_time, self.round_index
-                ),
+                &format!("local/paifu_mjlog/{}.xml", self.start_time),
                 &self.serializer.serialize(),
             )
             .ok();
-            self.round_index += 1;
         }
     }
 }
@@ -4,11 +4,13 @@ mod event_printer;
 mod event_sender;
 mod event_writer;
 mod message_channel;
+mod stats_collector;
 
 pub use self::{
     debug::Debug,
     event_printer::EventPrinter,
     event_sender::{EventSender, StageSender},
-    event_writer::{EventWriter, TenhouEventWriter},
+    event_writer::{EventWriter, MjlogEventWriter, TenhouEventWriter},
     message_channel::MessageChannel,
+    stats_collector::StatsCollector,
 };
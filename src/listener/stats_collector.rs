@@ -0,0 +1,210 @@
+use std::sync::{Arc, Mutex};
+
+use mahjong_core::{control::listener::Listener, control::stats::Stats, model::*};
+
+// 座席ごとの対局統計を集計 (試合終了後に共有しているstatsから取り出す)
+pub struct StatsCollector {
+    stats: Arc<Mutex<[Stats; SEAT]>>,
+    is_riichi: [bool; SEAT], // 現在の局で立直したかどうか
+    is_called: [bool; SEAT], // 現在の局で副露したかどうか
+}
+
+impl StatsCollector {
+    pub fn new(stats: Arc<Mutex<[Stats; SEAT]>>) -> Self {
+        Self {
+            stats,
+            is_riichi: [false; SEAT],
+            is_called: [false; SEAT],
+        }
+    }
+
+    fn end_kyoku(&self, stats: &mut [Stats; SEAT]) {
+        for s in 0..SEAT {
+            let st = &mut stats[s];
+            st.n_kyoku += 1;
+            st.n_riichi += self.is_riichi[s] as usize;
+            st.n_call += self.is_called[s] as usize;
+        }
+    }
+}
+
+impl Listener for StatsCollector {
+    fn notify_event(&mut self, stg: &Stage, event: &Event) {
+        let mut stats = self.stats.lock().unwrap();
+        match event {
+            Event::New(_) => {
+                self.is_riichi = [false; SEAT];
+                self.is_called = [false; SEAT];
+            }
+            Event::Discard(ev) if ev.is_riichi => {
+                self.is_riichi[ev.seat] = true;
+            }
+            Event::Meld(ev) if ev.meld_type != MeldType::Ankan => {
+                self.is_called[ev.seat] = true;
+            }
+            Event::Win(ev) => {
+                // ロン(槍槓を含む)の場合,放銃者は手番のプレイヤー
+                let mut deal_in = 0;
+                for ctx in &ev.contexts {
+                    let sc = &ctx.score_context;
+                    stats[ctx.seat].add_win(ctx.delta_scores[ctx.seat], ctx.is_drawn, &sc.yakus);
+                    if !ctx.is_drawn {
+                        deal_in -= ctx.delta_scores[stg.turn];
+                    }
+                }
+                if deal_in != 0 {
+                    stats[stg.turn].add_deal_in(deal_in);
+                }
+                self.end_kyoku(&mut stats);
+            }
+            Event::Draw(ev) => {
+                if ev.draw_type == DrawType::Kouhaiheikyoku {
+                    for s in 0..SEAT {
+                        stats[s].n_exhaustive += 1;
+                        stats[s].n_tenpai += !ev.hands[s].is_empty() as usize;
+                    }
+                }
+                self.end_kyoku(&mut stats);
+            }
+            Event::End(_) => {
+                for s in 0..SEAT {
+                    stats[s].n_game += 1;
+                    stats[s].rank_counts[stg.players[s].rank] += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_stats_collector() {
+    let shared = Arc::new(Mutex::new(<[Stats; SEAT]>::default()));
+    let mut collector = StatsCollector::new(shared.clone());
+    let mut stg = Stage::default();
+    for (s, rank) in [0, 2, 1, 3].into_iter().enumerate() {
+        stg.players[s].rank = rank; // 終局時の順位
+    }
+    let mut notify = |turn: Seat, event: Event| {
+        stg.turn = turn;
+        collector.notify_event(&stg, &event);
+    };
+
+    let new = || {
+        Event::new(
+            Rule::default(),
+            0,
+            0,
+            0,
+            0,
+            vec![],
+            Default::default(),
+            [25000; SEAT],
+            Default::default(),
+            0,
+            0,
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+        )
+    };
+    let ctx = |seat: Seat, is_drawn: bool, delta_scores: [Point; SEAT]| WinContext {
+        seat,
+        hand: vec![],
+        winning_tile: Z8,
+        melds: vec![],
+        is_dealer: false,
+        is_drawn,
+        is_riichi: false,
+        pao: None,
+        delta_scores,
+        score_context: ScoreContext {
+            yakus: vec![Yaku {
+                name: "断幺九".into(),
+                fan: 1,
+            }],
+            fu: 30,
+            fan: 1,
+            yakuman: 0,
+            score: 1000,
+            points: (1000, 0, 0),
+            title: String::new(),
+        },
+    };
+    let win = |contexts: Vec<WinContext>| {
+        let mut delta_scores = [0; SEAT];
+        for c in &contexts {
+            for s in 0..SEAT {
+                delta_scores[s] += c.delta_scores[s];
+            }
+        }
+        Event::win(vec![], delta_scores, contexts)
+    };
+    let t = Tile(TM, 1);
+
+    // 座席1が立直,座席2がポンした後に座席3の打牌を座席0がロン
+    notify(0, new());
+    notify(1, Event::discard(1, t, false, true));
+    notify(2, Event::meld(2, MeldType::Pon, vec![t, t], false));
+    notify(3, win(vec![ctx(0, false, [9000, 0, 0, -8000])]));
+
+    // 座席0が立直,座席3の打牌を座席1と座席2がダブロン (放銃は1回)
+    notify(0, new());
+    notify(0, Event::discard(0, t, false, true));
+    notify(
+        3,
+        win(vec![
+            ctx(1, false, [0, 3900, 0, -3900]),
+            ctx(2, false, [0, 0, 2000, -2000]),
+        ]),
+    );
+
+    // 座席3の暗槓(副露に含めない),座席1の加槓を座席0が槍槓
+    notify(0, new());
+    notify(3, Event::meld(3, MeldType::Ankan, vec![t; 4], false));
+    notify(1, Event::meld(1, MeldType::Pon, vec![t, t], false));
+    notify(1, Event::meld(1, MeldType::Kakan, vec![t], false));
+    notify(1, win(vec![ctx(0, false, [1300, -1300, 0, 0])]));
+
+    // 座席0がツモ和了
+    notify(0, new());
+    notify(0, win(vec![ctx(0, true, [1500, -500, -500, -500])]));
+
+    // 荒牌平局 (座席0と座席2が聴牌), 九種九牌 (荒牌平局に含めない)
+    notify(0, new());
+    let hands = [vec![t], vec![], vec![t], vec![]];
+    notify(
+        0,
+        Event::draw(DrawType::Kouhaiheikyoku, [0; SEAT], [0; SEAT], hands),
+    );
+    notify(0, new());
+    notify(
+        0,
+        Event::draw(
+            DrawType::Kyushukyuhai,
+            [0; SEAT],
+            [0; SEAT],
+            Default::default(),
+        ),
+    );
+
+    notify(0, Event::end());
+
+    let stats = shared.lock().unwrap();
+    let n = |f: fn(&Stats) -> usize| -> Vec<usize> { stats.iter().map(f).collect() };
+    assert_eq!(n(|st| st.n_game), [1, 1, 1, 1]);
+    assert_eq!(n(|st| st.n_kyoku), [6, 6, 6, 6]);
+    assert_eq!(n(|st| st.n_win), [3, 1, 1, 0]);
+    assert_eq!(n(|st| st.n_tsumo), [1, 0, 0, 0]);
+    assert_eq!(n(|st| st.n_deal_in), [0, 1, 0, 2]);
+    assert_eq!(n(|st| st.n_riichi), [1, 1, 0, 0]);
+    assert_eq!(n(|st| st.n_call), [0, 1, 1, 0]);
+    assert_eq!(n(|st| st.n_exhaustive), [1, 1, 1, 1]);
+    assert_eq!(n(|st| st.n_tenpai), [1, 0, 1, 0]);
+    assert_eq!(stats[0].sum_win_score, 9000.0 + 1300.0 + 1500.0);
+    assert_eq!(stats[1].sum_deal_in_score, 1300.0);
+    assert_eq!(stats[3].sum_deal_in_score, 8000.0 + 5900.0);
+    assert_eq!(stats[0].yaku_counts["断幺九"], 3);
+    assert_eq!(stats[2].rank_counts, [0, 1, 0, 0]);
+}
@@ -33,6 +33,14 @@ fn main() {
             // Replay (牌譜リプレイモード)
             app::ReplayApp::new(args2).run();
         }
+        "S" => {
+            // Server (ロビーサーバモード)
+            app::ServerApp::new(args2).run();
+        }
+        "L" => {
+            // Leaderboard (レーティング表示モード)
+            app::LeaderboardApp::new(args2).run();
+        }
         "G" => {
             // Gui (クライアントモード)
             app::ClientApp::new(args2).run();